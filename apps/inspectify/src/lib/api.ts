@@ -94,11 +94,17 @@ export type GroupConfig = {
 };
 export namespace Calculator {
   export type Input = {
-    expression: string
+    expression: string,
+    assignment: Interpreter.InterpreterMemory
   };
   export type Output = {
     result: string,
-    error: string
+    error: string,
+    error_span: (Calculator.Span | null)
+  };
+  export type Span = {
+    start: number,
+    end: number
   };
 }
 export namespace Compiler {
@@ -110,19 +116,82 @@ export namespace Compiler {
     dot: string
   };
 }
+export namespace ConstantAnalysis {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism,
+    assignment: ConstantAnalysis.ConstantMemory
+  };
+  export type Output = {
+    initial_node: string,
+    final_node: string,
+    nodes: Record<string, ConstantAnalysis.ConstantMemory[]>,
+    dot: string
+  };
+  export type Constant =
+    | { "type": "Value", "value": number }
+    | { "type": "NotConstant" };
+  export type ConstantMemory = {
+    variables: Record<GCL.Variable, ConstantAnalysis.Constant>,
+    arrays: Record<GCL.Array, ConstantAnalysis.Constant[]>,
+    bool_variables: Record<GCL.Variable, boolean>,
+    bool_arrays: Record<GCL.Array, boolean[]>
+  };
+}
+export namespace FineSignAnalysis {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism,
+    assignment: FineSignAnalysis.FineSignMemory
+  };
+  export type Output = {
+    initial_node: string,
+    final_node: string,
+    nodes: Record<string, FineSignAnalysis.FineSignMemory[]>,
+    dot: string
+  };
+  export type FineSign =
+    | "BelowMinusOne"
+    | "MinusOne"
+    | "Zero"
+    | "One"
+    | "AboveOne";
+  export const FINE_SIGN: FineSign[] = ["BelowMinusOne", "MinusOne", "Zero", "One", "AboveOne"];
+  export type FineSignMemory = {
+    variables: Record<GCL.Variable, FineSignAnalysis.FineSign>,
+    arrays: Record<GCL.Array, FineSignAnalysis.FineSign[]>,
+    bool_variables: Record<GCL.Variable, boolean>,
+    bool_arrays: Record<GCL.Array, boolean[]>
+  };
+}
 export namespace GCL {
+  export type DifferenceConstraint = {
+    left: (GCL.Variable | null),
+    right: (GCL.Variable | null),
+    bound: number
+  };
   export type Determinism =
     | "Deterministic"
     | "NonDeterministic";
   export const DETERMINISM: Determinism[] = ["Deterministic", "NonDeterministic"];
+  export type IntegerSemantics =
+    | { "type": "Checked" }
+    | { "type": "Wrapping", bits: number }
+    | { "type": "Unbounded" };
+  export type SourceSpan = {
+    offset: number,
+    length: number
+  };
   export type TargetDef = {
     name: string,
     kind: GCL.TargetKind
   };
   export type TargetKind =
     | "Variable"
-    | "Array";
-  export const TARGET_KIND: TargetKind[] = ["Variable", "Array"];
+    | "Array"
+    | "BoolVariable"
+    | "BoolArray";
+  export const TARGET_KIND: TargetKind[] = ["Variable", "Array", "BoolVariable", "BoolArray"];
   export type Variable = string;
   export type Array = string;
 }
@@ -131,7 +200,8 @@ export namespace Interpreter {
     commands: string,
     determinism: GCL.Determinism,
     assignment: Interpreter.InterpreterMemory,
-    trace_length: number
+    trace_length: number,
+    integers: GCL.IntegerSemantics
   };
   export type Output = {
     initial_node: string,
@@ -142,7 +212,9 @@ export namespace Interpreter {
   };
   export type InterpreterMemory = {
     variables: Record<GCL.Variable, number>,
-    arrays: Record<GCL.Array, number[]>
+    arrays: Record<GCL.Array, number[]>,
+    bool_variables: Record<GCL.Variable, boolean>,
+    bool_arrays: Record<GCL.Array, boolean[]>
   };
   export type TerminationState =
     | "Running"
@@ -151,10 +223,50 @@ export namespace Interpreter {
   export const TERMINATION_STATE: TerminationState[] = ["Running", "Stuck", "Terminated"];
   export type Step = {
     action: string,
+    span: GCL.SourceSpan,
     node: string,
     memory: Interpreter.InterpreterMemory
   };
 }
+export namespace Minimization {
+  export type Input = {
+    automaton: string
+  };
+  export type Output = {
+    dot: string
+  };
+}
+export namespace Optimizer {
+  export type Input = {
+    commands: string
+  };
+  export type Output = {
+    optimized: string
+  };
+}
+export namespace ParityAnalysis {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism,
+    assignment: ParityAnalysis.ParityMemory
+  };
+  export type Output = {
+    initial_node: string,
+    final_node: string,
+    nodes: Record<string, ParityAnalysis.ParityMemory[]>,
+    dot: string
+  };
+  export type Parity =
+    | "Even"
+    | "Odd";
+  export const PARITY: Parity[] = ["Even", "Odd"];
+  export type ParityMemory = {
+    variables: Record<GCL.Variable, ParityAnalysis.Parity>,
+    arrays: Record<GCL.Array, ParityAnalysis.Parity[]>,
+    bool_variables: Record<GCL.Variable, boolean>,
+    bool_arrays: Record<GCL.Array, boolean[]>
+  };
+}
 export namespace Parser {
   export type Input = {
     commands: string
@@ -173,11 +285,14 @@ export namespace SecurityAnalysis {
     actual: SecurityAnalysis.Flow[],
     allowed: SecurityAnalysis.Flow[],
     violations: SecurityAnalysis.Flow[],
+    declassified: SecurityAnalysis.Flow[],
     is_secure: boolean
   };
   export type Meta = {
     lattice: SecurityAnalysis.SecurityLattice,
-    targets: GCL.TargetDef[]
+    hasse: string,
+    targets: GCL.TargetDef[],
+    elements: string[]
   };
   export type SecurityLatticeInput = {
     rules: SecurityAnalysis.Flow[]
@@ -204,7 +319,9 @@ export namespace SignAnalysis {
   };
   export type SignMemory = {
     variables: Record<GCL.Variable, SignAnalysis.Sign>,
-    arrays: Record<GCL.Array, SignAnalysis.Sign[]>
+    arrays: Record<GCL.Array, SignAnalysis.Sign[]>,
+    bool_variables: Record<GCL.Variable, boolean>,
+    bool_arrays: Record<GCL.Array, boolean[]>
   };
   export type Sign =
     | "Positive"
@@ -212,6 +329,71 @@ export namespace SignAnalysis {
     | "Negative";
   export const SIGN: Sign[] = ["Positive", "Zero", "Negative"];
 }
+export namespace Slicing {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism,
+    node: string,
+    variables: string[]
+  };
+  export type Output = {
+    edges: Slicing.SlicedEdge[],
+    dot: string
+  };
+  export type Meta = {
+    nodes: string[],
+    variables: string[]
+  };
+  export type SlicedEdge = {
+    from: string,
+    action: string,
+    to: string
+  };
+}
+export namespace TaintTracking {
+  export type Input = {
+    commands: string,
+    classification: Record<string, string>,
+    lattice: SecurityAnalysis.SecurityLatticeInput,
+    assignment: Interpreter.InterpreterMemory,
+    trace_length: number
+  };
+  export type Output = {
+    observed: SecurityAnalysis.Flow[],
+    violations: SecurityAnalysis.Flow[],
+    is_secure: boolean
+  };
+}
+export namespace WorklistAnalysis {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism,
+    assignment: SignAnalysis.SignMemory
+  };
+  export type Output = {
+    iterations: Record<WorklistAnalysis.Strategy, Record<string, number>>,
+    dot: string
+  };
+  export type Strategy =
+    | "Fifo"
+    | "Lifo"
+    | "ReversePostOrder"
+    | "LoopAware";
+  export const STRATEGY: Strategy[] = ["Fifo", "Lifo", "ReversePostOrder", "LoopAware"];
+}
+export namespace ZoneAnalysis {
+  export type Input = {
+    commands: string,
+    determinism: GCL.Determinism
+  };
+  export type Output = {
+    initial_node: string,
+    final_node: string,
+    variables: GCL.Variable[],
+    nodes: Record<string, (GCL.DifferenceConstraint[] | null)>,
+    dot: string
+  };
+}
 export namespace ce_automata {
   export type Input = {
     regex: string
@@ -221,6 +403,14 @@ export namespace ce_automata {
   };
 }
 export namespace ce_core {
+  export type InputDiagnostic = {
+    message: string,
+    help: (string | null),
+    start_line: number,
+    start_column: number,
+    end_line: number,
+    end_column: number
+  };
   export type ValidationResult =
     | { "type": "Correct" }
     | { "type": "Mismatch", reason: string }
@@ -237,23 +427,41 @@ export namespace ce_hello_world {
 export namespace ce_shell {
   export type Envs =
     | { "analysis": "Automata", "io": { input: ce_automata.Input, output: ce_automata.Output, meta: void } }
-    | { "analysis": "Calculator", "io": { input: Calculator.Input, output: Calculator.Output, meta: void } }
+    | { "analysis": "Calculator", "io": { input: Calculator.Input, output: Calculator.Output, meta: GCL.TargetDef[] } }
     | { "analysis": "Compiler", "io": { input: Compiler.Input, output: Compiler.Output, meta: void } }
+    | { "analysis": "Constant", "io": { input: ConstantAnalysis.Input, output: ConstantAnalysis.Output, meta: GCL.TargetDef[] } }
+    | { "analysis": "FineSign", "io": { input: FineSignAnalysis.Input, output: FineSignAnalysis.Output, meta: GCL.TargetDef[] } }
     | { "analysis": "HelloWorld", "io": { input: ce_hello_world.Input, output: ce_hello_world.Output, meta: void } }
     | { "analysis": "Interpreter", "io": { input: Interpreter.Input, output: Interpreter.Output, meta: GCL.TargetDef[] } }
+    | { "analysis": "Minimization", "io": { input: Minimization.Input, output: Minimization.Output, meta: void } }
+    | { "analysis": "Optimizer", "io": { input: Optimizer.Input, output: Optimizer.Output, meta: void } }
+    | { "analysis": "Parity", "io": { input: ParityAnalysis.Input, output: ParityAnalysis.Output, meta: GCL.TargetDef[] } }
     | { "analysis": "Parser", "io": { input: Parser.Input, output: Parser.Output, meta: void } }
     | { "analysis": "Security", "io": { input: SecurityAnalysis.Input, output: SecurityAnalysis.Output, meta: SecurityAnalysis.Meta } }
-    | { "analysis": "Sign", "io": { input: SignAnalysis.Input, output: SignAnalysis.Output, meta: GCL.TargetDef[] } };
+    | { "analysis": "Sign", "io": { input: SignAnalysis.Input, output: SignAnalysis.Output, meta: GCL.TargetDef[] } }
+    | { "analysis": "Slicing", "io": { input: Slicing.Input, output: Slicing.Output, meta: Slicing.Meta } }
+    | { "analysis": "Taint", "io": { input: TaintTracking.Input, output: TaintTracking.Output, meta: SecurityAnalysis.Meta } }
+    | { "analysis": "Worklist", "io": { input: WorklistAnalysis.Input, output: WorklistAnalysis.Output, meta: GCL.TargetDef[] } }
+    | { "analysis": "Zone", "io": { input: ZoneAnalysis.Input, output: ZoneAnalysis.Output, meta: GCL.TargetDef[] } };
   export type Analysis =
     | "Automata"
     | "Calculator"
     | "Compiler"
+    | "Constant"
+    | "FineSign"
     | "HelloWorld"
     | "Interpreter"
+    | "Minimization"
+    | "Optimizer"
+    | "Parity"
     | "Parser"
     | "Security"
-    | "Sign";
-  export const ANALYSIS: Analysis[] = ["Automata", "Calculator", "Compiler", "HelloWorld", "Interpreter", "Parser", "Security", "Sign"];
+    | "Sign"
+    | "Slicing"
+    | "Taint"
+    | "Worklist"
+    | "Zone";
+  export const ANALYSIS: Analysis[] = ["Automata", "Calculator", "Compiler", "Constant", "FineSign", "HelloWorld", "Interpreter", "Minimization", "Optimizer", "Parity", "Parser", "Security", "Sign", "Slicing", "Taint", "Worklist", "Zone"];
   export namespace io {
     export type Input = {
       analysis: ce_shell.Analysis,
@@ -338,7 +546,8 @@ export namespace inspectify {
       };
       export type PublicAnalysis = {
         analysis: ce_shell.Analysis,
-        programs: (ce_shell.io.Input | null)[]
+        programs: ce_shell.io.Input[],
+        hidden_programs: number
       };
       export type PublicGroup = {
         name: inspectify.checko.config.GroupName,
@@ -348,7 +557,13 @@ export namespace inspectify {
         analysis: ce_shell.Analysis,
         status: inspectify.checko.GroupStatus,
         last_hash: (string | null),
-        results: inspectify.checko.scoreboard.PublicProgramResult[]
+        results: inspectify.checko.scoreboard.PublicProgramResult[],
+        hidden_results: inspectify.checko.scoreboard.PublicHiddenResults
+      };
+      export type PublicHiddenResults = {
+        passed: number,
+        failed: number,
+        pending: number
       };
       export type PublicProgramResult = {
         state: driver.job.JobState
@@ -359,7 +574,8 @@ export namespace inspectify {
     export type ReferenceExecution = {
       meta: ce_shell.io.Meta,
       output: (ce_shell.io.Output | null),
-      error: (string | null)
+      error: (string | null),
+      diagnostics: ce_core.InputDiagnostic[]
     };
     export type Event =
       | { "type": "Reset" }
@@ -395,7 +611,8 @@ export namespace inspectify {
     export type Program = {
       hash: ce_shell.io.Hash,
       hash_str: string,
-      input: ce_shell.io.Input
+      input: ce_shell.io.Input,
+      shown: boolean
     };
     export type Span = {
       text: string,
@@ -408,6 +625,30 @@ export namespace inspectify {
       reference_output: (ce_shell.io.Output | null),
       validation: (ce_core.ValidationResult | null)
     };
+    export type AdminTokenParams = {
+      token: string
+    };
+    export type AdminRerunParams = {
+      token: string,
+      group: inspectify.checko.config.GroupName,
+      analysis: ce_shell.Analysis
+    };
+    export type AdminPauseParams = {
+      token: string,
+      paused: boolean
+    };
+    export type AdminExcludeParams = {
+      token: string,
+      group: inspectify.checko.config.GroupName,
+      excluded: boolean
+    };
+    export type AdminResponse =
+      | { "type": "Ok", "value": inspectify.endpoints.AdminStatus }
+      | { "type": "Error", "value": { message: string } };
+    export type AdminStatus = {
+      paused: boolean,
+      excluded: inspectify.checko.config.GroupName[]
+    };
   }
 }
 export const api = {
@@ -415,6 +656,12 @@ export const api = {
     events: sse<[], inspectify.endpoints.Event>(() => `/events`, "json"),
     checkoCsv: request<Record<string, never>, string>("none", "GET", "/checko-csv", "text"),
     checkoPublic: sse<[], inspectify.endpoints.PublicEvent>(() => `/checko-public`, "json"),
+    checkoAdminStatus: request<inspectify.endpoints.AdminTokenParams, inspectify.endpoints.AdminResponse>("json", "POST", "/checko-admin/status", "json"),
+    checkoAdminRerun: request<inspectify.endpoints.AdminRerunParams, inspectify.endpoints.AdminResponse>("json", "POST", "/checko-admin/rerun", "json"),
+    checkoAdminPause: request<inspectify.endpoints.AdminPauseParams, inspectify.endpoints.AdminResponse>("json", "POST", "/checko-admin/pause", "json"),
+    checkoAdminExclude: request<inspectify.endpoints.AdminExcludeParams, inspectify.endpoints.AdminResponse>("json", "POST", "/checko-admin/exclude", "json"),
+    checkoAdminExport: request<inspectify.endpoints.AdminTokenParams, string>("json", "POST", "/checko-admin/export", "json"),
+    checkoAdminPrograms: request<inspectify.endpoints.AdminTokenParams, inspectify.endpoints.Program[]>("json", "POST", "/checko-admin/programs", "json"),
     jobsCancel: request<driver.job.JobId, void>("json", "POST", "/jobs/cancel", "none"),
     analysis: request<ce_shell.io.Input, (inspectify.endpoints.AnalysisExecution | null)>("json", "POST", "/analysis", "json"),
     reference: request<ce_shell.io.Input, inspectify.endpoints.ReferenceExecution>("json", "POST", "/reference", "json"),
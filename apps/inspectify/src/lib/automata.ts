@@ -0,0 +1,58 @@
+import type { ce_core } from './api';
+
+/** The distinguishing word in the mismatch reason of the automata envs, if any. */
+export const counterexample = (
+  validation: ce_core.ValidationResult | { type: 'Failure'; message: string } | null,
+): string | null => {
+  if (validation?.type != 'Mismatch') return null;
+  const match = validation.reason.match(/(?:accepts|rejects) (?:ε|"([^"]*)")/);
+  return match ? (match[1] ?? '') : null;
+};
+
+type Node = { id: string; initial?: boolean; accepting?: boolean };
+type Edge = { from: string; to: string; label?: string };
+
+/**
+ * The nodes and edges visited when reading the word, following every
+ * transition an automaton could take. The initial node is the one marked
+ * `initial`, or otherwise the first node without ingoing edges.
+ */
+export const wordPath = (
+  nodes: Node[],
+  edges: Edge[],
+  word: string,
+): { nodes: Set<string>; edges: Set<Edge> } => {
+  const visitedNodes = new Set<string>();
+  const visitedEdges = new Set<Edge>();
+
+  const initial =
+    nodes.find((n) => n.initial) ?? nodes.find((n) => !edges.some((e) => e.to == n.id));
+  if (!initial) return { nodes: visitedNodes, edges: visitedEdges };
+
+  const step = (states: Set<string>, symbol: string) => {
+    const next = new Set<string>();
+    for (const e of edges) {
+      if (states.has(e.from) && e.label == symbol) {
+        visitedEdges.add(e);
+        next.add(e.to);
+      }
+    }
+    return next;
+  };
+  const closure = (states: Set<string>) => {
+    let frontier = states;
+    while (frontier.size > 0) {
+      frontier = new Set([...step(frontier, 'ε')].filter((s) => !states.has(s)));
+      frontier.forEach((s) => states.add(s));
+    }
+    return states;
+  };
+
+  let states = closure(new Set([initial.id]));
+  states.forEach((s) => visitedNodes.add(s));
+  for (const symbol of word) {
+    states = closure(step(states, symbol));
+    states.forEach((s) => visitedNodes.add(s));
+  }
+  return { nodes: visitedNodes, edges: visitedEdges };
+};
@@ -33,6 +33,7 @@ export class Io<A extends ce_shell.Analysis> {
   analysis: A;
   input: Input<A> = $state(null as any);
   meta: Meta<A> | null = $state(null);
+  diagnostics: ce_core.InputDiagnostic[] = $state([]);
   reference: Results<A> = $state(defaultResults());
 
   currentJob: { jobId: number; input: Input<A> } | null = $state(null);
@@ -136,8 +137,9 @@ export class Io<A extends ce_shell.Analysis> {
         hash: { bytes: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] },
       });
 
-      analysisRequest.data.then(({ output, error, meta }) => {
+      analysisRequest.data.then(({ output, error, meta, diagnostics }) => {
         this.meta = meta.json;
+        this.diagnostics = diagnostics;
         this.reference = {
           input: this.input,
           outputState: 'Current',
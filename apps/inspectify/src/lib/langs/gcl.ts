@@ -49,7 +49,7 @@ monaco.languages.setMonarchTokensProvider(GCL_LANGUAGE_ID, {
     { token: 'delimiter.angle', open: '<', close: '>' },
   ],
 
-  keywords: ['if', 'fi', 'do', 'od'],
+  keywords: ['if', 'fi', 'do', 'od', 'proc', 'end', 'call', 'par', 'rap', 'declassify'],
   operators: [
     '-',
     ',',
@@ -1,6 +1,6 @@
 mod gcl_gen;
 
-pub use gcl_gen::Context as GclGenContext;
+pub use gcl_gen::{Context as GclGenContext, interpreter_memory};
 use rand::Rng;
 
 pub trait Generate: 'static {
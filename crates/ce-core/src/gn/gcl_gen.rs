@@ -1,5 +1,11 @@
-use gcl::ast::{
-    AExpr, AOp, Array, BExpr, Command, Commands, Guard, LogicOp, RelOp, Target, Variable,
+use gcl::{
+    ast::{
+        AExpr, AOp, Array, BExpr, BExprKind, Command, CommandKind, Commands, Guard, LogicOp, RelOp,
+        Target, Variable,
+    },
+    integer::Integer,
+    interpreter::InterpreterMemory,
+    typeck::Type,
 };
 use rand::{Rng, seq::IndexedRandom};
 
@@ -13,6 +19,9 @@ pub struct Context {
     pub no_division: bool,
     pub no_unary_minus: bool,
     pub names: Vec<String>,
+    /// The names of boolean variables, and in upper case boolean arrays. None
+    /// are generated if empty, which is the default.
+    pub bool_names: Vec<String>,
 }
 
 type GenerationOptions<R, Ctx, G> = Vec<(f32, Box<dyn Fn(&mut Ctx, &mut R) -> G>)>;
@@ -27,6 +36,7 @@ impl Default for Context {
             no_division: Default::default(),
             no_unary_minus: Default::default(),
             names: ["a", "b", "c", "d"].map(Into::into).to_vec(),
+            bool_names: Vec::new(),
         }
     }
 }
@@ -41,6 +51,7 @@ impl Context {
             no_division: false,
             no_unary_minus: false,
             names: ["a", "b", "c", "d"].map(Into::into).to_vec(),
+            bool_names: Vec::new(),
         }
     }
 
@@ -56,11 +67,24 @@ impl Context {
         self.no_unary_minus = no_unary_minus;
         self
     }
+    pub fn set_bool_names(&mut self, bool_names: &[&str]) -> &mut Self {
+        self.bool_names = bool_names.iter().map(|&name| name.into()).collect();
+        self
+    }
 
     fn use_array(&self) -> bool {
         false
     }
 
+    fn bool_reference<R: Rng>(&mut self, rng: &mut R) -> Target<Box<AExpr>> {
+        let name = self.bool_names.choose(rng).cloned().unwrap();
+        if self.use_array() && rng.random_bool(0.3) {
+            Target::Array(Array(name.to_uppercase()), Box::new(AExpr::gn(self, rng)))
+        } else {
+            Target::Variable(Variable(name))
+        }
+    }
+
     fn reference<R: Rng>(&mut self, rng: &mut R) -> Target<Box<AExpr>> {
         self.sample(
             rng,
@@ -110,6 +134,36 @@ impl Context {
     }
 }
 
+/// Generates an initial memory for the variables and arrays of `commands`.
+pub fn interpreter_memory<R: Rng>(commands: &Commands, rng: &mut R) -> InterpreterMemory {
+    let mut memory = InterpreterMemory::default();
+    for (target, ty) in commands.types() {
+        match (target, ty) {
+            (Target::Variable(var), Type::Int) => {
+                memory
+                    .variables
+                    .insert(var, Integer::from(rng.random_range(-10..=10)));
+            }
+            (Target::Array(arr, ()), Type::Int) => {
+                let len = rng.random_range(5..=10);
+                let data = (0..len)
+                    .map(|_| Integer::from(rng.random_range(-10..=10)))
+                    .collect();
+                memory.arrays.insert(arr, data);
+            }
+            (Target::Variable(var), Type::Bool) => {
+                memory.bool_variables.insert(var, rng.random());
+            }
+            (Target::Array(arr, ()), Type::Bool) => {
+                let len = rng.random_range(5..=10);
+                let data = (0..len).map(|_| rng.random()).collect();
+                memory.bool_arrays.insert(arr, data);
+            }
+        }
+    }
+    memory
+}
+
 impl Generate for Commands {
     type Context = Context;
 
@@ -172,13 +226,32 @@ impl Generate for Command {
                 (
                     1.0,
                     Box::new(|cx, rng| {
-                        Command::Assignment(Target::gn(cx, rng), AExpr::gn(cx, rng))
+                        CommandKind::Assignment(Target::gn(cx, rng), AExpr::gn(cx, rng)).into()
                     }),
                 ),
-                (0.6, Box::new(|cx, rng| Command::If(cx.many(1, 10, rng)))),
+                (
+                    if cx.bool_names.is_empty() { 0.0 } else { 0.4 },
+                    Box::new(|cx, rng| {
+                        let target = cx.bool_reference(rng);
+                        // NOTE: a bare reference would be inferred to be an
+                        // integer if nothing else constrains it
+                        let expr = match BExpr::gn(cx, rng) {
+                            b @ BExpr {
+                                kind: BExprKind::Reference(_),
+                                ..
+                            } => !b,
+                            b => b,
+                        };
+                        CommandKind::BoolAssignment(target, expr).into()
+                    }),
+                ),
+                (
+                    0.6,
+                    Box::new(|cx, rng| CommandKind::If(cx.many(1, 10, rng)).into()),
+                ),
                 (
                     if cx.no_loops { 0.0 } else { 0.3 },
-                    Box::new(|cx, rng| Command::Loop(cx.many(1, 10, rng))),
+                    Box::new(|cx, rng| CommandKind::Loop(cx.many(1, 10, rng)).into()),
                 ),
             ],
         )
@@ -199,7 +272,11 @@ impl Generate for Guard {
     fn gn<R: Rng>(cx: &mut Self::Context, rng: &mut R) -> Self {
         cx.recursion_limit = 5;
         cx.negation_limit = 3;
-        Guard(BExpr::gn(cx, rng), Commands::gn(cx, rng))
+        Guard {
+            guard_span: Default::default(),
+            guard: BExpr::gn(cx, rng),
+            cmds: Commands::gn(cx, rng),
+        }
     }
 }
 
@@ -211,11 +288,11 @@ impl Generate for AExpr {
             vec![
                 (
                     0.4,
-                    Box::new(|_, rng| AExpr::Number(rng.random_range(-100..=100))),
+                    Box::new(|_, rng| AExpr::number(rng.random_range(-100..=100))),
                 ),
                 (
                     if cx.names.is_empty() { 0.0 } else { 0.8 },
-                    Box::new(|cx, rng| AExpr::Reference(cx.reference(rng))),
+                    Box::new(|cx, rng| AExpr::reference(cx.reference(rng))),
                 ),
                 (
                     if cx.recursion_limit == 0 || cx.fuel == 0 {
@@ -260,12 +337,16 @@ impl Generate for BExpr {
         cx.sample(
             rng,
             vec![
-                (0.2, Box::new(|_cx, rng| BExpr::Bool(rng.random()))),
+                (0.2, Box::new(|_cx, rng| BExpr::bool(rng.random()))),
+                (
+                    if cx.bool_names.is_empty() { 0.0 } else { 0.3 },
+                    Box::new(|cx, rng| BExpr::reference(cx.bool_reference(rng))),
+                ),
                 (
                     if cx.recursion_limit == 0 { 0.0 } else { 0.7 },
                     Box::new(|cx, rng| {
                         cx.recursion_limit = cx.recursion_limit.checked_sub(1).unwrap_or_default();
-                        BExpr::Rel(AExpr::gn(cx, rng), RelOp::gn(cx, rng), AExpr::gn(cx, rng))
+                        BExpr::rel(AExpr::gn(cx, rng), RelOp::gn(cx, rng), AExpr::gn(cx, rng))
                     }),
                 ),
                 (
@@ -279,7 +360,7 @@ impl Generate for BExpr {
                     if cx.negation_limit == 0 { 0.0 } else { 0.4 },
                     Box::new(|cx, rng| {
                         cx.negation_limit = cx.negation_limit.checked_sub(1).unwrap_or_default();
-                        BExpr::Not(Box::new(BExpr::gn(cx, rng)))
+                        !BExpr::gn(cx, rng)
                     }),
                 ),
             ],
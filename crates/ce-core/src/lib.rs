@@ -1,4 +1,6 @@
 pub mod gn;
+#[cfg(test)]
+mod tests;
 
 use std::sync::Arc;
 
@@ -52,6 +54,48 @@ impl EnvError {
             source: Some(Arc::new(source)),
         }
     }
+    /// The individual syntax errors of the program, if that is why the input
+    /// is invalid.
+    pub fn diagnostics(&self) -> Vec<InputDiagnostic> {
+        let EnvError::InvalidInputForProgram {
+            source: Some(source),
+            ..
+        } = self
+        else {
+            return Vec::new();
+        };
+        let Some(errors) = source.downcast_ref::<gcl::parse::ParseErrors>() else {
+            return Vec::new();
+        };
+        errors
+            .errors()
+            .iter()
+            .map(|e| {
+                let span = e.span();
+                let (start_line, start_column) = gcl::parse::line_column(e.src(), span.offset());
+                let (end_line, end_column) = gcl::parse::line_column(e.src(), span.end());
+                InputDiagnostic {
+                    message: e.to_string(),
+                    help: miette::Diagnostic::help(e).map(|h| h.to_string()),
+                    start_line,
+                    start_column,
+                    end_line,
+                    end_column,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A located problem in the program of an input, such as a syntax error.
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InputDiagnostic {
+    pub message: String,
+    pub help: Option<String>,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
 }
 
 pub type Result<T, E = EnvError> = std::result::Result<T, E>;
@@ -0,0 +1,29 @@
+use gcl::parse::parse_commands;
+
+use crate::{EnvError, InputDiagnostic};
+
+#[test]
+fn diagnostics_locate_every_syntax_error() {
+    let errors = parse_commands("/* 😀 */ x := ;\nif -> skip fi").unwrap_err();
+    let described: Vec<_> = errors.errors().iter().map(|e| e.describe()).collect();
+    let err = EnvError::invalid_input_for_program("failed to parse commands")(errors);
+    let diagnostics = err.diagnostics();
+    let locations: Vec<_> = diagnostics
+        .iter()
+        .map(|d: &InputDiagnostic| ((d.start_line, d.start_column), (d.end_line, d.end_column)))
+        .collect();
+    assert_eq!(locations, [((1, 15), (1, 16)), ((2, 4), (2, 6))]);
+    for (d, described) in diagnostics.iter().zip(described) {
+        let prefix = format!("{}:{}: {}", d.start_line, d.start_column, d.message);
+        assert!(described.starts_with(&prefix), "{described} vs {prefix}");
+    }
+}
+
+#[test]
+fn other_errors_have_no_diagnostics() {
+    let err = EnvError::InvalidInputForProgram {
+        message: "no program".to_string(),
+        source: None,
+    };
+    assert!(err.diagnostics().is_empty());
+}
@@ -17,11 +17,20 @@ define_shell!(
     ce_automata::AutomataEnv[Automata, "Automata"],
     ce_calculator::CalcEnv[Calculator, "Calculator"],
     ce_compiler::CompilerEnv[Compiler, "Compiler"],
+    ce_constant::ConstantEnv[Constant, "Constant Propagation"],
+    ce_fine_sign::FineSignEnv[FineSign, "Fine Sign Analysis"],
     ce_hello_world::HelloWorldEnv[HelloWorld, "Hello World"],
     ce_interpreter::InterpreterEnv[Interpreter, "Interpreter"],
+    ce_minimization::MinimizationEnv[Minimization, "DFA Minimization"],
+    ce_optimizer::OptimizerEnv[Optimizer, "Optimizer"],
+    ce_parity::ParityEnv[Parity, "Parity Analysis"],
     ce_parser::ParserEnv[Parser, "Parser"],
     ce_security::SecurityEnv[Security, "Security"],
     ce_sign::SignEnv[Sign, "Sign Analysis"],
+    ce_slicing::SlicingEnv[Slicing, "Slicing"],
+    ce_taint::TaintEnv[Taint, "Taint Tracking"],
+    ce_worklist::WorklistEnv[Worklist, "Worklist Algorithm"],
+    ce_zone::ZoneEnv[Zone, "Zone Analysis"],
 );
 
 impl Analysis {
@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque, hash_map::Entry};
+
+use petgraph::Direction;
+
+use crate::{dot, regex::Regex};
+
+/// A nondeterministic finite automaton, where transitions without a symbol
+/// are ε-transitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nfa {
+    transitions: Vec<Vec<(Option<char>, usize)>>,
+    initial: usize,
+    accepting: BTreeSet<usize>,
+}
+
+/// A deterministic finite automaton with initial state `0`, where missing
+/// transitions reject the word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dfa {
+    transitions: Vec<BTreeMap<char, usize>>,
+    accepting: BTreeSet<usize>,
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.transitions.push(Vec::new());
+        self.transitions.len() - 1
+    }
+
+    fn add_transition(&mut self, from: usize, symbol: Option<char>, to: usize) {
+        self.transitions[from].push((symbol, to));
+    }
+
+    /// Builds the automaton of the regex using Thompson's construction.
+    pub fn from_regex(regex: &Regex) -> Nfa {
+        let mut nfa = Nfa {
+            transitions: Vec::new(),
+            initial: 0,
+            accepting: BTreeSet::new(),
+        };
+        let (initial, accepting) = nfa.thompson(regex);
+        nfa.initial = initial;
+        nfa.accepting.insert(accepting);
+        nfa
+    }
+
+    /// Adds the states of `regex` and returns its entry and exit.
+    fn thompson(&mut self, regex: &Regex) -> (usize, usize) {
+        match regex {
+            Regex::Epsilon => {
+                let (s, e) = (self.add_state(), self.add_state());
+                self.add_transition(s, None, e);
+                (s, e)
+            }
+            Regex::Symbols(symbols) => {
+                let (s, e) = (self.add_state(), self.add_state());
+                for c in symbols {
+                    self.add_transition(s, Some(*c), e);
+                }
+                (s, e)
+            }
+            Regex::Concat(l, r) => {
+                let (ls, le) = self.thompson(l);
+                let (rs, re) = self.thompson(r);
+                self.add_transition(le, None, rs);
+                (ls, re)
+            }
+            Regex::Union(l, r) => {
+                let s = self.add_state();
+                let (ls, le) = self.thompson(l);
+                let (rs, re) = self.thompson(r);
+                let e = self.add_state();
+                self.add_transition(s, None, ls);
+                self.add_transition(s, None, rs);
+                self.add_transition(le, None, e);
+                self.add_transition(re, None, e);
+                (s, e)
+            }
+            Regex::Star(r) | Regex::Plus(r) | Regex::Optional(r) => {
+                let s = self.add_state();
+                let (rs, re) = self.thompson(r);
+                let e = self.add_state();
+                self.add_transition(s, None, rs);
+                self.add_transition(re, None, e);
+                if !matches!(regex, Regex::Plus(_)) {
+                    self.add_transition(s, None, e);
+                }
+                if !matches!(regex, Regex::Optional(_)) {
+                    self.add_transition(re, None, rs);
+                }
+                (s, e)
+            }
+        }
+    }
+
+    /// Reads an automaton from a dot graph.
+    ///
+    /// The initial state is the node marked `initial=true`, or otherwise the
+    /// first node without ingoing edges. The accepting states are the nodes
+    /// marked `accepting=true`, or if no node has an `accepting` attribute,
+    /// the nodes without outgoing edges. Edges are labeled by a symbol or by `ε`. A graph
+    /// without nodes accepts only the empty word.
+    pub fn from_dot(src: &str) -> Result<Nfa, String> {
+        let parsed = dot::dot_to_petgraph(src)?;
+        let graph = &parsed.graph;
+        let attribute = |name: &str, key: &str| {
+            parsed.nodes.get(name).and_then(|n| {
+                n.attributes
+                    .iter()
+                    .rfind(|a| a.0.to_string() == key)
+                    .map(|a| a.1.to_string().trim_matches('"') == "true")
+            })
+        };
+        let marked = |name: &str, key: &str| attribute(name, key) == Some(true);
+
+        let mut nfa = Nfa {
+            transitions: vec![Vec::new(); graph.node_count()],
+            initial: 0,
+            accepting: BTreeSet::new(),
+        };
+        if graph.node_count() == 0 {
+            nfa.add_state();
+            nfa.accepting.insert(0);
+            return Ok(nfa);
+        }
+
+        let initial: Vec<_> = graph
+            .node_indices()
+            .filter(|n| marked(&graph[*n], "initial"))
+            .collect();
+        nfa.initial = match initial.as_slice() {
+            [n] => n.index(),
+            [] => graph
+                .node_indices()
+                .find(|n| {
+                    graph
+                        .neighbors_directed(*n, Direction::Incoming)
+                        .next()
+                        .is_none()
+                })
+                .ok_or("no initial state was marked, and every state has ingoing edges")?
+                .index(),
+            _ => return Err("more than one state is marked as initial".to_string()),
+        };
+
+        nfa.accepting = graph
+            .node_indices()
+            .filter(|n| marked(&graph[*n], "accepting"))
+            .map(|n| n.index())
+            .collect();
+        if graph
+            .node_indices()
+            .all(|n| attribute(&graph[n], "accepting").is_none())
+        {
+            nfa.accepting = graph
+                .node_indices()
+                .filter(|n| {
+                    graph
+                        .neighbors_directed(*n, Direction::Outgoing)
+                        .next()
+                        .is_none()
+                })
+                .map(|n| n.index())
+                .collect();
+        }
+
+        for e in graph.edge_indices() {
+            let (from, to) = graph.edge_endpoints(e).unwrap();
+            let symbol = match graph[e].chars().collect::<Vec<_>>().as_slice() {
+                ['ε'] => None,
+                [c] if c.is_ascii() && crate::ALPHABET.contains(&(*c as u8)) => Some(*c),
+                _ => return Err(format!("'{}' is not a symbol of the alphabet", graph[e])),
+            };
+            nfa.add_transition(from.index(), symbol, to.index());
+        }
+
+        Ok(nfa)
+    }
+
+    /// The number of states.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Whether the automaton has no ε-transitions and at most one transition
+    /// for each symbol from each state.
+    pub fn is_deterministic(&self) -> bool {
+        self.transitions.iter().all(|ts| {
+            let mut symbols = HashSet::new();
+            ts.iter().all(|(c, _)| c.is_some_and(|c| symbols.insert(c)))
+        })
+    }
+
+    /// The number of states from which no word is accepted.
+    pub fn dead_states(&self) -> usize {
+        let edges = self
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(s, ts)| ts.iter().map(move |(_, t)| (s, *t)));
+        self.len() - coaccessible(self.len(), edges, &self.accepting).len()
+    }
+
+    /// The states reachable from `states` by ε-transitions.
+    fn closure(&self, mut states: BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut stack: Vec<_> = states.iter().copied().collect();
+        while let Some(s) = stack.pop() {
+            for (symbol, t) in &self.transitions[s] {
+                if symbol.is_none() && states.insert(*t) {
+                    stack.push(*t);
+                }
+            }
+        }
+        states
+    }
+
+    /// Builds an equivalent deterministic automaton using the subset
+    /// construction, numbering the states in breadth-first order.
+    pub fn determinize(&self) -> Dfa {
+        let initial = self.closure(BTreeSet::from([self.initial]));
+        let mut ids = BTreeMap::from([(initial.clone(), 0)]);
+        let mut queue = VecDeque::from([initial]);
+        let mut dfa = Dfa {
+            transitions: Vec::new(),
+            accepting: BTreeSet::new(),
+        };
+
+        while let Some(states) = queue.pop_front() {
+            let id = dfa.transitions.len();
+            if states.iter().any(|s| self.accepting.contains(s)) {
+                dfa.accepting.insert(id);
+            }
+            let mut targets = BTreeMap::<char, BTreeSet<usize>>::new();
+            for s in &states {
+                for (symbol, t) in &self.transitions[*s] {
+                    if let Some(c) = symbol {
+                        targets.entry(*c).or_default().insert(*t);
+                    }
+                }
+            }
+            let transitions = targets
+                .into_iter()
+                .map(|(c, targets)| {
+                    let targets = self.closure(targets);
+                    let next = ids.len();
+                    let target = *ids.entry(targets.clone()).or_insert_with(|| {
+                        queue.push_back(targets);
+                        next
+                    });
+                    (c, target)
+                })
+                .collect();
+            dfa.transitions.push(transitions);
+        }
+
+        dfa
+    }
+}
+
+impl Dfa {
+    /// An automaton with initial state `0`, where `transitions[s]` are the
+    /// transitions from state `s`.
+    pub fn new(transitions: Vec<BTreeMap<char, usize>>, accepting: BTreeSet<usize>) -> Dfa {
+        Dfa {
+            transitions,
+            accepting,
+        }
+    }
+
+    /// The number of states.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    pub fn accepts(&self, word: &str) -> bool {
+        let mut state = 0;
+        for c in word.chars() {
+            match self.transitions[state].get(&c) {
+                Some(next) => state = *next,
+                None => return false,
+            }
+        }
+        self.accepting.contains(&state)
+    }
+
+    /// Whether the automata accept the same words.
+    pub fn equivalent(&self, other: &Dfa) -> bool {
+        self.distinguishing_word(other).is_none()
+    }
+
+    /// A shortest word accepted by exactly one of the automata, found by
+    /// exploring the product of the automata breadth-first.
+    pub fn distinguishing_word(&self, other: &Dfa) -> Option<String> {
+        let start = (Some(0), Some(0));
+        let mut parents = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some((s, t)) = queue.pop_front() {
+            let accepting =
+                |dfa: &Dfa, s: Option<usize>| s.is_some_and(|s| dfa.accepting.contains(&s));
+            if accepting(self, s) != accepting(other, t) {
+                let mut word = Vec::new();
+                let mut pair = (s, t);
+                while let Some(Some((parent, c))) = parents.get(&pair) {
+                    word.push(*c);
+                    pair = *parent;
+                }
+                return Some(word.into_iter().rev().collect());
+            }
+            let symbols: BTreeSet<char> = s
+                .into_iter()
+                .flat_map(|s| self.transitions[s].keys())
+                .chain(t.into_iter().flat_map(|t| other.transitions[t].keys()))
+                .copied()
+                .collect();
+            for c in symbols {
+                let next = (
+                    s.and_then(|s| self.transitions[s].get(&c).copied()),
+                    t.and_then(|t| other.transitions[t].get(&c).copied()),
+                );
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some(((s, t), c)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The number of states from which no word is accepted.
+    pub fn dead_states(&self) -> usize {
+        self.len() - coaccessible(self.len(), self.edges(), &self.accepting).len()
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(s, ts)| ts.values().map(move |t| (s, *t)))
+    }
+
+    /// The minimal automaton accepting the same words, computed by
+    /// refining the partition of accepting and rejecting states until
+    /// equivalent states agree on every transition.
+    ///
+    /// States from which no word is accepted are removed, so the result has
+    /// no dead state unless it accepts no words at all.
+    pub fn minimize(&self) -> Dfa {
+        let live = coaccessible(self.len(), self.edges(), &self.accepting);
+        if !live.contains(&0) {
+            return Dfa::new(vec![BTreeMap::new()], BTreeSet::new());
+        }
+
+        let mut class: Vec<usize> = (0..self.len())
+            .map(|s| self.accepting.contains(&s) as usize)
+            .collect();
+        let mut classes = 0;
+        loop {
+            let mut ids = BTreeMap::new();
+            let refined: Vec<usize> = (0..self.len())
+                .map(|s| {
+                    let signature = (
+                        class[s],
+                        self.transitions[s]
+                            .iter()
+                            .filter(|(_, t)| live.contains(t))
+                            .map(|(c, t)| (*c, class[*t]))
+                            .collect::<Vec<_>>(),
+                    );
+                    let next = ids.len();
+                    *ids.entry(signature).or_insert(next)
+                })
+                .collect();
+            class = refined;
+            if ids.len() == classes {
+                break;
+            }
+            classes = ids.len();
+        }
+
+        // NOTE: the states are renumbered in breadth-first order, so equal
+        // languages give equal automata
+        let mut ids = BTreeMap::from([(class[0], 0)]);
+        let mut queue = VecDeque::from([0]);
+        let mut minimal = Dfa::new(Vec::new(), BTreeSet::new());
+        while let Some(s) = queue.pop_front() {
+            let id = minimal.transitions.len();
+            if self.accepting.contains(&s) {
+                minimal.accepting.insert(id);
+            }
+            let transitions = self.transitions[s]
+                .iter()
+                .filter(|(_, t)| live.contains(t))
+                .map(|(c, t)| {
+                    let next = ids.len();
+                    let target = *ids.entry(class[*t]).or_insert_with(|| {
+                        queue.push_back(*t);
+                        next
+                    });
+                    (*c, target)
+                })
+                .collect();
+            minimal.transitions.push(transitions);
+        }
+        minimal
+    }
+
+    pub fn dot(&self) -> String {
+        let mut dot = String::from("digraph Automaton {\n    rankdir=LR;\n");
+        for s in 0..self.len() {
+            let mut attributes = Vec::new();
+            if s == 0 {
+                attributes.push("initial=true");
+            }
+            if self.accepting.contains(&s) {
+                attributes.push("accepting=true");
+            } else if s == 0 && self.accepting.is_empty() {
+                // NOTE: without any accepting attribute, the states without
+                // outgoing edges would be read as accepting
+                attributes.push("accepting=false");
+            }
+            if !attributes.is_empty() {
+                dot.push_str(&format!("    \"{s}\" [{}];\n", attributes.join(", ")));
+            }
+        }
+        for (s, transitions) in self.transitions.iter().enumerate() {
+            for (c, t) in transitions {
+                dot.push_str(&format!("    \"{s}\" -> \"{t}\" [label=\"{c}\"];\n"));
+            }
+        }
+        dot.push('}');
+        dot
+    }
+}
+
+/// The states from which an accepting state can be reached along `edges`.
+fn coaccessible(
+    states: usize,
+    edges: impl Iterator<Item = (usize, usize)>,
+    accepting: &BTreeSet<usize>,
+) -> BTreeSet<usize> {
+    let mut predecessors = vec![Vec::new(); states];
+    for (s, t) in edges {
+        predecessors[t].push(s);
+    }
+    let mut reached = accepting.clone();
+    let mut stack: Vec<_> = accepting.iter().copied().collect();
+    while let Some(t) = stack.pop() {
+        for s in &predecessors[t] {
+            if reached.insert(*s) {
+                stack.push(*s);
+            }
+        }
+    }
+    reached
+}
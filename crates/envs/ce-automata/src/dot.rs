@@ -27,7 +27,7 @@ pub fn dot_to_petgraph(dot: &str) -> Result<ParsedGraph, String> {
     let parsed = graphviz_rust::parse(dot)?;
 
     match parsed {
-        Graph::Graph { .. } => todo!(),
+        Graph::Graph { .. } => return Err("expected a digraph".to_string()),
         Graph::DiGraph { stmts, .. } => {
             for stmt in stmts {
                 match stmt {
@@ -58,14 +58,15 @@ pub fn dot_to_petgraph(dot: &str) -> Result<ParsedGraph, String> {
                                     .attributes
                                     .iter()
                                     .find_map(|a| match (&a.0, &a.1) {
-                                        (Id::Plain(l), Id::Escaped(v)) if l == "label" => {
+                                        (Id::Plain(l), Id::Escaped(v) | Id::Plain(v))
+                                            if l == "label" =>
+                                        {
                                             Some(v.to_string())
                                         }
                                         _ => None,
                                     })
                                     .ok_or("edge label not found")?;
-                                let label = label
-                                    .trim_matches('"');
+                                let label = label.trim_matches('"');
                                 graph.add_edge(a_id, b_id, label.to_string());
 
                                 nodes
@@ -93,4 +94,3 @@ pub fn dot_to_petgraph(dot: &str) -> Result<ParsedGraph, String> {
         graph,
     })
 }
-
@@ -0,0 +1,286 @@
+//! Regular expressions over [`ALPHABET`].
+//!
+//! The syntax, from loosest to tightest binding:
+//!
+//! - `r|s` matches `r` or `s`
+//! - `rs` matches `r` followed by `s`, and the empty expression matches the
+//!   empty word
+//! - `r*`, `r+` and `r?` match `r` any number of times, at least once, and at
+//!   most once
+//! - `a`, `.`, `[a-cx]` and `[^a-c]` match a symbol, any symbol, a symbol of
+//!   the class, and a symbol not in the class
+//! - `(r)` matches `r`
+//!
+//! Letters match regardless of their case.
+
+use std::{collections::BTreeSet, fmt, iter::Peekable, str::CharIndices, str::FromStr};
+
+use ce_core::{Generate, rand::seq::IndexedRandom};
+
+/// The symbols of words.
+pub const ALPHABET: &[u8; 62] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Regex {
+    /// Matches the empty word.
+    Epsilon,
+    /// Matches any one of the symbols.
+    Symbols(BTreeSet<char>),
+    Concat(Box<Regex>, Box<Regex>),
+    Union(Box<Regex>, Box<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RegexError {
+    #[error("'{symbol}' at position {position} is not a symbol of the alphabet")]
+    UnknownSymbol { symbol: char, position: usize },
+    #[error("unexpected '{symbol}' at position {position}")]
+    Unexpected { symbol: char, position: usize },
+    #[error("'{open}' at position {position} is never closed")]
+    Unclosed { open: char, position: usize },
+    #[error("the range {from}-{to} at position {position} is empty")]
+    EmptyRange {
+        from: char,
+        to: char,
+        position: usize,
+    },
+}
+
+fn is_symbol(c: char) -> bool {
+    c.is_ascii() && ALPHABET.contains(&(c as u8))
+}
+
+/// The symbol along with its other case, if it is a letter.
+fn cases(c: char) -> impl Iterator<Item = char> {
+    [c.to_ascii_lowercase(), c.to_ascii_uppercase()].into_iter()
+}
+
+impl FromStr for Regex {
+    type Err = RegexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.char_indices().peekable(),
+        };
+        let regex = parser.union()?;
+        match parser.chars.next() {
+            None => Ok(regex),
+            Some((position, symbol)) => Err(RegexError::Unexpected { symbol, position }),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn union(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.concat()?;
+        while self.chars.next_if(|(_, c)| *c == '|').is_some() {
+            regex = Regex::Union(Box::new(regex), Box::new(self.concat()?));
+        }
+        Ok(regex)
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex: Option<Regex> = None;
+        while matches!(self.chars.peek(), Some((_, c)) if !matches!(c, '|' | ')')) {
+            let next = self.repeat()?;
+            regex = Some(match regex {
+                Some(regex) => Regex::Concat(Box::new(regex), Box::new(next)),
+                None => next,
+            });
+        }
+        Ok(regex.unwrap_or(Regex::Epsilon))
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| matches!(c, '*' | '+' | '?')) {
+            regex = match c {
+                '*' => Regex::Star(Box::new(regex)),
+                '+' => Regex::Plus(Box::new(regex)),
+                _ => Regex::Optional(Box::new(regex)),
+            };
+        }
+        Ok(regex)
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let (position, c) = self
+            .chars
+            .next()
+            .expect("atoms are only parsed before a symbol");
+        match c {
+            '(' => {
+                let regex = self.union()?;
+                match self.chars.next() {
+                    Some((_, ')')) => Ok(regex),
+                    _ => Err(RegexError::Unclosed {
+                        open: '(',
+                        position,
+                    }),
+                }
+            }
+            '[' => self.class(position),
+            '.' => Ok(Regex::Symbols(
+                ALPHABET.iter().map(|&b| b as char).collect(),
+            )),
+            _ if is_symbol(c) => Ok(Regex::Symbols(cases(c).collect())),
+            '*' | '+' | '?' | ']' => Err(RegexError::Unexpected {
+                symbol: c,
+                position,
+            }),
+            _ => Err(RegexError::UnknownSymbol {
+                symbol: c,
+                position,
+            }),
+        }
+    }
+
+    fn class(&mut self, open: usize) -> Result<Regex, RegexError> {
+        let negated = self.chars.next_if(|(_, c)| *c == '^').is_some();
+        let mut symbols = BTreeSet::new();
+        loop {
+            let (position, from) = match self.chars.next() {
+                None => {
+                    return Err(RegexError::Unclosed {
+                        open: '[',
+                        position: open,
+                    });
+                }
+                Some((_, ']')) if !symbols.is_empty() => break,
+                Some((position, c)) if !is_symbol(c) => {
+                    return Err(RegexError::Unexpected {
+                        symbol: c,
+                        position,
+                    });
+                }
+                Some(next) => next,
+            };
+            let to = if self.chars.next_if(|(_, c)| *c == '-').is_some() {
+                match self.chars.next() {
+                    Some((_, to)) if is_symbol(to) => to,
+                    Some((position, symbol)) => {
+                        return Err(RegexError::Unexpected { symbol, position });
+                    }
+                    None => {
+                        return Err(RegexError::Unclosed {
+                            open: '[',
+                            position: open,
+                        });
+                    }
+                }
+            } else {
+                from
+            };
+            if from > to {
+                return Err(RegexError::EmptyRange { from, to, position });
+            }
+            symbols.extend((from..=to).filter(|c| is_symbol(*c)).flat_map(cases));
+        }
+        if negated {
+            symbols = ALPHABET
+                .iter()
+                .map(|&b| b as char)
+                .filter(|c| !symbols.contains(c))
+                .collect();
+        }
+        Ok(Regex::Symbols(symbols))
+    }
+}
+
+impl Regex {
+    /// How tightly the expression binds, where atoms bind tightest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Regex::Union(_, _) => 0,
+            Regex::Concat(_, _) => 1,
+            Regex::Star(_) | Regex::Plus(_) | Regex::Optional(_) => 2,
+            Regex::Epsilon | Regex::Symbols(_) => 3,
+        }
+    }
+
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "(")?;
+            self.fmt_with(f, 0)?;
+            return write!(f, ")");
+        }
+        match self {
+            Regex::Epsilon => write!(f, "()"),
+            Regex::Symbols(symbols) => {
+                // NOTE: both cases of a letter are printed as the lowercase
+                // letter, which matches both
+                let printed: BTreeSet<char> =
+                    symbols.iter().map(|c| c.to_ascii_lowercase()).collect();
+                if symbols.len() == ALPHABET.len() {
+                    write!(f, ".")
+                } else if printed.len() == 1 {
+                    write!(f, "{}", printed.first().unwrap())
+                } else {
+                    write!(f, "[{}]", printed.iter().collect::<String>())
+                }
+            }
+            Regex::Concat(l, r) => {
+                l.fmt_with(f, 1)?;
+                r.fmt_with(f, 2)
+            }
+            Regex::Union(l, r) => {
+                l.fmt_with(f, 0)?;
+                write!(f, "|")?;
+                r.fmt_with(f, 1)
+            }
+            Regex::Star(r) => {
+                r.fmt_with(f, 3)?;
+                write!(f, "*")
+            }
+            Regex::Plus(r) => {
+                r.fmt_with(f, 3)?;
+                write!(f, "+")
+            }
+            Regex::Optional(r) => {
+                r.fmt_with(f, 3)?;
+                write!(f, "?")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, 0)
+    }
+}
+
+impl Generate for Regex {
+    /// The remaining depth of the expression.
+    type Context = usize;
+
+    fn gn<R: ce_core::rand::Rng>(cx: &mut Self::Context, rng: &mut R) -> Self {
+        let symbol = |rng: &mut R| *ALPHABET.choose(rng).unwrap() as char;
+        if *cx == 0 || rng.random_bool(0.3) {
+            return match rng.random_range(0..10) {
+                0 => {
+                    let (a, b) = (symbol(rng), symbol(rng));
+                    Regex::Symbols(cases(a).chain(cases(b)).collect())
+                }
+                _ => Regex::Symbols(cases(symbol(rng)).collect()),
+            };
+        }
+        *cx -= 1;
+        let regex = match rng.random_range(0..10) {
+            0..=3 => Regex::Concat(Box::new(Regex::gn(cx, rng)), Box::new(Regex::gn(cx, rng))),
+            4..=6 => Regex::Union(Box::new(Regex::gn(cx, rng)), Box::new(Regex::gn(cx, rng))),
+            7 => Regex::Star(Box::new(Regex::gn(cx, rng))),
+            8 => Regex::Plus(Box::new(Regex::gn(cx, rng))),
+            _ => Regex::Optional(Box::new(Regex::gn(cx, rng))),
+        };
+        *cx += 1;
+        regex
+    }
+}
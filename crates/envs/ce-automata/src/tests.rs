@@ -1,4 +1,4 @@
-use crate::{AutomataEnv, Input, Output};
+use crate::{AutomataEnv, Input, Output, Regex};
 use ce_core::{Env, ValidationResult};
 
 #[test]
@@ -40,7 +40,8 @@ fn dead_end_equivalence() {
                 "0-1" -> "0-2" [label="B"];
                 "0-2" -> "0-3" [label="b"];
                 "0-2" -> "0-3" [label="B"];
-                "0" -> "k" [label="A"];
+                "0" -> "k" [label="b"];
+                "0-3" [accepting=true];
             }
         "#
         .to_string(),
@@ -235,19 +236,15 @@ fn split_on_bar() {
 
 #[test]
 fn invalid_character_fails() {
-    let input = Input {
-        regex: "a+b".to_string(),
-    };
-
-    let result = AutomataEnv::run(&input).unwrap();
-
-    assert!(
-        result
-            .dot
-            .contains("Only alhpanumerical chars and | is allowed"),
-        "Expected error message, got: {}",
-        result.dot
-    );
+    for regex in ["a$b", "a(b", "a|*", "[b-a]", "[ab"] {
+        let input = Input {
+            regex: regex.to_string(),
+        };
+        assert!(
+            AutomataEnv::run(&input).is_err(),
+            "expected {regex:?} to be rejected"
+        );
+    }
 }
 
 #[test]
@@ -291,3 +288,176 @@ fn casing_does_not_matter() {
         -if input contains non-alphabet character, then must return Err ---DONE
 
 */
+
+fn accepts(regex: &str, words: &[&str], rejects: &[&str]) {
+    let dfa = Input {
+        regex: regex.to_string(),
+    }
+    .dfa()
+    .unwrap();
+    for word in words {
+        assert!(dfa.accepts(word), "{regex:?} should accept {word:?}");
+    }
+    for word in rejects {
+        assert!(!dfa.accepts(word), "{regex:?} should reject {word:?}");
+    }
+}
+
+#[test]
+fn operators() {
+    accepts("ab*c", &["ac", "abc", "aBbbC"], &["", "a", "abbd"]);
+    accepts("ab+c", &["abc", "abbbc"], &["ac"]);
+    accepts("ab?c", &["ac", "abc"], &["abbc"]);
+    accepts("(ab|c)*", &["", "ab", "cabc"], &["a", "ba"]);
+    accepts("a|", &["", "a"], &["aa"]);
+}
+
+#[test]
+fn character_classes() {
+    accepts("[a-c]x", &["ax", "Bx", "cX"], &["dx", "x"]);
+    accepts("[^a-z]", &["1", "0"], &["a", "Q", "12"]);
+    accepts("[a0-2].", &["a1", "2z"], &["3a", "a"]);
+}
+
+#[test]
+fn reference_is_deterministic_and_correct() {
+    for regex in ["(a|b)*abb", "[a-c]+1?", "()", "(a*b*)*", "[^a-z0-9]"] {
+        let input = Input {
+            regex: regex.to_string(),
+        };
+        let output = AutomataEnv::run(&input).unwrap();
+        assert!(output.dot.contains("initial=true"));
+        match AutomataEnv::validate(&input, &output).unwrap() {
+            ValidationResult::Correct => (),
+            ValidationResult::Mismatch { reason } => panic!("{regex}: {reason}"),
+            ValidationResult::TimeOut => panic!("unexpected timeout"),
+        }
+    }
+}
+
+#[test]
+fn equivalent_automata_with_loops() {
+    // NOTE: an automaton for (ab)*a written as a(ba)*
+    let input = Input {
+        regex: "(ab)*a".to_string(),
+    };
+    let output = Output {
+        dot: r#"
+            digraph Automaton {
+                "p" [initial=true];
+                "q" [accepting=true];
+                "p" -> "q" [label="a"];
+                "p" -> "q" [label="A"];
+                "q" -> "p" [label="b"];
+                "q" -> "p" [label="B"];
+            }
+        "#
+        .to_string(),
+    };
+    assert_eq!(
+        AutomataEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let input = Input {
+        regex: "(ab)+a".to_string(),
+    };
+    assert!(matches!(
+        AutomataEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+}
+
+#[test]
+fn display_roundtrips() {
+    for regex in ["(a|b)*abb", "a(b|c)?", "[ab]+|.", "()|a"] {
+        let parsed: Regex = regex.parse().unwrap();
+        let printed: Regex = parsed.to_string().parse().unwrap();
+        assert_eq!(parsed, printed, "{regex} printed as {parsed}");
+    }
+}
+
+#[test]
+fn mismatch_gives_shortest_word() {
+    let reason = |regex: &str, dot: &str| {
+        let input = Input {
+            regex: regex.to_string(),
+        };
+        let output = Output {
+            dot: dot.to_string(),
+        };
+        match AutomataEnv::validate(&input, &output).unwrap() {
+            ValidationResult::Mismatch { reason } => reason,
+            result => panic!("expected mismatch, got {result:?}"),
+        }
+    };
+    // NOTE: accepts only the word `b`
+    let b = r#"
+        digraph {
+            "0" -> "1" [label="b"];
+            "0" -> "1" [label="B"];
+        }
+    "#;
+
+    assert_eq!(
+        reason("a*b", b),
+        "the automaton rejects \"AB\", which the regex matches"
+    );
+    assert_eq!(
+        reason("bb", b),
+        "the automaton accepts \"B\", which the regex does not match"
+    );
+    assert_eq!(
+        reason("b?", b),
+        "the automaton rejects ε, which the regex matches"
+    );
+}
+
+#[test]
+fn nondeterministic_automaton_is_rejected() {
+    let input = Input {
+        regex: "a|ab".to_string(),
+    };
+    let validate = |dot: &str| {
+        AutomataEnv::validate(
+            &input,
+            &Output {
+                dot: dot.to_string(),
+            },
+        )
+        .unwrap()
+    };
+    // NOTE: both accept the language of the regex, but the first has two
+    // transitions on `a` from the initial state
+    let nfa = r#"
+        digraph {
+            "0" [initial=true];
+            "1" [accepting=true];
+            "3" [accepting=true];
+            "0" -> "1" [label="a"];
+            "0" -> "1" [label="A"];
+            "0" -> "2" [label="a"];
+            "0" -> "2" [label="A"];
+            "2" -> "3" [label="b"];
+            "2" -> "3" [label="B"];
+        }
+    "#;
+    let dfa = r#"
+        digraph {
+            "0" [initial=true];
+            "1" [accepting=true];
+            "2" [accepting=true];
+            "0" -> "1" [label="a"];
+            "0" -> "1" [label="A"];
+            "1" -> "2" [label="b"];
+            "1" -> "2" [label="B"];
+        }
+    "#;
+    match validate(nfa) {
+        ValidationResult::Mismatch { reason } => {
+            assert!(reason.contains("not deterministic"), "{reason}")
+        }
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+    assert_eq!(validate(dfa), ValidationResult::Correct);
+}
@@ -0,0 +1,75 @@
+use gcl::{
+    ast::{AExpr, AExprKind, BExpr, BExprKind, LogicOp, Target},
+    integer::Integer,
+    parse::SourceSpan,
+    semantics::{SemanticsContext, SemanticsError},
+};
+
+use crate::Expression;
+
+/// Evaluates `expr` in `cx`, or gives the error along with the span of the
+/// sub-expression it arose in.
+pub fn evaluate<S: SemanticsContext>(
+    expr: &Expression,
+    cx: &S,
+) -> Result<String, (SemanticsError, SourceSpan)> {
+    match expr {
+        Expression::Arithmetic(a) => aexpr(cx, a).map(|n| n.to_string()),
+        Expression::Boolean(b) => bexpr(cx, b).map(|b| b.to_string()),
+    }
+}
+
+fn aexpr<S: SemanticsContext>(cx: &S, a: &AExpr) -> Result<Integer, (SemanticsError, SourceSpan)> {
+    let integers = cx.integers();
+    let result = match &a.kind {
+        AExprKind::Number(n) => integers.normalize((*n).into()),
+        AExprKind::Reference(Target::Variable(x)) => cx.variable(x),
+        AExprKind::Reference(Target::Array(arr, idx)) => {
+            let idx = aexpr(cx, idx)?;
+            cx.array_element(arr, &idx)
+        }
+        AExprKind::Binary(l, op, r) => {
+            let l = aexpr(cx, l)?;
+            let r = aexpr(cx, r)?;
+            integers.binary(*op, &l, &r)
+        }
+        AExprKind::Minus(n) => {
+            let n = aexpr(cx, n)?;
+            integers.negate(&n)
+        }
+        AExprKind::Declassify(e, _) => return aexpr(cx, e),
+    };
+    result.map_err(|err| (err, a.span))
+}
+
+fn bexpr<S: SemanticsContext>(cx: &S, b: &BExpr) -> Result<bool, (SemanticsError, SourceSpan)> {
+    let result = match &b.kind {
+        BExprKind::Bool(b) => Ok(*b),
+        BExprKind::Reference(Target::Variable(x)) => cx.bool_variable(x),
+        BExprKind::Reference(Target::Array(arr, idx)) => {
+            let idx = aexpr(cx, idx)?;
+            cx.bool_array_element(arr, &idx)
+        }
+        BExprKind::Rel(l, op, r) => {
+            let l = aexpr(cx, l)?;
+            let r = aexpr(cx, r)?;
+            Ok(op.semantic(l, r))
+        }
+        BExprKind::Logic(l, op, r) => {
+            let l = bexpr(cx, l)?;
+            let short_circuits = match op {
+                LogicOp::And => !l,
+                LogicOp::Or => l,
+                LogicOp::Land | LogicOp::Lor => false,
+            };
+            if short_circuits {
+                Ok(l)
+            } else {
+                let r = bexpr(cx, r)?;
+                op.semantic(l, || Ok(r))
+            }
+        }
+        BExprKind::Not(b) => return bexpr(cx, b).map(|b| !b),
+    };
+    result.map_err(|err| (err, b.span))
+}
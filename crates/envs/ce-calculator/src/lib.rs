@@ -1,5 +1,17 @@
+mod eval;
+#[cfg(test)]
+mod tests;
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
 use ce_core::{Env, Generate, ValidationResult, define_env, gn::GclGenContext, rand};
-use gcl::ast::AExpr;
+use gcl::{
+    ast::{AExpr, AExprKind, BExpr, BExprKind, Target, TargetDef, Variable},
+    integer::Integer,
+    interpreter::InterpreterMemory,
+    parse::{ParseError, SourceSpan, parse_aexpr, parse_bexpr},
+    typeck::Type,
+};
 use serde::{Deserialize, Serialize};
 use stdx::stringify::Stringify;
 
@@ -8,7 +20,10 @@ define_env!(CalcEnv);
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[tapi(path = "Calculator")]
 pub struct Input {
-    pub expression: Stringify<AExpr>,
+    pub expression: Stringify<Expression>,
+    /// The values of the variables and arrays of the expression.
+    #[serde(default)]
+    pub assignment: InterpreterMemory,
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,6 +31,178 @@ pub struct Input {
 pub struct Output {
     pub result: String,
     pub error: String,
+    /// The sub-expression whose evaluation failed, if any. It is required
+    /// whenever `error` is given.
+    #[serde(default)]
+    pub error_span: Option<Span>,
+}
+
+/// A part of the expression, from `start` up to `end`, counted in characters.
+///
+/// The span of a sub-expression includes a leading unary minus, as in `-a`,
+/// but not the parentheses around it, so the span of `(a / b)` is `a / b`.
+/// Spans which differ only by enclosing parentheses and whitespace are
+/// considered the same location.
+#[derive(tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[tapi(path = "Calculator")]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn new(src: &str, span: SourceSpan) -> Span {
+        let chars = |bytes: usize| src[..bytes].chars().count();
+        Span {
+            start: chars(span.offset()),
+            end: chars(span.end()),
+        }
+    }
+
+    /// The part of `src` within the span.
+    pub fn slice<'a>(&self, src: &'a str) -> &'a str {
+        let byte = |chars: usize| src.char_indices().nth(chars).map_or(src.len(), |(i, _)| i);
+        &src[byte(self.start)..byte(self.end)]
+    }
+
+    /// The span without the whitespace and the matching parentheses which
+    /// enclose it in `src`.
+    fn strip(self, src: &str) -> Span {
+        let chars = src.chars().collect::<Vec<_>>();
+        let Span { mut start, mut end } = self;
+        end = end.min(chars.len());
+        start = start.min(end);
+        loop {
+            while start < end && chars[start].is_whitespace() {
+                start += 1;
+            }
+            while start < end && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            if end - start < 2 || chars[start] != '(' || chars[end - 1] != ')' {
+                return Span { start, end };
+            }
+            // NOTE: the parentheses only enclose the span if the first one is
+            // closed by the last, unlike in `(a) + (b)`
+            let mut depth = 0usize;
+            for c in &chars[start..end - 1] {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    return Span { start, end };
+                }
+            }
+            (start, end) = (start + 1, end - 1);
+        }
+    }
+}
+
+/// An integer or a boolean expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Arithmetic(AExpr),
+    Boolean(BExpr),
+}
+
+impl Expression {
+    /// The variables and arrays of the expression. A bare reference is
+    /// boolean if the assignment says so, and an integer otherwise.
+    pub fn targets(&self, assignment: &InterpreterMemory) -> BTreeSet<TargetDef> {
+        fn aexpr(a: &AExpr, defs: &mut BTreeSet<TargetDef>) {
+            match &a.kind {
+                AExprKind::Number(_) => {}
+                AExprKind::Reference(t) => target(t, Type::Int, defs),
+                AExprKind::Binary(l, _, r) => {
+                    aexpr(l, defs);
+                    aexpr(r, defs);
+                }
+                AExprKind::Minus(a) | AExprKind::Declassify(a, _) => aexpr(a, defs),
+            }
+        }
+        fn bexpr(b: &BExpr, defs: &mut BTreeSet<TargetDef>) {
+            match &b.kind {
+                BExprKind::Bool(_) => {}
+                BExprKind::Reference(t) => target(t, Type::Bool, defs),
+                BExprKind::Rel(l, _, r) => {
+                    aexpr(l, defs);
+                    aexpr(r, defs);
+                }
+                BExprKind::Logic(l, _, r) => {
+                    bexpr(l, defs);
+                    bexpr(r, defs);
+                }
+                BExprKind::Not(b) => bexpr(b, defs),
+            }
+        }
+        fn target(t: &Target<Box<AExpr>>, ty: Type, defs: &mut BTreeSet<TargetDef>) {
+            if let Target::Array(_, idx) = t {
+                aexpr(idx, defs);
+            }
+            defs.insert(t.def(ty));
+        }
+
+        let mut defs = BTreeSet::new();
+        match self.resolve(assignment) {
+            Expression::Arithmetic(a) => aexpr(&a, &mut defs),
+            Expression::Boolean(b) => bexpr(&b, &mut defs),
+        }
+        defs
+    }
+
+    /// Reads a bare reference to a boolean of the assignment as a boolean
+    /// expression, since it parses as an integer expression.
+    fn resolve(&self, assignment: &InterpreterMemory) -> Expression {
+        match self {
+            Expression::Arithmetic(AExpr {
+                kind: AExprKind::Reference(t),
+                span,
+            }) => {
+                let is_bool = match t {
+                    Target::Variable(x) => assignment.bool_variables.contains_key(x),
+                    Target::Array(arr, _) => assignment.bool_arrays.contains_key(arr),
+                };
+                if is_bool {
+                    Expression::Boolean(BExpr::reference(t.clone()).with_span(*span))
+                } else {
+                    self.clone()
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_aexpr(s) {
+            Ok(a) => Ok(Expression::Arithmetic(a)),
+            Err(_) => parse_bexpr(s).map(Expression::Boolean),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Arithmetic(a) => a.fmt(f),
+            Expression::Boolean(b) => b.fmt(f),
+        }
+    }
+}
+
+impl Input {
+    /// The source of the expression, which spans refer to.
+    pub fn source(&self) -> String {
+        match &self.expression {
+            Stringify::Parsed(e) => e.to_string(),
+            Stringify::Unparsed(s) => s.clone(),
+        }
+    }
 }
 
 impl Env for CalcEnv {
@@ -23,29 +210,39 @@ impl Env for CalcEnv {
 
     type Output = Output;
 
-    type Meta = ();
+    type Meta = BTreeSet<TargetDef>;
+
+    fn meta(input: &Self::Input) -> Self::Meta {
+        if let Ok(expr) = input.expression.try_parse() {
+            expr.targets(&input.assignment)
+        } else {
+            Default::default()
+        }
+    }
 
     fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
-        let expr =
-            input
-                .expression
-                .try_parse()
+        let src = input.source();
+        let expr: Expression =
+            src.parse()
                 .map_err(ce_core::EnvError::invalid_input_for_program(
                     "failed to parse expression",
                 ))?;
-        let (result, error) = match expr.semantics(&gcl::semantics::EmptySemanticsContext) {
-            Ok(result) => (result.to_string(), String::new()),
-            Err(err) => {
-                let error = format!("{err}");
-                (String::new(), error)
-            }
-        };
+        let (result, error, error_span) =
+            match eval::evaluate(&expr.resolve(&input.assignment), &input.assignment) {
+                Ok(result) => (result, String::new(), None),
+                Err((err, span)) => (String::new(), format!("{err}"), Some(Span::new(&src, span))),
+            };
 
-        Ok(Output { result, error })
+        Ok(Output {
+            result,
+            error,
+            error_span,
+        })
     }
 
     fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
         let reference = Self::run(input)?;
+        let src = input.source();
 
         Ok(
             match (
@@ -54,8 +251,27 @@ impl Env for CalcEnv {
                 !reference.error.is_empty(),
                 !output.error.is_empty(),
             ) {
-                // Both errors are present
-                (_, _, true, true) => ValidationResult::Correct,
+                // Both errors are present, and agree on the location
+                (_, _, true, true) => match (reference.error_span, output.error_span) {
+                    (Some(expected), None) => ValidationResult::Mismatch {
+                        reason: format!(
+                            "the error is caused by `{}`, but no location was given",
+                            expected.slice(&src),
+                        ),
+                    },
+                    (Some(expected), Some(actual))
+                        if expected.strip(&src) != actual.strip(&src) =>
+                    {
+                        ValidationResult::Mismatch {
+                            reason: format!(
+                                "the error is caused by `{}`, not by `{}`",
+                                expected.slice(&src),
+                                actual.slice(&src),
+                            ),
+                        }
+                    }
+                    _ => ValidationResult::Correct,
+                },
                 // Both results are present
                 (r, o, _, _) if r == o => ValidationResult::Correct,
                 (_, _, _, _) => {
@@ -76,22 +292,33 @@ impl Generate for Input {
     type Context = ();
 
     fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let mut assignment = InterpreterMemory::default();
+        for name in ["a", "b", "c", "d"] {
+            assignment.variables.insert(
+                Variable(name.to_string()),
+                Integer::from(rng.random_range(-10..=10)),
+            );
+        }
+        for name in ["p", "q"] {
+            assignment
+                .bool_variables
+                .insert(Variable(name.to_string()), rng.random());
+        }
+
         let mut gen_expr = || {
-            AExpr::gn(
-                &mut GclGenContext {
-                    names: Vec::new(),
-                    ..GclGenContext::new(25, rng)
-                },
-                rng,
-            )
+            let mut cx = GclGenContext::new(25, rng);
+            cx.set_bool_names(&["p", "q"]);
+            if rng.random_bool(0.3) {
+                cx.recursion_limit = 4;
+                Expression::Boolean(BExpr::gn(&mut cx, rng))
+            } else {
+                Expression::Arithmetic(AExpr::gn(&mut cx, rng))
+            }
         };
 
         let mut expr = gen_expr();
         for _ in 0..10 {
-            if expr
-                .semantics(&gcl::semantics::EmptySemanticsContext)
-                .is_ok()
-            {
+            if eval::evaluate(&expr, &assignment).is_ok() {
                 break;
             }
             expr = gen_expr();
@@ -99,6 +326,7 @@ impl Generate for Input {
 
         Input {
             expression: Stringify::new(expr),
+            assignment,
         }
     }
 }
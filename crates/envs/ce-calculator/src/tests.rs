@@ -0,0 +1,148 @@
+use ce_core::rand::{SeedableRng, rngs::SmallRng};
+use ce_core::{Env, Generate, ValidationResult};
+use gcl::{
+    ast::{Array, Variable},
+    integer::Integer,
+    interpreter::InterpreterMemory,
+};
+use stdx::stringify::Stringify;
+
+use crate::{CalcEnv, Expression, Input, Output};
+
+fn input(expression: &str) -> Input {
+    let mut assignment = InterpreterMemory::default();
+    for (name, value) in [("a", 2), ("b", 5), ("z", 0)] {
+        assignment
+            .variables
+            .insert(Variable(name.to_string()), Integer::from(value));
+    }
+    assignment
+        .arrays
+        .insert(Array("A".to_string()), [1, 2].map(Integer::from).to_vec());
+    assignment
+        .bool_variables
+        .insert(Variable("p".to_string()), true);
+    Input {
+        expression: Stringify::Unparsed(expression.to_string()),
+        assignment,
+    }
+}
+
+/// The result of evaluating `expression`, or the error along with the part of
+/// the expression it is located at.
+fn run(expression: &str) -> Result<String, (String, String)> {
+    let input = input(expression);
+    let output = CalcEnv::run(&input).unwrap();
+    match output.error_span {
+        Some(span) => Err((output.error, span.slice(expression).to_string())),
+        None => Ok(output.result),
+    }
+}
+
+#[test]
+fn variables_and_arrays() {
+    assert_eq!(run("a * b - A[1]"), Ok("8".to_string()));
+}
+
+#[test]
+fn division_by_zero_is_located() {
+    assert_eq!(
+        run("1 + 4 / (a - a)"),
+        Err(("division by zero".to_string(), "4 / (a - a)".to_string()))
+    );
+}
+
+#[test]
+fn index_out_of_bound_is_located() {
+    let (error, span) = run("a + A[b - 1] * 2").unwrap_err();
+    assert!(error.contains("out-of-bounds"), "{error}");
+    assert_eq!(span, "A[b - 1]");
+}
+
+#[test]
+fn boolean_expressions() {
+    assert_eq!(run("p && a < b"), Ok("true".to_string()));
+    assert_eq!(run("!(a = 2) || A[0] >= 1"), Ok("true".to_string()));
+    assert_eq!(run("p"), Ok("true".to_string()));
+}
+
+#[test]
+fn short_circuit_skips_operand() {
+    assert_eq!(run("!p && 1 / z = 0"), Ok("false".to_string()));
+    assert_eq!(
+        run("(p || 1 / z = 0) && 2 / z > 0"),
+        Err(("division by zero".to_string(), "2 / z".to_string()))
+    );
+    assert_eq!(
+        run("!p & 1 / z = 0"),
+        Err(("division by zero".to_string(), "1 / z".to_string()))
+    );
+}
+
+#[test]
+fn wrong_error_location_is_rejected() {
+    let input = input("1 / z + 2 / (a - 2)");
+    let mut output = CalcEnv::run(&input).unwrap();
+    assert_eq!(
+        CalcEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+    output.error_span = Some(crate::Span { start: 8, end: 19 });
+    match CalcEnv::validate(&input, &output).unwrap() {
+        ValidationResult::Mismatch { reason } => {
+            assert!(reason.contains("`1 / z`"), "{reason}")
+        }
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+
+    output.error_span = None;
+    match CalcEnv::validate(&input, &output).unwrap() {
+        ValidationResult::Mismatch { reason } => {
+            assert!(reason.contains("no location"), "{reason}")
+        }
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+}
+
+#[test]
+fn agrees_with_semantics() {
+    let mut rng = SmallRng::seed_from_u64(0xca1c);
+    for _ in 0..200 {
+        let input = Input::gn(&mut (), &mut rng);
+        let expected = match input.expression.try_parse().unwrap() {
+            Expression::Arithmetic(a) => a.semantics(&input.assignment).map(|n| n.to_string()),
+            Expression::Boolean(b) => b.semantics(&input.assignment).map(|b| b.to_string()),
+        };
+        let output: Output = CalcEnv::run(&input).unwrap();
+        match expected {
+            Ok(result) => assert_eq!(output.result, result, "{}", input.source()),
+            Err(err) => {
+                assert_eq!(output.error, err.to_string(), "{}", input.source());
+                assert!(output.error_span.is_some(), "{}", input.source());
+            }
+        }
+    }
+}
+
+#[test]
+fn enclosing_parentheses_are_ignored_in_locations() {
+    let nested = input("1 + ( (4 / z)) * (a) + (b)");
+    let mut output = CalcEnv::run(&nested).unwrap();
+    assert_eq!(output.error_span.unwrap().slice(&nested.source()), "4 / z");
+
+    for (start, end) in [(4, 14), (5, 13), (6, 13)] {
+        output.error_span = Some(crate::Span { start, end });
+        assert_eq!(
+            CalcEnv::validate(&nested, &output).unwrap(),
+            ValidationResult::Correct
+        );
+    }
+
+    let separate = input("(a) + (b) / z");
+    let mut output = CalcEnv::run(&separate).unwrap();
+    output.error_span = Some(crate::Span { start: 0, end: 13 });
+    assert!(matches!(
+        CalcEnv::validate(&separate, &output).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+}
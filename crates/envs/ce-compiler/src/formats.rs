@@ -0,0 +1,233 @@
+//! Parsing of the program graph formats accepted as output, besides dot.
+
+use std::collections::BTreeMap;
+
+use gcl::pg::{Action, formats::JsonProgramGraph};
+
+use crate::dot::{self, ParsedGraph};
+
+/// Parses a program graph in any of the supported formats: Graphviz dot, the
+/// JSON edge list of [`gcl::pg::ProgramGraph::json`], GraphML, or a Mermaid
+/// flowchart.
+pub fn parse_graph(src: &str) -> Result<ParsedGraph, String> {
+    let trimmed = src.trim_start();
+    let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+    if trimmed.starts_with('{') {
+        json_to_petgraph(src)
+    } else if trimmed.starts_with('<') {
+        graphml_to_petgraph(src)
+    } else if first_word == "flowchart" || (first_word == "graph" && !trimmed.contains('{')) {
+        mermaid_to_petgraph(src)
+    } else {
+        dot::dot_to_petgraph(src)
+    }
+}
+
+fn from_edges(edges: Vec<(String, Action, String)>) -> ParsedGraph {
+    let mut nodes = BTreeMap::<String, dot::Node>::new();
+    let mut node_mapping = BTreeMap::new();
+    let mut graph = petgraph::Graph::new();
+
+    for (a, action, b) in edges {
+        let a_id = *node_mapping
+            .entry(a.clone())
+            .or_insert_with_key(|k| graph.add_node(k.to_string()));
+        let b_id = *node_mapping
+            .entry(b.clone())
+            .or_insert_with_key(|k| graph.add_node(k.to_string()));
+        graph.add_edge(a_id, b_id, action);
+
+        nodes.entry(a.clone()).or_default().outgoing.push(b.clone());
+        nodes.entry(b).or_default().ingoing.push(a);
+    }
+
+    ParsedGraph {
+        nodes,
+        node_mapping,
+        graph,
+    }
+}
+
+fn json_to_petgraph(src: &str) -> Result<ParsedGraph, String> {
+    let graph: JsonProgramGraph = serde_json::from_str(src).map_err(|e| e.to_string())?;
+    let edges = graph
+        .edges
+        .into_iter()
+        .map(|e| {
+            let action = Action::try_from(&e.action).map_err(|e| e.to_string())?;
+            Ok((e.from, action, e.to))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(from_edges(edges))
+}
+
+fn graphml_to_petgraph(src: &str) -> Result<ParsedGraph, String> {
+    let doc = roxmltree::Document::parse(src).map_err(|e| e.to_string())?;
+
+    // NOTE: edge data is referenced by key id, so look up the id of the key
+    // named "action", falling back to one named "label"
+    let key_id = |name: &str| {
+        doc.descendants()
+            .filter(|n| n.has_tag_name("key"))
+            .filter(|n| matches!(n.attribute("for"), Some("edge" | "all") | None))
+            .find(|n| n.attribute("attr.name") == Some(name))
+            .and_then(|n| n.attribute("id"))
+    };
+    let action_key = key_id("action")
+        .or_else(|| key_id("label"))
+        .ok_or("no edge key named `action` or `label` found")?;
+
+    let edges = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("edge"))
+        .map(|e| {
+            let source = e.attribute("source").ok_or("edge without source")?;
+            let target = e.attribute("target").ok_or("edge without target")?;
+            let label = e
+                .children()
+                .find(|d| d.has_tag_name("data") && d.attribute("key") == Some(action_key))
+                .and_then(|d| d.text())
+                .ok_or_else(|| format!("edge from {source} to {target} has no action"))?;
+            let action = gcl::parse::parse_action(label.trim())
+                .map_err(|e| format!("failed to parse action: {label}. {e}"))?;
+            Ok((source.to_string(), action, target.to_string()))
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(from_edges(edges))
+}
+
+/// Parses a Mermaid flowchart, where every edge is labelled with its action,
+/// either as `A -->|action| B` or as `A -- action --> B`. Statements are
+/// separated by newlines or `;`, and links may be chained, as in
+/// `A -->|x := 1| B -->|y := 2| C`. Statements without links, such as node
+/// declarations, are ignored.
+fn mermaid_to_petgraph(src: &str) -> Result<ParsedGraph, String> {
+    let mut statements = mermaid_statements(src);
+    // NOTE: the header may be followed by the first statement on its line
+    if let Some(first) = statements.first_mut() {
+        let mut words = first.split_whitespace().peekable();
+        words.next_if(|w| matches!(*w, "flowchart" | "graph"));
+        words.next_if(|w| matches!(*w, "TB" | "TD" | "BT" | "LR" | "RL"));
+        *first = words.collect::<Vec<_>>().join(" ");
+    }
+
+    let mut edges = vec![];
+    for statement in &statements {
+        let statement = statement.trim();
+        if statement.is_empty() || statement.starts_with("%%") {
+            continue;
+        }
+        let (from, mut rest) = mermaid_node(statement);
+        let mut from = from.to_string();
+        while !rest.is_empty() {
+            let (label, after) = mermaid_link(&from, rest)?;
+            let (to, after) = mermaid_node(after);
+            if to.is_empty() {
+                return Err(format!("edge from {from} has no target"));
+            }
+            let label = label
+                .replace("#quot;", "\"")
+                .replace("#lt;", "<")
+                .replace("#gt;", ">")
+                .replace("#amp;", "&");
+            let action = gcl::parse::parse_action(&label)
+                .map_err(|e| format!("failed to parse action: {label}. {e}"))?;
+            edges.push((from, action, to.to_string()));
+            from = to.to_string();
+            rest = after;
+        }
+    }
+
+    Ok(from_edges(edges))
+}
+
+/// Splits the flowchart into statements at newlines and at `;`, except for the
+/// `;` ending an entity such as `#quot;`, and those within labels or quotes.
+fn mermaid_statements(src: &str) -> Vec<String> {
+    let mut statements = vec![String::new()];
+    let (mut in_label, mut in_quotes, mut entity) = (false, false, false);
+    for c in src.chars() {
+        match c {
+            '\n' => {
+                statements.push(String::new());
+                (in_label, in_quotes, entity) = (false, false, false);
+                continue;
+            }
+            ';' if !in_label && !in_quotes && !entity => {
+                statements.push(String::new());
+                continue;
+            }
+            '|' if !in_quotes => in_label = !in_label,
+            '"' => in_quotes = !in_quotes,
+            '#' => entity = true,
+            c if !c.is_ascii_alphanumeric() => entity = false,
+            _ => {}
+        }
+        statements.last_mut().unwrap().push(c);
+    }
+    statements
+}
+
+/// Splits off the node at the start of `src`, returning its id and the rest,
+/// which starts at the following link, if any.
+fn mermaid_node(src: &str) -> (&str, &str) {
+    let src = src.trim_start();
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut shape = None;
+    for (idx, c) in src.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            '[' | '(' | '{' | '>' => {
+                shape.get_or_insert(idx);
+                depth += 1;
+            }
+            ']' | ')' | '}' => depth = depth.saturating_sub(1),
+            '-' | '=' | '~' if depth == 0 && src[idx..].len() > 1 => {
+                let next = src[idx + 1..].chars().next();
+                if matches!(next, Some('-' | '.' | '=' | '>' | '~')) {
+                    let id = &src[..shape.unwrap_or(idx)];
+                    return (id.trim(), &src[idx..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    (src[..shape.unwrap_or(src.len())].trim(), "")
+}
+
+/// Parses the link at the start of `src`, returning its label and the rest.
+fn mermaid_link<'a>(from: &str, src: &'a str) -> Result<(String, &'a str), String> {
+    let label = |label: &str| label.trim().trim_matches('"').trim().to_string();
+    if let Some(rest) = src.strip_prefix("-->") {
+        let rest = rest.trim_start();
+        let Some(rest) = rest.strip_prefix('|') else {
+            return Err(format!("edge from {from} has no label"));
+        };
+        let (text, rest) = split_label(rest, "|")
+            .ok_or_else(|| format!("the label of the edge from {from} is not closed"))?;
+        Ok((label(text), rest))
+    } else if let Some(rest) = src.strip_prefix("--") {
+        let (text, rest) = split_label(rest, "-->")
+            .ok_or_else(|| format!("the edge from {from} is not an arrow `-->`"))?;
+        Ok((label(text), rest))
+    } else {
+        let link = src.split_whitespace().next().unwrap_or_default();
+        Err(format!(
+            "the link `{link}` from {from} is not supported, only arrows `-->` are"
+        ))
+    }
+}
+
+/// Splits `src` at the `delimiter` ending a label, skipping over the label if
+/// it is quoted, as it may then contain the delimiter, e.g. `|` in `"a | b"`.
+fn split_label<'a>(src: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let start = match src.trim_start().strip_prefix('"') {
+        Some(quoted) => src.len() - quoted.len() + quoted.find('"')? + 1,
+        None => 0,
+    };
+    let idx = start + src[start..].find(delimiter)?;
+    Some((&src[..idx], &src[idx + delimiter.len()..]))
+}
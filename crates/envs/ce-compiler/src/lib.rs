@@ -1,15 +1,15 @@
 mod dot;
-
-use std::collections::{BTreeMap, BTreeSet};
+mod formats;
+#[cfg(test)]
+mod tests;
 
 use ce_core::{Env, Generate, ValidationResult, define_env};
 use gcl::{
     ast::Commands,
-    interpreter::InterpreterMemory,
-    pg::{Determinism, ProgramGraph},
+    pg::{Determinism, Node, ProgramGraph, equivalence::Inequivalence},
 };
 use itertools::Itertools;
-use rand::{Rng, seq::IndexedRandom};
+use rand::seq::IndexedRandom;
 use serde::{Deserialize, Serialize};
 use stdx::stringify::Stringify;
 
@@ -25,6 +25,8 @@ pub struct Input {
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[tapi(path = "Compiler")]
 pub struct Output {
+    /// The program graph as Graphviz dot, JSON, GraphML, or a Mermaid
+    /// flowchart.
     pub dot: String,
 }
 
@@ -55,44 +57,61 @@ impl Env for CompilerEnv {
                 .map_err(ce_core::EnvError::invalid_input_for_program(
                     "failed to parse commands",
                 ))?;
-        let o_dot = ProgramGraph::new(input.determinism, &commands).dot();
-
-        let mut rng = <rand::rngs::SmallRng as rand::SeedableRng>::seed_from_u64(0xCEC34);
-        let sample_mems = (0..10)
-            .map(|_| {
-                let initial_memory = gcl::memory::Memory::from_targets_with(
-                    commands.fv(),
-                    &mut rng,
-                    |rng, _| rng.random_range(-10..=10),
-                    |rng, _| {
-                        let len = rng.random_range(5..=10);
-                        (0..len).map(|_| rng.random_range(-10..=10)).collect()
-                    },
-                );
-                InterpreterMemory {
-                    variables: initial_memory.variables,
-                    arrays: initial_memory.arrays,
-                }
-            })
-            .collect_vec();
+        let o_pg = ProgramGraph::new(input.determinism, &commands);
 
-        let t_g = match dot::dot_to_petgraph(&output.dot) {
+        let t_g = match formats::parse_graph(&output.dot) {
             Ok(t_g) => t_g,
             Err(err) => {
                 return Ok(ValidationResult::Mismatch {
-                    reason: format!("failed to parse dot: {err}"),
+                    reason: format!("failed to parse graph: {err}"),
                 });
             }
         };
-        let o_g = dot::dot_to_petgraph(&o_dot).expect("we always produce valid dot");
+        let names = t_g
+            .graph
+            .node_weights()
+            .map(|n| n.trim_matches('"'))
+            .collect_vec();
+        let (t_pg, t_nodes) = ProgramGraph::from_named_edges(
+            names.iter().copied(),
+            t_g.graph.edge_indices().map(|e| {
+                let (a, b) = t_g.graph.edge_endpoints(e).unwrap();
+                (names[a.index()], t_g.graph[e].clone(), names[b.index()])
+            }),
+        );
+        let t_name = |node: Node| {
+            t_nodes
+                .iter()
+                .find(|(_, n)| **n == node)
+                .map_or_else(|| format!("{node:?}"), |(name, _)| name.to_string())
+        };
 
-        if action_bag(&o_g, &sample_mems) != action_bag(&t_g, &sample_mems) {
-            Ok(ValidationResult::Mismatch {
-                reason: "the graphs have different structure".to_string(),
-            })
-        } else {
-            Ok(ValidationResult::Correct)
-        }
+        Ok(match o_pg.find_isomorphism(&t_pg) {
+            Ok(_) => ValidationResult::Correct,
+            Err(Inequivalence::Missing {
+                edge,
+                counterpart: Some(counterpart),
+            }) => ValidationResult::Mismatch {
+                reason: format!(
+                    "your graph has no edge from {} corresponding to {edge}",
+                    t_name(counterpart)
+                ),
+            },
+            Err(Inequivalence::Missing {
+                edge,
+                counterpart: None,
+            }) => ValidationResult::Mismatch {
+                reason: format!("your graph has no edge corresponding to {edge}"),
+            },
+            Err(Inequivalence::Extra { edge }) => ValidationResult::Mismatch {
+                reason: format!(
+                    "the edge {} -> {} labelled `{}` does not correspond to any expected edge",
+                    t_name(edge.from()),
+                    t_name(edge.to()),
+                    edge.action(),
+                ),
+            },
+        })
     }
 }
 
@@ -110,47 +129,3 @@ impl Generate for Input {
         }
     }
 }
-
-fn action_bag(
-    g: &dot::ParsedGraph,
-    mems: &[InterpreterMemory],
-) -> BTreeMap<[BTreeSet<Fingerprint>; 2], usize> {
-    let mut counts = BTreeMap::new();
-
-    for i in g.graph.node_indices() {
-        let id = [petgraph::Incoming, petgraph::Outgoing].map(|dir| {
-            g.graph
-                .edges_directed(i, dir)
-                .map(|e| fingerprint(e.weight(), mems))
-                .collect()
-        });
-        *counts.entry(id).or_insert(0) += 1;
-    }
-
-    counts
-}
-
-type Fingerprint = (ActionKind, Vec<Option<InterpreterMemory>>);
-fn fingerprint(a: &gcl::pg::Action, mems: &[InterpreterMemory]) -> Fingerprint {
-    (
-        a.into(),
-        mems.iter().map(|mem| a.semantics(mem).ok()).collect(),
-    )
-}
-
-impl From<&'_ gcl::pg::Action> for ActionKind {
-    fn from(action: &'_ gcl::pg::Action) -> Self {
-        match action {
-            gcl::pg::Action::Assignment(t, _) => ActionKind::Assignment(t.clone().map_idx(|_| ())),
-            gcl::pg::Action::Skip => ActionKind::Skip,
-            gcl::pg::Action::Condition(_) => ActionKind::Condition,
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum ActionKind {
-    Assignment(gcl::ast::Target<()>),
-    Skip,
-    Condition,
-}
@@ -0,0 +1,121 @@
+use ce_core::{Env, ValidationResult};
+use gcl::pg::{Determinism, ProgramGraph};
+use stdx::stringify::Stringify;
+
+use crate::{CompilerEnv, Input, Output};
+
+const PROGRAMS: &[&str] = &[
+    "x := 1 ; y := x * -2",
+    "A[x + 1] := A[x] ; if x > 0 -> y := x [] x <= 0 -> skip fi",
+    "do y > 0 -> y := y - 1 od",
+    "proc inc(n; m) m := n + 1 end ; call inc(x; y)",
+];
+
+fn validate(src: &str, determinism: Determinism, graph: impl Fn(&ProgramGraph) -> String) {
+    let input = Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        determinism,
+    };
+    let pg = ProgramGraph::new(determinism, &input.commands.try_parse().unwrap());
+    let output = Output { dot: graph(&pg) };
+    assert_eq!(
+        CompilerEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct,
+        "{src}\n{}",
+        output.dot
+    );
+}
+
+#[test]
+fn dot_is_accepted() {
+    for src in PROGRAMS {
+        validate(src, Determinism::Deterministic, ProgramGraph::dot);
+        validate(src, Determinism::NonDeterministic, ProgramGraph::dot);
+    }
+}
+
+#[test]
+fn json_is_accepted() {
+    for src in PROGRAMS {
+        validate(src, Determinism::Deterministic, ProgramGraph::json);
+        validate(src, Determinism::NonDeterministic, ProgramGraph::json);
+    }
+}
+
+#[test]
+fn graphml_is_accepted() {
+    for src in PROGRAMS {
+        validate(src, Determinism::Deterministic, ProgramGraph::graphml);
+        validate(src, Determinism::NonDeterministic, ProgramGraph::graphml);
+    }
+}
+
+#[test]
+fn mermaid_is_accepted() {
+    for src in PROGRAMS {
+        validate(src, Determinism::Deterministic, ProgramGraph::mermaid);
+        validate(src, Determinism::NonDeterministic, ProgramGraph::mermaid);
+    }
+}
+
+#[test]
+fn mermaid_statements_and_links() {
+    let src = "x := 1 ; y := 2";
+    let pg =
+        |_: &ProgramGraph| "flowchart TD; qStart -->|x := 1| q1 -->|\"y := 2\"| qFinal".to_string();
+    validate(src, Determinism::Deterministic, pg);
+    let pg = |_: &ProgramGraph| {
+        "flowchart LR\n  qStart((start)) -- \"x := 1\" --> q1\n  q1 -- y := 2 --> qFinal((end));"
+            .to_string()
+    };
+    validate(src, Determinism::Deterministic, pg);
+}
+
+#[test]
+fn unsupported_mermaid_links_are_rejected() {
+    let input = Input {
+        commands: Stringify::Unparsed("x := 1".to_string()),
+        determinism: Determinism::Deterministic,
+    };
+    for (dot, error) in [
+        ("flowchart TD; qStart --> qFinal", "has no label"),
+        (
+            "flowchart TD; qStart -.->|x := 1| qFinal",
+            "is not supported",
+        ),
+        (
+            "flowchart TD; qStart ==>|x := 1| qFinal",
+            "is not supported",
+        ),
+        (
+            "flowchart TD; qStart -- x := 1 --- qFinal",
+            "is not an arrow",
+        ),
+    ] {
+        let output = Output {
+            dot: dot.to_string(),
+        };
+        match CompilerEnv::validate(&input, &output).unwrap() {
+            ValidationResult::Mismatch { reason } => assert!(reason.contains(error), "{reason}"),
+            result => panic!("{dot} was accepted: {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn graph_of_another_program_is_rejected() {
+    let input = Input {
+        commands: Stringify::Unparsed("x := 1 ; y := 2".to_string()),
+        determinism: Determinism::Deterministic,
+    };
+    let other = ProgramGraph::new(
+        Determinism::Deterministic,
+        &gcl::parse::parse_commands("x := 1 ; y := 3").unwrap(),
+    );
+    for dot in [other.dot(), other.json(), other.graphml(), other.mermaid()] {
+        assert!(matches!(
+            CompilerEnv::validate(&input, &Output { dot }).unwrap(),
+            ValidationResult::Mismatch { .. }
+        ));
+    }
+}
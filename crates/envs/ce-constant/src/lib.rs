@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use ce_sign::{Bools, domain::AbstractDomain};
+use gcl::ast::{AOp, Int, RelOp};
+use serde::{Deserialize, Serialize};
+
+ce_sign::domain_env! {
+    env: ConstantEnv,
+    path: "ConstantAnalysis",
+    domain: Constant,
+    memory: ConstantMemory,
+    generate: |rng| {
+        if rng.random_bool(0.2) {
+            Constant::NotConstant
+        } else {
+            Constant::Value(rng.random_range(-5..=5))
+        }
+    },
+}
+
+/// The value of an integer if it is constant.
+#[derive(
+    tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(tag = "type", content = "value")]
+#[tapi(path = "ConstantAnalysis")]
+pub enum Constant {
+    Value(Int),
+    NotConstant,
+}
+
+/// Memories are merged by joining their values, such that the analysis
+/// terminates even though there are infinitely many constants.
+impl AbstractDomain for Constant {
+    fn alpha(n: Int) -> Self {
+        Constant::Value(n)
+    }
+
+    fn top() -> Vec<Self> {
+        vec![Constant::NotConstant]
+    }
+
+    // NOTE: values which are not constant describe infinitely many integers,
+    // so the operators are defined directly instead
+    fn representatives(&self) -> Vec<Int> {
+        match self {
+            Constant::Value(n) => vec![*n],
+            Constant::NotConstant => vec![],
+        }
+    }
+
+    fn join(&self, other: &Self) -> Option<Self> {
+        Some(if self == other {
+            *self
+        } else {
+            Constant::NotConstant
+        })
+    }
+
+    fn binary(&self, op: AOp, other: &Self) -> BTreeSet<Self> {
+        match (self, other) {
+            (Constant::Value(l), Constant::Value(r)) => op
+                .semantic(*l, *r)
+                .ok()
+                .map(Constant::Value)
+                .into_iter()
+                .collect(),
+            _ => [Constant::NotConstant].into(),
+        }
+    }
+
+    fn negate(&self) -> BTreeSet<Self> {
+        match self {
+            Constant::Value(n) => n.checked_neg().map(Constant::Value).into_iter().collect(),
+            Constant::NotConstant => [Constant::NotConstant].into(),
+        }
+    }
+
+    fn relation(&self, op: RelOp, other: &Self) -> Bools {
+        match (self, other) {
+            (Constant::Value(l), Constant::Value(r)) => op.semantic(l, r).into(),
+            _ => Bools::ALL,
+        }
+    }
+
+    fn may_be_index(&self) -> bool {
+        match self {
+            Constant::Value(n) => *n >= 0,
+            Constant::NotConstant => true,
+        }
+    }
+}
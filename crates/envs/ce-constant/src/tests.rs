@@ -0,0 +1,28 @@
+use crate::{Constant, final_values};
+
+#[test]
+fn propagation() {
+    use Constant::*;
+
+    let src = "y := x + 2 ; x := y * y";
+    let x = final_values(src, &[("x", Value(1)), ("y", NotConstant)], "x");
+    assert_eq!(x, [Value(9)].into());
+    let x = final_values("x := x * y", &[("x", Value(1)), ("y", NotConstant)], "x");
+    assert_eq!(x, [NotConstant].into());
+}
+
+#[test]
+fn branches_are_joined() {
+    use Constant::*;
+
+    let src = "if y > 0 -> x := 1 [] y <= 0 -> x := 2 fi";
+    let x = final_values(src, &[("x", Value(0)), ("y", NotConstant)], "x");
+    assert_eq!(x, [NotConstant].into());
+    let src = "if y > 0 -> x := 1 [] y <= 0 -> x := 1 fi";
+    let x = final_values(src, &[("x", Value(0)), ("y", NotConstant)], "x");
+    assert_eq!(x, [Value(1)].into());
+    // NOTE: the loop terminates even though `i` takes infinitely many values
+    let src = "i := 0 ; do i < n -> i := i + 1 od";
+    let i = final_values(src, &[("i", Value(0)), ("n", NotConstant)], "i");
+    assert_eq!(i, [NotConstant].into());
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests;
+
+use ce_sign::domain::AbstractDomain;
+use gcl::ast::Int;
+use serde::{Deserialize, Serialize};
+
+ce_sign::domain_env! {
+    env: FineSignEnv,
+    path: "FineSignAnalysis",
+    domain: FineSign,
+    memory: FineSignMemory,
+    generate: |rng| *FineSign::top().choose(rng).unwrap(),
+}
+
+/// A sign which distinguishes one and minus one from the numbers further
+/// from zero.
+#[derive(
+    tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[tapi(path = "FineSignAnalysis")]
+pub enum FineSign {
+    BelowMinusOne,
+    MinusOne,
+    Zero,
+    One,
+    AboveOne,
+}
+
+impl std::fmt::Display for FineSign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FineSign::BelowMinusOne => write!(f, "<-1"),
+            FineSign::MinusOne => write!(f, "-1"),
+            FineSign::Zero => write!(f, "0"),
+            FineSign::One => write!(f, "1"),
+            FineSign::AboveOne => write!(f, ">1"),
+        }
+    }
+}
+
+impl AbstractDomain for FineSign {
+    fn alpha(n: Int) -> Self {
+        match n {
+            _ if n < -1 => FineSign::BelowMinusOne,
+            -1 => FineSign::MinusOne,
+            0 => FineSign::Zero,
+            1 => FineSign::One,
+            _ => FineSign::AboveOne,
+        }
+    }
+
+    fn top() -> Vec<Self> {
+        vec![
+            FineSign::BelowMinusOne,
+            FineSign::MinusOne,
+            FineSign::Zero,
+            FineSign::One,
+            FineSign::AboveOne,
+        ]
+    }
+
+    // NOTE: `5 / 2` is above one, which no smaller pair of numbers above one
+    // divide into
+    fn representatives(&self) -> Vec<Int> {
+        match self {
+            FineSign::BelowMinusOne => vec![-2, -3, -5],
+            FineSign::MinusOne => vec![-1],
+            FineSign::Zero => vec![0],
+            FineSign::One => vec![1],
+            FineSign::AboveOne => vec![2, 3, 5],
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{FineSign, final_values};
+
+#[test]
+fn multiplication() {
+    use FineSign::*;
+
+    let x = final_values("x := y * y", &[("x", Zero), ("y", MinusOne)], "x");
+    assert_eq!(x, [One].into());
+    let x = final_values(
+        "x := y * z",
+        &[("x", Zero), ("y", MinusOne), ("z", AboveOne)],
+        "x",
+    );
+    assert_eq!(x, [BelowMinusOne].into());
+    let x = final_values(
+        "x := y * z",
+        &[("x", Zero), ("y", One), ("z", AboveOne)],
+        "x",
+    );
+    assert_eq!(x, [AboveOne].into());
+}
+
+#[test]
+fn addition() {
+    use FineSign::*;
+
+    let x = final_values("x := y + 1", &[("x", Zero), ("y", Zero)], "x");
+    assert_eq!(x, [One].into());
+    let x = final_values("x := y - 1", &[("x", Zero), ("y", AboveOne)], "x");
+    assert_eq!(x, [One, AboveOne].into());
+    let x = final_values(
+        "x := y / z",
+        &[("x", Zero), ("y", AboveOne), ("z", AboveOne)],
+        "x",
+    );
+    assert_eq!(x, [Zero, One, AboveOne].into());
+}
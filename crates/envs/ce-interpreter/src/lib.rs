@@ -9,6 +9,7 @@ use ce_core::{
 };
 use gcl::{
     ast::{Commands, Int, TargetDef},
+    integer::IntegerSemantics,
     interpreter::{Execution, InterpreterMemory, Step, TerminationState},
     pg::{Determinism, Node},
 };
@@ -24,6 +25,9 @@ pub struct Input {
     pub determinism: Determinism,
     pub assignment: InterpreterMemory,
     pub trace_length: Int,
+    /// The model of integers arithmetic is evaluated in.
+    #[serde(default)]
+    pub integers: IntegerSemantics,
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -45,13 +49,20 @@ impl Env for InterpreterEnv {
 
     fn meta(input: &Self::Input) -> Self::Meta {
         if let Ok(commands) = input.commands.try_parse() {
-            commands.fv().into_iter().map(|t| t.def()).collect()
+            commands.types().iter().map(|(t, ty)| t.def(*ty)).collect()
         } else {
             Default::default()
         }
     }
 
     fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        input
+            .integers
+            .check()
+            .map_err(ce_core::EnvError::invalid_input_for_program(
+                "unsupported integer semantics",
+            ))?;
+
         let pg =
             gcl::pg::ProgramGraph::new(
                 input.determinism,
@@ -60,7 +71,7 @@ impl Env for InterpreterEnv {
                 )?,
             );
 
-        let mut exe = Execution::new(input.assignment.clone());
+        let mut exe = Execution::with_integers(input.assignment.clone(), input.integers);
 
         for _ in 0..input.trace_length {
             if let Some(next) = exe.nexts(&pg).first().cloned() {
@@ -100,7 +111,10 @@ impl Env for InterpreterEnv {
                     ce_core::EnvError::invalid_input_for_program("failed to parse commands"),
                 )?,
             );
-        let mut possible_executions = vec![Execution::new(input.assignment.clone())];
+        let mut possible_executions = vec![Execution::with_integers(
+            input.assignment.clone(),
+            input.integers,
+        )];
 
         for step in &output.trace {
             possible_executions = possible_executions
@@ -156,21 +170,9 @@ impl Env for InterpreterEnv {
 impl Generate for Input {
     type Context = ();
 
-    fn gn<R: rand::Rng>(_cx: &mut Self::Context, mut rng: &mut R) -> Self {
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
         let commands = gcl::ast::Commands::gn(&mut Default::default(), rng);
-        let initial_memory = gcl::memory::Memory::from_targets_with(
-            commands.fv(),
-            &mut rng,
-            |rng, _| rng.random_range(-10..=10),
-            |rng, _| {
-                let len = rng.random_range(5..=10);
-                (0..len).map(|_| rng.random_range(-10..=10)).collect()
-            },
-        );
-        let assignment = InterpreterMemory {
-            variables: initial_memory.variables,
-            arrays: initial_memory.arrays,
-        };
+        let assignment = ce_core::gn::interpreter_memory(&commands, rng);
 
         let determinism = *[Determinism::Deterministic, Determinism::NonDeterministic]
             .choose(rng)
@@ -181,6 +183,7 @@ impl Generate for Input {
             determinism,
             assignment,
             trace_length: rng.random_range(10..=15),
+            integers: IntegerSemantics::default(),
         }
     }
 }
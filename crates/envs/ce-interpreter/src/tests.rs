@@ -1,9 +1,15 @@
-use ce_core::{Env, ValidationResult};
+use ce_core::{
+    Env, Generate, ValidationResult,
+    rand::{SeedableRng, rngs::SmallRng},
+};
 use gcl::{
-    ast::Variable,
-    interpreter::TerminationState,
-    pg::{Determinism, Node},
+    ast::{Commands, Int, Variable},
+    integer::{Integer, IntegerSemantics},
+    interpreter::{Execution, TerminationState},
+    pg::{Determinism, Node, ProgramGraph},
+    typeck::Type,
 };
+use itertools::Itertools;
 use stdx::stringify::Stringify;
 
 use crate::{Input, InterpreterEnv, InterpreterMemory, Output};
@@ -15,6 +21,7 @@ fn initially_stuck_program() {
         determinism: Determinism::Deterministic,
         assignment: Default::default(),
         trace_length: 1,
+        integers: Default::default(),
     };
     let output = InterpreterEnv::run(&input).unwrap();
     match InterpreterEnv::validate(&input, &output).unwrap() {
@@ -37,16 +44,18 @@ fn test_true_skip() {
         determinism: Determinism::Deterministic,
         assignment: InterpreterMemory {
             variables: [
-                (Variable("a".to_string()), -8),
-                (Variable("b".to_string()), -9),
-                (Variable("c".to_string()), -3),
-                (Variable("d".to_string()), -6),
+                (Variable("a".to_string()), Integer::from(-8)),
+                (Variable("b".to_string()), Integer::from(-9)),
+                (Variable("c".to_string()), Integer::from(-3)),
+                (Variable("d".to_string()), Integer::from(-6)),
             ]
             .into_iter()
             .collect(),
             arrays: Default::default(),
+            ..Default::default()
         },
         trace_length: 11,
+        integers: Default::default(),
     };
     let output = InterpreterEnv::run(&input).unwrap();
     match InterpreterEnv::validate(&input, &output).unwrap() {
@@ -74,16 +83,18 @@ fn test_thingy() {
         determinism: Determinism::Deterministic,
         assignment: InterpreterMemory {
             variables: [
-                (Variable("a".to_string()), -8),
-                (Variable("b".to_string()), -9),
-                (Variable("c".to_string()), -3),
-                (Variable("d".to_string()), -6),
+                (Variable("a".to_string()), Integer::from(-8)),
+                (Variable("b".to_string()), Integer::from(-9)),
+                (Variable("c".to_string()), Integer::from(-3)),
+                (Variable("d".to_string()), Integer::from(-6)),
             ]
             .into_iter()
             .collect(),
             arrays: Default::default(),
+            ..Default::default()
         },
         trace_length: 11,
+        integers: Default::default(),
     };
     let output = InterpreterEnv::run(&input).unwrap();
     match InterpreterEnv::validate(&input, &output).unwrap() {
@@ -106,10 +117,14 @@ fn empty_trace_running() {
         commands,
         determinism: Determinism::Deterministic,
         assignment: InterpreterMemory {
-            variables: [(Variable("x".to_string()), 0)].into_iter().collect(),
+            variables: [(Variable("x".to_string()), Integer::from(0))]
+                .into_iter()
+                .collect(),
             arrays: Default::default(),
+            ..Default::default()
         },
         trace_length: 1,
+        integers: Default::default(),
     };
     let output = Output {
         initial_node: Node::Start.to_string(),
@@ -140,10 +155,14 @@ fn empty_trace_terminated() {
         commands,
         determinism: Determinism::Deterministic,
         assignment: InterpreterMemory {
-            variables: [(Variable("x".to_string()), 0)].into_iter().collect(),
+            variables: [(Variable("x".to_string()), Integer::from(0))]
+                .into_iter()
+                .collect(),
             arrays: Default::default(),
+            ..Default::default()
         },
         trace_length: 1,
+        integers: Default::default(),
     };
     let output = Output {
         initial_node: Node::Start.to_string(),
@@ -161,5 +180,308 @@ fn empty_trace_terminated() {
     );
 }
 
+#[test]
+fn boolean_variables() {
+    let commands = Stringify::Unparsed(
+        r#"
+            done := x > 0;
+            do !done -> x := x + 1; done := x > 0 od;
+            b := done
+        "#
+        .to_string(),
+    );
+
+    let input = Input {
+        commands,
+        determinism: Determinism::Deterministic,
+        assignment: InterpreterMemory {
+            variables: [(Variable("x".to_string()), Integer::from(-1))]
+                .into_iter()
+                .collect(),
+            bool_variables: [
+                (Variable("done".to_string()), false),
+                (Variable("b".to_string()), false),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+        trace_length: 20,
+        integers: Default::default(),
+    };
+    let output = InterpreterEnv::run(&input).unwrap();
+    assert_eq!(output.termination, TerminationState::Terminated);
+    let last = &output.trace.last().unwrap().memory;
+    assert_eq!(last.variables[&Variable("x".to_string())], 1);
+    assert!(last.bool_variables[&Variable("b".to_string())]);
+    assert_eq!(
+        InterpreterEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+}
+
+#[test]
+fn recursive_procedure() {
+    let commands = Stringify::Unparsed(
+        r#"
+            proc fib(n; r)
+                if n < 2 -> r := n
+                [] n >= 2 -> call fib(n - 1; r); call fib(n - 2; n); r := r + n
+                fi
+            end;
+            n := 0;
+            call fib(x; y)
+        "#
+        .to_string(),
+    );
+
+    let input = Input {
+        commands,
+        determinism: Determinism::Deterministic,
+        assignment: InterpreterMemory {
+            variables: [
+                (Variable("n".to_string()), Integer::from(0)),
+                (Variable("x".to_string()), Integer::from(6)),
+                (Variable("y".to_string()), Integer::from(0)),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+        trace_length: 1000,
+        integers: Default::default(),
+    };
+    let output = InterpreterEnv::run(&input).unwrap();
+    assert_eq!(output.termination, TerminationState::Terminated);
+    let last = &output.trace.last().unwrap().memory;
+    assert_eq!(last.variables[&Variable("y".to_string())], 8);
+    assert!(!last.variables.contains_key(&Variable("r".to_string())));
+    assert_eq!(
+        InterpreterEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+}
+
+#[test]
+fn integer_semantics() {
+    let run = |integers| {
+        let input = Input {
+            commands: Stringify::Unparsed("x := 2 ^ 31 - 1; x := x + 1".to_string()),
+            determinism: Determinism::Deterministic,
+            assignment: InterpreterMemory {
+                variables: [(Variable("x".to_string()), Integer::from(0))]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            trace_length: 10,
+            integers,
+        };
+        let output = InterpreterEnv::run(&input).unwrap();
+        assert_eq!(
+            InterpreterEnv::validate(&input, &output).unwrap(),
+            ValidationResult::Correct
+        );
+        output
+    };
+
+    let output = run(IntegerSemantics::Checked);
+    assert_eq!(output.termination, TerminationState::Stuck);
+
+    let output = run(IntegerSemantics::Wrapping { bits: 32 });
+    assert_eq!(output.termination, TerminationState::Terminated);
+    let last = &output.trace.last().unwrap().memory;
+    assert_eq!(last.variables[&Variable("x".to_string())], i32::MIN);
+
+    let output = run(IntegerSemantics::Wrapping { bits: 8 });
+    let last = &output.trace.last().unwrap().memory;
+    assert_eq!(last.variables[&Variable("x".to_string())], 0);
+
+    let output = run(IntegerSemantics::Unbounded);
+    assert_eq!(output.termination, TerminationState::Terminated);
+    let last = &output.trace.last().unwrap().memory;
+    assert_eq!(
+        last.variables[&Variable("x".to_string())],
+        "2147483648".parse::<Integer>().unwrap()
+    );
+}
+
+#[test]
+fn unbounded_integers_are_limited_in_size() {
+    let input = Input {
+        commands: Stringify::Unparsed("x := 3 ; do true -> x := x * x od".to_string()),
+        determinism: Determinism::Deterministic,
+        assignment: InterpreterMemory {
+            variables: [(Variable("x".to_string()), Integer::from(0))]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        },
+        trace_length: 80,
+        integers: IntegerSemantics::Unbounded,
+    };
+    let output = InterpreterEnv::run(&input).unwrap();
+    assert_eq!(output.termination, TerminationState::Stuck);
+    assert_eq!(
+        InterpreterEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+}
+
+#[test]
+fn unsupported_bit_widths_are_rejected() {
+    for bits in [0, 65, 4_000_000_000] {
+        let input = Input {
+            commands: Stringify::Unparsed("x := 2 ^ 3".to_string()),
+            determinism: Determinism::Deterministic,
+            assignment: Default::default(),
+            trace_length: 10,
+            integers: IntegerSemantics::Wrapping { bits },
+        };
+        assert!(
+            matches!(
+                InterpreterEnv::run(&input),
+                Err(ce_core::EnvError::InvalidInputForProgram { .. })
+            ),
+            "{bits} bits"
+        );
+    }
+    assert_eq!(
+        IntegerSemantics::Wrapping {
+            bits: 4_000_000_000
+        }
+        .binary(gcl::ast::AOp::Pow, &Integer::from(2), &Integer::from(3)),
+        Err(gcl::semantics::SemanticsError::UnsupportedBitWidth {
+            bits: 4_000_000_000
+        })
+    );
+}
+
 #[test]
 fn mutation_of_valid_trace() {}
+
+#[test]
+fn exploration() {
+    let explore = |src: &str, x: Int, bound| {
+        let commands: Commands = src.parse().unwrap();
+        let pg = ProgramGraph::new(Determinism::NonDeterministic, &commands);
+        let memory = InterpreterMemory {
+            variables: [
+                (Variable("x".to_string()), Integer::from(x)),
+                (Variable("y".to_string()), Integer::from(0)),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        Execution::new(memory).explore(&pg, bound)
+    };
+    let x = |mem: &InterpreterMemory| mem.variables[&Variable("x".to_string())].clone();
+
+    let exploration = explore(
+        "if true -> x := 1 [] true -> x := 2 fi; if x = 2 -> y := x fi",
+        0,
+        100,
+    );
+    assert!(exploration.complete);
+    assert_eq!(exploration.final_memories().map(x).collect_vec(), [2]);
+    assert_eq!(exploration.terminated[0].trace().len(), 4);
+    assert_eq!(exploration.stuck.len(), 1);
+    assert_eq!(x(exploration.stuck[0].current_mem()), 1);
+
+    // NOTE: revisited configurations are only explored once
+    let exploration = explore("do x > 0 -> x := x - 1 [] x > 0 -> skip od", 3, 100);
+    assert!(exploration.complete);
+    assert_eq!(exploration.final_memories().map(x).collect_vec(), [0]);
+
+    let exploration = explore("do true -> x := x + 1 od", 0, 100);
+    assert!(!exploration.complete);
+    assert_eq!(exploration.visited, 100);
+    assert!(exploration.terminated.is_empty() && exploration.stuck.is_empty());
+}
+
+#[test]
+fn parallel_composition() {
+    let explore = |src: &str| {
+        let commands: Commands = src.parse().unwrap();
+        let printed = commands.to_string();
+        assert_eq!(printed.parse::<Commands>().unwrap().to_string(), printed);
+        let pg = ProgramGraph::new(Determinism::Deterministic, &commands);
+        let memory = InterpreterMemory {
+            variables: commands
+                .fv()
+                .into_iter()
+                .filter_map(|t| match t {
+                    gcl::ast::Target::Variable(v) => Some((v, Integer::from(0))),
+                    gcl::ast::Target::Array(..) => None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let exploration = Execution::new(memory).explore(&pg, 10_000);
+        assert!(exploration.complete);
+        exploration
+    };
+    let finals = |exploration: &gcl::interpreter::Exploration, var: &str| {
+        exploration
+            .final_memories()
+            .map(|mem| mem.variables[&Variable(var.to_string())].clone())
+            .sorted()
+            .dedup()
+            .collect_vec()
+    };
+
+    let exploration = explore("par x := x + 1 [] x := x + 2 rap");
+    assert_eq!(finals(&exploration, "x"), [3]);
+
+    // NOTE: the read and the write of each component may interleave
+    let exploration = explore("par t := x; x := t + 1 [] u := x; x := u + 1 rap");
+    assert_eq!(finals(&exploration, "x"), [1, 2]);
+
+    // Peterson's algorithm, where `c` counts the components in their
+    // critical section
+    let exploration = explore(
+        r#"
+        par
+            f0 := 1; turn := 1;
+            if f1 = 0 | turn = 0 -> skip fi;
+            c := c + 1; m := m + c; c := c - 1;
+            f0 := 0
+        []
+            f1 := 1; turn := 0;
+            if f0 = 0 | turn = 1 -> skip fi;
+            c := c + 1; m := m + c; c := c - 1;
+            f1 := 0
+        rap
+        "#,
+    );
+    assert!(exploration.stuck.is_empty());
+    assert_eq!(finals(&exploration, "m"), [2]);
+
+    let exploration = explore("par if f = 1 -> skip fi; g := 1 [] if g = 1 -> skip fi; f := 1 rap");
+    assert!(exploration.terminated.is_empty());
+    assert_eq!(exploration.stuck.len(), 1);
+
+    assert!(
+        "proc p() skip end; par call p() [] skip rap"
+            .parse::<Commands>()
+            .is_err()
+    );
+}
+
+#[test]
+fn generated_programs_have_no_booleans() {
+    // NOTE: booleans are opt-in for generators, so existing implementations
+    // keep receiving programs they can parse
+    let mut rng = SmallRng::seed_from_u64(0xCEC34);
+    for _ in 0..100 {
+        let input = Input::gn(&mut (), &mut rng);
+        let commands = input.commands.try_parse().unwrap();
+        assert!(
+            commands.types().values().all(|ty| *ty == Type::Int),
+            "{commands}"
+        );
+        assert!(input.assignment.bool_variables.is_empty());
+        assert!(input.assignment.bool_arrays.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ce_automata::{ALPHABET, Dfa, Nfa, fmt_word};
+use ce_core::{Env, EnvError, Generate, ValidationResult, define_env, rand};
+use serde::{Deserialize, Serialize};
+
+define_env!(MinimizationEnv);
+
+#[derive(tapi::Tapi, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Minimization")]
+pub struct Input {
+    /// The automaton to minimize, written in dot as in the automata env, or
+    /// as a [`JsonDfa`].
+    pub automaton: String,
+}
+
+/// The JSON form of a deterministic automaton with initial state `0`, where
+/// `transitions[s]` maps each symbol to the state it leads to from `s`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonDfa {
+    pub transitions: Vec<BTreeMap<char, usize>>,
+    #[serde(default)]
+    pub accepting: BTreeSet<usize>,
+}
+
+impl JsonDfa {
+    fn dfa(self) -> Result<Dfa, String> {
+        let states = self.transitions.len();
+        if states == 0 {
+            return Err("the automaton has no states".to_string());
+        }
+        for (c, to) in self.transitions.iter().flatten() {
+            if !c.is_ascii() || !ALPHABET.contains(&(*c as u8)) {
+                return Err(format!("'{c}' is not a symbol of the alphabet"));
+            }
+            if *to >= states {
+                return Err(format!("there is no state {to}"));
+            }
+        }
+        if let Some(s) = self.accepting.iter().find(|s| **s >= states) {
+            return Err(format!("there is no state {s}"));
+        }
+        Ok(Dfa::new(self.transitions, self.accepting))
+    }
+}
+
+#[derive(tapi::Tapi, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Minimization")]
+pub struct Output {
+    /// The minimal deterministic automaton accepting the same words.
+    pub dot: String,
+}
+
+impl Input {
+    /// The minimal deterministic automaton accepting the same words as the
+    /// input automaton.
+    pub fn minimal(&self) -> ce_core::Result<Dfa> {
+        let invalid = |err: String| EnvError::InvalidInputForProgram {
+            message: format!("failed to read automaton: {err}"),
+            source: None,
+        };
+        // NOTE: a dot graph never starts with `{`, so anything which does is
+        // read as JSON
+        let dfa = if self.automaton.trim_start().starts_with('{') {
+            serde_json::from_str::<JsonDfa>(&self.automaton)
+                .map_err(|err| err.to_string())
+                .and_then(JsonDfa::dfa)
+                .map_err(invalid)?
+        } else {
+            Nfa::from_dot(&self.automaton)
+                .map_err(invalid)?
+                .determinize()
+        };
+        Ok(dfa.minimize())
+    }
+}
+
+impl Env for MinimizationEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = ();
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        Ok(Output {
+            dot: input.minimal()?.dot(),
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let reference = input.minimal()?;
+        let actual = match Nfa::from_dot(&output.dot) {
+            Ok(nfa) => nfa,
+            Err(err) => {
+                return Ok(ValidationResult::Mismatch {
+                    reason: format!("failed to read automaton: {err}"),
+                });
+            }
+        };
+
+        if !actual.is_deterministic() {
+            return Ok(ValidationResult::Mismatch {
+                reason: "the automaton is not deterministic".to_string(),
+            });
+        }
+
+        if let Some(word) = actual.determinize().distinguishing_word(&reference) {
+            let (accepted, rejected) = if reference.accepts(&word) {
+                ("rejects", "accepts")
+            } else {
+                ("accepts", "rejects")
+            };
+            return Ok(ValidationResult::Mismatch {
+                reason: format!(
+                    "the automaton {accepted} {}, which the input automaton {rejected}",
+                    fmt_word(&word)
+                ),
+            });
+        }
+
+        // NOTE: an explicit dead state is allowed, since the minimal
+        // automaton leaves out transitions into it
+        let states = actual.len() - actual.dead_states().min(1);
+        let minimal = reference.len() - reference.dead_states();
+        if states > minimal {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!(
+                    "the automaton has {states} states, but the minimal automaton has {minimal}"
+                ),
+            });
+        }
+
+        Ok(ValidationResult::Correct)
+    }
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let states = rng.random_range(2..=7);
+        let transitions = (0..states)
+            .map(|_| {
+                let mut transitions = BTreeMap::new();
+                for c in ['a', 'b'] {
+                    if rng.random_bool(0.9) {
+                        transitions.insert(c, rng.random_range(0..states));
+                    }
+                }
+                transitions
+            })
+            .collect();
+        let accepting: BTreeSet<usize> = (0..states).filter(|_| rng.random_bool(0.4)).collect();
+
+        Input {
+            automaton: Dfa::new(transitions, accepting).dot(),
+        }
+    }
+}
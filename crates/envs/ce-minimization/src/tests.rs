@@ -0,0 +1,210 @@
+use ce_core::{Env, ValidationResult};
+
+use crate::{Input, MinimizationEnv, Output};
+
+/// Accepts the words over `a` and `b` ending in `b`, with the states `1` and
+/// `3` and the states `2` and `4` being equivalent.
+const REDUNDANT: &str = r#"
+    digraph Automaton {
+        "0" [initial=true];
+        "2" [accepting=true];
+        "4" [accepting=true];
+        "0" -> "1" [label="a"];
+        "0" -> "2" [label="b"];
+        "1" -> "3" [label="a"];
+        "1" -> "4" [label="b"];
+        "2" -> "1" [label="a"];
+        "2" -> "4" [label="b"];
+        "3" -> "3" [label="a"];
+        "3" -> "2" [label="b"];
+        "4" -> "3" [label="a"];
+        "4" -> "2" [label="b"];
+    }
+"#;
+
+fn validate(automaton: &str, dot: &str) -> ValidationResult {
+    let input = Input {
+        automaton: automaton.to_string(),
+    };
+    let output = Output {
+        dot: dot.to_string(),
+    };
+    MinimizationEnv::validate(&input, &output).unwrap()
+}
+
+#[test]
+fn merges_equivalent_states() {
+    let input = Input {
+        automaton: REDUNDANT.to_string(),
+    };
+    let minimal = input.minimal().unwrap();
+    // NOTE: the initial state is equivalent to the states after an `a`
+    assert_eq!(minimal.len(), 2);
+    assert!(minimal.accepts("abab"));
+    assert!(!minimal.accepts("aba"));
+}
+
+#[test]
+fn json_automata_are_minimized() {
+    // NOTE: the same automaton as `REDUNDANT`
+    let input = Input {
+        automaton: r#"{
+            "transitions": [
+                { "a": 1, "b": 2 },
+                { "a": 3, "b": 4 },
+                { "a": 1, "b": 4 },
+                { "a": 3, "b": 2 },
+                { "a": 3, "b": 2 }
+            ],
+            "accepting": [2, 4]
+        }"#
+        .to_string(),
+    };
+    let minimal = input.minimal().unwrap();
+    assert_eq!(minimal.len(), 2);
+    assert!(
+        minimal.equivalent(
+            &Input {
+                automaton: REDUNDANT.to_string()
+            }
+            .minimal()
+            .unwrap()
+        )
+    );
+
+    for automaton in [
+        r#"{ "transitions": [] }"#,
+        r#"{ "transitions": [{ "a": 1 }] }"#,
+        r#"{ "transitions": [{ "a": 0 }], "accepting": [1] }"#,
+        r#"{ "transitions": [{ "!": 0 }] }"#,
+    ] {
+        let input = Input {
+            automaton: automaton.to_string(),
+        };
+        assert!(
+            matches!(
+                input.minimal(),
+                Err(ce_core::EnvError::InvalidInputForProgram { .. })
+            ),
+            "{automaton}"
+        );
+    }
+}
+
+#[test]
+fn minimal_automaton_is_accepted() {
+    let dot = r#"
+        digraph {
+            "x" [initial=true];
+            "y" [accepting=true];
+            "x" -> "x" [label="a"];
+            "x" -> "y" [label="b"];
+            "y" -> "x" [label="a"];
+            "y" -> "y" [label="b"];
+        }
+    "#;
+    assert_eq!(validate(REDUNDANT, dot), ValidationResult::Correct);
+}
+
+#[test]
+fn wrong_language_gives_word() {
+    let dot = r#"
+        digraph {
+            "x" [initial=true];
+            "y" [accepting=true];
+            "x" -> "x" [label="a"];
+            "x" -> "y" [label="b"];
+            "y" -> "y" [label="b"];
+        }
+    "#;
+    match validate(REDUNDANT, dot) {
+        ValidationResult::Mismatch { reason } => {
+            assert!(reason.contains("rejects \"bab\""), "{reason}")
+        }
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+}
+
+#[test]
+fn unminimized_automaton_is_rejected() {
+    match validate(REDUNDANT, REDUNDANT) {
+        ValidationResult::Mismatch { reason } => assert!(reason.contains("5 states"), "{reason}"),
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+}
+
+#[test]
+fn explicit_dead_state_is_allowed() {
+    let automaton = r#"
+        digraph {
+            "0" [initial=true];
+            "1" [accepting=true];
+            "0" -> "1" [label="a"];
+            "1" -> "2" [label="a"];
+            "2" -> "3" [label="b"];
+            "3" -> "2" [label="b"];
+        }
+    "#;
+    let dot = r#"
+        digraph {
+            "0" [initial=true];
+            "1" [accepting=true];
+            "0" -> "1" [label="a"];
+            "0" -> "dead" [label="b"];
+            "1" -> "dead" [label="a"];
+            "1" -> "dead" [label="b"];
+        }
+    "#;
+    assert_eq!(validate(automaton, dot), ValidationResult::Correct);
+}
+
+#[test]
+fn nondeterministic_input_is_determinized() {
+    // NOTE: the words containing `ab`
+    let automaton = r#"
+        digraph {
+            "0" [initial=true];
+            "2" [accepting=true];
+            "0" -> "0" [label="a"];
+            "0" -> "0" [label="b"];
+            "0" -> "1" [label="a"];
+            "1" -> "2" [label="b"];
+            "2" -> "2" [label="a"];
+            "2" -> "2" [label="b"];
+        }
+    "#;
+    let input = Input {
+        automaton: automaton.to_string(),
+    };
+    let output = MinimizationEnv::run(&input).unwrap();
+    assert_eq!(input.minimal().unwrap().len(), 3);
+    assert_eq!(
+        MinimizationEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+    match validate(automaton, automaton) {
+        ValidationResult::Mismatch { reason } => {
+            assert!(reason.contains("not deterministic"), "{reason}")
+        }
+        result => panic!("expected mismatch, got {result:?}"),
+    }
+}
+
+#[test]
+fn empty_language() {
+    let automaton = r#"
+        digraph {
+            "0" [initial=true, accepting=false];
+            "0" -> "1" [label="a"];
+        }
+    "#;
+    let input = Input {
+        automaton: automaton.to_string(),
+    };
+    let output = MinimizationEnv::run(&input).unwrap();
+    assert_eq!(input.minimal().unwrap().len(), 1);
+    assert_eq!(
+        MinimizationEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests;
+
+use ce_core::{Env, Generate, ValidationResult, define_env};
+use gcl::{
+    ast::Commands,
+    interpreter::Execution,
+    pg::{Determinism, ProgramGraph},
+};
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use stdx::stringify::Stringify;
+
+define_env!(OptimizerEnv);
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Optimizer")]
+pub struct Input {
+    pub commands: Stringify<Commands>,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Optimizer")]
+pub struct Output {
+    pub optimized: Stringify<Commands>,
+}
+
+/// The number of memories the optimized program is executed on.
+const SAMPLES: usize = 10;
+/// The number of configurations after which an execution is considered
+/// inconclusive.
+const BOUND: usize = 500;
+
+impl Env for OptimizerEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = ();
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        Ok(Output {
+            optimized: Stringify::new(commands.optimize()),
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        let optimized = match output.optimized.try_parse() {
+            Ok(optimized) => optimized,
+            Err(err) => {
+                return Ok(ValidationResult::Mismatch {
+                    reason: format!("failed to parse optimized program: {err}"),
+                });
+            }
+        };
+
+        let reference = commands.optimize();
+        if optimized.size() > reference.size() {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!(
+                    "the optimized program has size {}, but could be reduced to size {}",
+                    optimized.size(),
+                    reference.size()
+                ),
+            });
+        }
+
+        Ok(match find_difference(&commands, &optimized) {
+            Some(reason) => ValidationResult::Mismatch { reason },
+            None => ValidationResult::Correct,
+        })
+    }
+}
+
+/// Executes both programs on random memories, and describes the first memory
+/// on which their outcomes differ.
+fn find_difference(original: &Commands, optimized: &Commands) -> Option<String> {
+    let pg1 = ProgramGraph::new(Determinism::NonDeterministic, original);
+    let pg2 = ProgramGraph::new(Determinism::NonDeterministic, optimized);
+
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(0xCEC34);
+
+    for _ in 0..SAMPLES {
+        let memory = ce_core::gn::interpreter_memory(original, &mut rng);
+        let exe = Execution::new(memory.clone());
+        // NOTE: executions with too many configurations are inconclusive
+        let (Some(expected), Some(found)) = (exe.outcomes(&pg1, BOUND), exe.outcomes(&pg2, BOUND))
+        else {
+            continue;
+        };
+        if expected != found {
+            return Some(format!(
+                "the programs have different outcomes when starting in {memory:?}: \
+                 expected {expected:?}, found {found:?}"
+            ));
+        }
+    }
+
+    None
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        Self {
+            commands: Stringify::new(Commands::gn(&mut Default::default(), rng)),
+        }
+    }
+}
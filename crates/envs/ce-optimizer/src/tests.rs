@@ -0,0 +1,85 @@
+use ce_core::{Env, Generate, ValidationResult};
+use gcl::ast::Commands;
+use rand::SeedableRng;
+use stdx::stringify::Stringify;
+
+use crate::{Input, OptimizerEnv, Output, find_difference};
+
+#[track_caller]
+fn assert_optimizes(src: &str, expected: &str) {
+    assert_eq!(
+        src.parse::<Commands>().unwrap().optimize().to_string(),
+        expected.parse::<Commands>().unwrap().to_string()
+    );
+}
+
+fn validate(src: &str, optimized: &str) -> ValidationResult {
+    let input = Input {
+        commands: Stringify::Unparsed(src.to_string()),
+    };
+    let output = Output {
+        optimized: Stringify::Unparsed(optimized.to_string()),
+    };
+    OptimizerEnv::validate(&input, &output).unwrap()
+}
+
+#[test]
+fn constant_folding() {
+    assert_optimizes("x := 2 * 3 + y * 1", "x := 6 + y");
+    assert_optimizes("x := (y - y) + z * 0", "x := 0");
+    // NOTE: failing subexpressions are kept
+    assert_optimizes("x := 1 / 0 * 0", "x := 1 / 0 * 0");
+    assert_optimizes("x := a[3] * 0", "x := a[3] * 0");
+}
+
+#[test]
+fn dead_guards() {
+    assert_optimizes(
+        "if 1 > 2 -> x := 1 [] y > 0 -> x := 2 fi",
+        "if y > 0 -> x := 2 fi",
+    );
+    assert_optimizes("if true -> x := 1 fi; do false -> x := 2 od", "x := 1");
+    assert_optimizes("if false -> x := 1 fi", "if false -> skip fi");
+}
+
+#[test]
+fn copy_propagation() {
+    assert_optimizes("x := 2; y := x; z := y + x", "x := 2; y := 2; z := 4");
+    // NOTE: facts about `x` do not survive its reassignment in the loop
+    assert_optimizes(
+        "x := 0; do x < 10 -> x := x + 1 od; y := x",
+        "x := 0; do x < 10 -> x := x + 1 od; y := x",
+    );
+    assert_optimizes(
+        "if c -> x := 1 [] !c -> x := 1 fi; y := x",
+        "if c -> x := 1 [] !c -> x := 1 fi; y := 1",
+    );
+}
+
+#[test]
+fn validation() {
+    let src = "x := 2 + 3; if x > 4 -> y := x fi";
+    assert_eq!(validate(src, "x := 5; y := 5"), ValidationResult::Correct);
+    assert!(matches!(
+        validate(src, "x := 5; y := 4"),
+        ValidationResult::Mismatch { .. }
+    ));
+    assert!(matches!(
+        validate(src, "x := 5; if x > 4 -> y := x fi"),
+        ValidationResult::Mismatch { .. }
+    ));
+}
+
+#[test]
+fn optimization_preserves_outcomes() {
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(0xCEC34);
+    for _ in 0..200 {
+        let commands = Commands::gn(&mut Default::default(), &mut rng);
+        let optimized = commands.optimize();
+        if let Some(reason) = find_difference(&commands, &optimized) {
+            panic!("{commands}  optimized to  {optimized}  {reason}");
+        }
+        let reparsed: Commands = optimized.to_string().parse().unwrap();
+        assert_eq!(reparsed.optimize().to_string(), optimized.to_string());
+    }
+}
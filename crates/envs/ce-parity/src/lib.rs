@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests;
+
+use ce_sign::domain::AbstractDomain;
+use gcl::ast::Int;
+use serde::{Deserialize, Serialize};
+
+ce_sign::domain_env! {
+    env: ParityEnv,
+    path: "ParityAnalysis",
+    domain: Parity,
+    memory: ParityMemory,
+    generate: |rng| *Parity::top().choose(rng).unwrap(),
+}
+
+#[derive(
+    tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[tapi(path = "ParityAnalysis")]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl AbstractDomain for Parity {
+    fn alpha(n: Int) -> Self {
+        if n % 2 == 0 {
+            Parity::Even
+        } else {
+            Parity::Odd
+        }
+    }
+
+    fn top() -> Vec<Self> {
+        vec![Parity::Even, Parity::Odd]
+    }
+
+    // NOTE: `4 / 3` is odd, which no smaller even number divided by an odd
+    // number is
+    fn representatives(&self) -> Vec<Int> {
+        match self {
+            Parity::Even => vec![-2, 0, 2, 4],
+            Parity::Odd => vec![-1, 1, 3],
+        }
+    }
+}
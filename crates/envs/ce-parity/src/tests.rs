@@ -0,0 +1,38 @@
+use crate::{Parity, final_values};
+
+#[test]
+fn arithmetic() {
+    use Parity::*;
+
+    let odd = final_values("x := 2 * y + 1", &[("x", Even), ("y", Odd)], "x");
+    assert_eq!(odd, [Odd].into());
+    let square = final_values("x := y ^ 2", &[("x", Even), ("y", Odd)], "x");
+    assert_eq!(square, [Odd].into());
+    // NOTE: division truncates, so it may not preserve the parity
+    let divided = final_values("x := y / 3", &[("x", Even), ("y", Even)], "x");
+    assert_eq!(divided, [Even, Odd].into());
+}
+
+#[test]
+fn loops() {
+    use Parity::*;
+
+    let src = "i := 0 ; do i < n -> i := i + 2 od";
+    let i = final_values(src, &[("i", Odd), ("n", Odd)], "i");
+    assert_eq!(i, [Even].into());
+    let src = "i := 0 ; do i < n -> i := i + 1 od";
+    let i = final_values(src, &[("i", Odd), ("n", Odd)], "i");
+    assert_eq!(i, [Even, Odd].into());
+}
+
+#[test]
+fn procedures() {
+    use Parity::*;
+
+    // NOTE: each call returns the results of its own arguments
+    let src = "proc inc(n; m) m := n + 1 end ; call inc(x; y) ; call inc(y; z)";
+    let variables = [("x", Even), ("y", Even), ("z", Odd)];
+    assert_eq!(final_values(src, &variables, "y"), [Odd].into());
+    assert_eq!(final_values(src, &variables, "z"), [Even].into());
+    assert_eq!(final_values(src, &variables, "x"), [Even].into());
+}
@@ -1,5 +1,5 @@
 use ce_core::{Env, Generate, ValidationResult, define_env, rand};
-use gcl::{ast::Commands, interpreter::InterpreterMemory};
+use gcl::ast::Commands;
 use serde::{Deserialize, Serialize};
 use stdx::stringify::Stringify;
 
@@ -79,7 +79,7 @@ fn check_programs_for_semantic_equivalence(p1: &Commands, p2: &Commands) -> bool
     let mut rng = <rand::rngs::SmallRng as rand::SeedableRng>::seed_from_u64(0xCEC34);
 
     for _ in 0..n_samples {
-        let assignment = generate_input_assignment(p1, &mut rng);
+        let assignment = ce_core::gn::interpreter_memory(p1, &mut rng);
 
         let mut exe1 = gcl::interpreter::Execution::new(assignment.clone());
         let mut exe2 = gcl::interpreter::Execution::new(assignment.clone());
@@ -104,21 +104,3 @@ fn check_programs_for_semantic_equivalence(p1: &Commands, p2: &Commands) -> bool
     true
 }
 
-fn generate_input_assignment(
-    commands: &gcl::ast::Commands,
-    mut rng: &mut impl rand::Rng,
-) -> InterpreterMemory {
-    let initial_memory = gcl::memory::Memory::from_targets_with(
-        commands.fv(),
-        &mut rng,
-        |rng, _| rng.random_range(-10..=10),
-        |rng, _| {
-            let len = rng.random_range(5..=10);
-            (0..len).map(|_| rng.random_range(-10..=10)).collect()
-        },
-    );
-    InterpreterMemory {
-        variables: initial_memory.variables,
-        arrays: initial_memory.arrays,
-    }
-}
@@ -1,8 +1,11 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use gcl::ast::{Command, Commands, Guard, Target};
+use gcl::ast::{
+    AExpr, AExprKind, BExpr, BExprKind, Command, CommandKind, Commands, Guard, Int, Target,
+};
 use itertools::{Itertools, chain};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{Flow, flow};
 
@@ -12,6 +15,16 @@ pub struct SecurityLattice {
     pub allowed: BTreeSet<Flow>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LatticeError {
+    #[error(
+        "`{a}` and `{b}` are distinct but flow into each other, so the flows are not a partial order"
+    )]
+    NotAntisymmetric { a: String, b: String },
+    #[error("`{a}` and `{b}` have no least upper bound")]
+    NoJoin { a: String, b: String },
+}
+
 impl SecurityLattice {
     pub fn new(rules: &[Flow]) -> Self {
         let mut allowed: BTreeSet<Flow> = rules.iter().cloned().collect();
@@ -47,51 +60,223 @@ impl SecurityLattice {
         f.from == f.into || self.allowed.contains(f)
     }
 
+    /// The security classes mentioned by the flows.
+    pub fn classes(&self) -> BTreeSet<&str> {
+        self.allowed
+            .iter()
+            .flat_map(|f| [f.from.as_str(), f.into.as_str()])
+            .collect()
+    }
+
+    /// The least class both `a` and `b` may flow into, if any.
+    pub fn join<'a>(&'a self, a: &str, b: &str) -> Option<&'a str> {
+        let upper = self
+            .classes()
+            .into_iter()
+            .filter(|c| self.allows(&flow(a, c)) && self.allows(&flow(b, c)))
+            .collect_vec();
+        upper
+            .iter()
+            .find(|u| upper.iter().all(|c| self.allows(&flow(u, c))))
+            .copied()
+    }
+
+    /// The lattice with a class added above the classes which flow into no
+    /// other class, if there are several, such that they have a join. The
+    /// added class is named by joining their names with `_`.
+    pub fn with_top(mut self) -> Self {
+        let classes = self.classes();
+        let maximal = classes
+            .iter()
+            .filter(|c| !self.allowed.iter().any(|f| f.from == **c && f.into != **c))
+            .map(|c| c.to_string())
+            .collect_vec();
+        if maximal.len() < 2 {
+            return self;
+        }
+        let top = maximal.iter().join("_");
+        let below = classes.iter().map(|c| flow(c, &top)).collect_vec();
+        self.allowed.extend(below);
+        self
+    }
+
+    /// Checks that the flows are a partial order in which every two classes
+    /// have a least upper bound.
+    pub fn validate(&self) -> Result<(), LatticeError> {
+        for f in &self.allowed {
+            if f.from != f.into && self.allows(&flow(&f.into, &f.from)) {
+                return Err(LatticeError::NotAntisymmetric {
+                    a: f.from.clone(),
+                    b: f.into.clone(),
+                });
+            }
+        }
+        for (a, b) in self.classes().iter().tuple_combinations() {
+            if self.join(a, b).is_none() {
+                return Err(LatticeError::NoJoin {
+                    a: a.to_string(),
+                    b: b.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The flows between distinct classes with no class in between them,
+    /// which are the edges of the Hasse diagram of the lattice.
+    pub fn covering(&self) -> impl Iterator<Item = &Flow> {
+        let classes = self.classes();
+        self.allowed.iter().filter(move |f| {
+            f.from != f.into
+                && !classes.iter().any(|c| {
+                    *c != f.from
+                        && *c != f.into
+                        && self.allows(&flow(&f.from, c))
+                        && self.allows(&flow(c, &f.into))
+                })
+        })
+    }
+
+    /// The Hasse diagram of the lattice as Graphviz dot, with flows pointing
+    /// upwards.
+    pub fn hasse_dot(&self) -> String {
+        format!(
+            "digraph G {{\n  rankdir=BT;\n{}\n{}\n}}",
+            self.classes()
+                .iter()
+                .map(|c| format!("  {c:?}[label={c:?}];"))
+                .format("\n"),
+            self.covering()
+                .map(|f| format!("  {:?} -> {:?};", f.from, f.into))
+                .format("\n"),
+        )
+    }
+
+    /// The flows between the variables, arrays and array elements of the
+    /// classification which the lattice allows.
     pub fn all_allowed<'a>(
         &'a self,
-        classification: &'a BTreeMap<Target, String>,
+        classification: &'a BTreeMap<String, String>,
     ) -> impl Iterator<Item = Flow> + 'a {
-        classification
+        Classification(classification)
+            .allowed(self, classification.keys().map(|n| n.as_str()))
+            .into_iter()
+    }
+}
+
+/// A variable, array or element of an array read or written by the
+/// commands. Elements are distinguished when their index is constant.
+pub type Endpoint = Target<Option<Int>>;
+
+/// An endpoint whose value is read, along with the class it was declassified
+/// to, if any.
+pub type Source = (Endpoint, Option<String>);
+
+/// The classes of variables, arrays and individual array elements, where
+/// elements are named like `A[0]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Classification<'a>(pub &'a BTreeMap<String, String>);
+
+impl Classification<'_> {
+    /// The name `e` is classified by. Elements with constant indices are
+    /// named individually, like `A[0]`, if any element of their array is
+    /// classified individually.
+    pub fn name(&self, e: &Endpoint) -> String {
+        match e {
+            Target::Array(a, Some(k)) if self.0.keys().any(|n| array_of(n) == Some(&a.0)) => {
+                format!("{a}[{k}]")
+            }
+            _ => e.name().to_string(),
+        }
+    }
+
+    /// The classes of the values which may be read or written through
+    /// `name`. Elements which are not classified individually have the class
+    /// of their array, and reading or writing an array at an unknown index
+    /// may read or write any of its individually classified elements.
+    fn classes<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b str> {
+        let own = match (self.0.get(name), array_of(name)) {
+            (Some(c), _) => Some(c),
+            (None, Some(a)) => self.0.get(a),
+            (None, None) => None,
+        };
+        let elements = self
+            .0
             .iter()
-            .cartesian_product(classification.iter())
-            .filter_map(|(a, b)| {
-                if self.allows(&flow(a.1, b.1)) {
-                    Some(flow(a.0, b.0))
-                } else {
-                    None
-                }
-            })
+            .filter(move |(n, _)| array_of(name).is_none() && array_of(n) == Some(name))
+            .map(|(_, c)| c.as_str());
+        own.map(|c| c.as_str()).into_iter().chain(elements)
+    }
+
+    /// The flows between the `names` which the lattice allows.
+    pub fn allowed<'b>(
+        &self,
+        lattice: &SecurityLattice,
+        names: impl IntoIterator<Item = &'b str>,
+    ) -> Vec<Flow> {
+        let names = names.into_iter().collect_vec();
+        names
+            .iter()
+            .cartesian_product(&names)
+            .filter(|(a, b)| self.allows(lattice, a, b))
+            .map(|(a, b)| flow(a, b))
+            .collect()
+    }
+
+    /// Whether the lattice allows information to flow from `from` into
+    /// `into`, both of which must be classified.
+    pub fn allows(&self, lattice: &SecurityLattice, from: &str, into: &str) -> bool {
+        self.classes(from).next().is_some() && self.admits(lattice, self.classes(from), into)
+    }
+
+    /// Whether the lattice allows values of all the `classes` to flow into
+    /// `into`, which must be classified.
+    pub fn admits<'b>(
+        &self,
+        lattice: &SecurityLattice,
+        classes: impl IntoIterator<Item = &'b str>,
+        into: &str,
+    ) -> bool {
+        let into = self.classes(into).collect_vec();
+        !into.is_empty()
+            && classes
+                .into_iter()
+                .all(|a| into.iter().all(|b| lattice.allows(&flow(a, b))))
     }
 }
 
+/// The array an element like `A[0]` is in.
+fn array_of(name: &str) -> Option<&str> {
+    name.split_once('[').map(|(a, _)| a)
+}
+
+/// The flows of the commands, from the source whose value is read into the
+/// endpoint written.
 pub(crate) trait Security {
-    fn flows(&self) -> BTreeSet<Flow> {
+    fn flows(&self) -> BTreeSet<(Source, Endpoint)> {
         self.sec(&Default::default())
     }
-    fn sec(&self, implicit: &BTreeSet<Target>) -> BTreeSet<Flow>;
+    fn sec(&self, implicit: &BTreeSet<Source>) -> BTreeSet<(Source, Endpoint)>;
 }
 
 impl Security for Commands {
-    fn sec(&self, implicit: &BTreeSet<Target>) -> BTreeSet<Flow> {
+    fn sec(&self, implicit: &BTreeSet<Source>) -> BTreeSet<(Source, Endpoint)> {
         self.0.iter().flat_map(|c| c.sec(implicit)).collect()
     }
 }
 
 impl Security for Command {
-    fn sec(&self, implicit: &BTreeSet<Target>) -> BTreeSet<Flow> {
-        match self {
-            Command::Assignment(t, a) => chain!(
-                implicit.iter().cloned(),
-                match t {
-                    Target::Variable(_) => BTreeSet::default(),
-                    Target::Array(_, idx) => idx.fv().into_iter().collect(),
-                },
-                a.fv()
-            )
-            .map(|i| flow(i, t.clone().unit()))
-            .collect(),
-            Command::Skip => BTreeSet::default(),
-            Command::If(c) | Command::Loop(c) => {
+    fn sec(&self, implicit: &BTreeSet<Source>) -> BTreeSet<(Source, Endpoint)> {
+        match &self.kind {
+            CommandKind::Assignment(t, a) => assignment_flows(implicit, t, a.sources()),
+            CommandKind::BoolAssignment(t, b) => assignment_flows(implicit, t, b.sources()),
+            CommandKind::Skip => BTreeSet::default(),
+            // NOTE: programs with procedures are rejected by `SecurityEnv::run`
+            CommandKind::Procedure(_) | CommandKind::Call(_) => BTreeSet::default(),
+            CommandKind::Par(components) => {
+                components.iter().flat_map(|c| c.sec(implicit)).collect()
+            }
+            CommandKind::If(c) | CommandKind::Loop(c) => {
                 c.iter()
                     .fold(
                         (implicit.clone(), BTreeSet::default()),
@@ -110,14 +295,121 @@ impl Security for Command {
     }
 }
 
+fn assignment_flows(
+    implicit: &BTreeSet<Source>,
+    t: &Target<Box<AExpr>>,
+    rhs: BTreeSet<Source>,
+) -> BTreeSet<(Source, Endpoint)> {
+    chain!(
+        implicit.iter().cloned(),
+        match t {
+            Target::Variable(_) => BTreeSet::default(),
+            Target::Array(_, idx) => idx.sources(),
+        },
+        rhs
+    )
+    .map(|i| (i, endpoint(t)))
+    .collect()
+}
+
+fn endpoint(t: &Target<Box<AExpr>>) -> Endpoint {
+    match t {
+        Target::Variable(v) => Target::Variable(v.clone()),
+        Target::Array(a, idx) => Target::Array(
+            a.clone(),
+            match idx.fold().kind {
+                AExprKind::Number(k) => Some(k),
+                _ => None,
+            },
+        ),
+    }
+}
+
+/// The array elements with constant indices used by the commands, which may
+/// be classified individually.
+pub fn elements(commands: &Commands) -> BTreeSet<String> {
+    commands
+        .flows()
+        .into_iter()
+        .flat_map(|((from, _), into)| [from, into])
+        .filter_map(|e| match e {
+            Target::Array(a, Some(k)) => Some(format!("{a}[{k}]")),
+            _ => None,
+        })
+        .collect()
+}
+
+trait Sources {
+    /// The endpoints read to evaluate the expression, each with the class
+    /// of the outermost declassification it is read under, if any.
+    fn sources(&self) -> BTreeSet<Source> {
+        let mut sources = BTreeSet::new();
+        self.collect_sources(None, &mut sources);
+        sources
+    }
+    fn collect_sources(&self, declassified: Option<&str>, sources: &mut BTreeSet<Source>);
+}
+
+impl Sources for Target<Box<AExpr>> {
+    fn collect_sources(&self, declassified: Option<&str>, sources: &mut BTreeSet<Source>) {
+        sources.insert((endpoint(self), declassified.map(|c| c.to_string())));
+        if let Target::Array(_, idx) = self {
+            idx.collect_sources(declassified, sources);
+        }
+    }
+}
+
+impl Sources for AExpr {
+    fn collect_sources(&self, declassified: Option<&str>, sources: &mut BTreeSet<Source>) {
+        match &self.kind {
+            AExprKind::Number(_) => {}
+            AExprKind::Reference(t) => t.collect_sources(declassified, sources),
+            AExprKind::Binary(l, _, r) => {
+                l.collect_sources(declassified, sources);
+                r.collect_sources(declassified, sources);
+            }
+            AExprKind::Minus(e) => e.collect_sources(declassified, sources),
+            AExprKind::Declassify(e, c) => {
+                e.collect_sources(Some(declassified.unwrap_or(c)), sources)
+            }
+        }
+    }
+}
+
+impl Sources for BExpr {
+    fn collect_sources(&self, declassified: Option<&str>, sources: &mut BTreeSet<Source>) {
+        match &self.kind {
+            BExprKind::Bool(_) => {}
+            BExprKind::Reference(t) => t.collect_sources(declassified, sources),
+            BExprKind::Rel(l, _, r) => {
+                l.collect_sources(declassified, sources);
+                r.collect_sources(declassified, sources);
+            }
+            BExprKind::Logic(l, _, r) => {
+                l.collect_sources(declassified, sources);
+                r.collect_sources(declassified, sources);
+            }
+            BExprKind::Not(b) => b.collect_sources(declassified, sources),
+        }
+    }
+}
+
 trait Security2 {
-    fn sec2(&self, implicit: &BTreeSet<Target>) -> (BTreeSet<Target>, BTreeSet<Flow>);
+    fn sec2(&self, implicit: &BTreeSet<Source>)
+    -> (BTreeSet<Source>, BTreeSet<(Source, Endpoint)>);
 }
 
 impl Security2 for Guard {
-    fn sec2(&self, implicit: &BTreeSet<Target>) -> (BTreeSet<Target>, BTreeSet<Flow>) {
-        let implicit = implicit.iter().cloned().chain(self.0.fv()).collect();
-        let flows = self.1.sec(&implicit);
+    fn sec2(
+        &self,
+        implicit: &BTreeSet<Source>,
+    ) -> (BTreeSet<Source>, BTreeSet<(Source, Endpoint)>) {
+        let implicit = implicit
+            .iter()
+            .cloned()
+            .chain(self.guard.sources())
+            .collect();
+        let flows = self.cmds.sec(&implicit);
         (implicit, flows)
     }
 }
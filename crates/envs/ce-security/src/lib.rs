@@ -1,14 +1,17 @@
 mod analysis;
+#[cfg(test)]
+mod tests;
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use analysis::{Security, SecurityLattice};
+use analysis::Security;
+pub use analysis::{Classification, LatticeError, SecurityLattice};
 use ce_core::{
     Env, Generate, ValidationResult, define_env,
     rand::{self, seq::IndexedRandom},
 };
 use gcl::{
-    ast::{Commands, Target, TargetDef, Variable},
+    ast::{Commands, TargetDef},
     memory::Memory,
 };
 use itertools::Itertools;
@@ -38,10 +41,26 @@ pub struct SecurityLatticeInput {
     pub rules: Vec<Flow>,
 }
 
+impl SecurityLatticeInput {
+    /// The lattice of the rules, completed with a top class if the rules have
+    /// several maximal classes, as described in [`SecurityLattice::with_top`].
+    ///
+    /// Inputs generated before lattices were validated, such as
+    /// `clean < Facebook, Google, Microsoft`, only lack the top class, and are
+    /// analysed with the class `Facebook_Google_Microsoft` added, like the
+    /// diamonds generated now.
+    pub fn lattice(&self) -> SecurityLattice {
+        SecurityLattice::new(&self.rules).with_top()
+    }
+}
+
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[tapi(path = "SecurityAnalysis")]
 pub struct Input {
     pub commands: Stringify<Commands>,
+    /// The classes of variables, arrays and array elements with constant
+    /// indices, like `A[0]`. Elements which are not classified individually
+    /// have the class of their array.
     pub classification: BTreeMap<String, String>,
     pub lattice: SecurityLatticeInput,
 }
@@ -52,6 +71,10 @@ pub struct Output {
     pub actual: Vec<Flow>,
     pub allowed: Vec<Flow>,
     pub violations: Vec<Flow>,
+    /// The flows which are not allowed, but where every use of the source
+    /// is declassified to a class allowed to flow into the target.
+    #[serde(default)]
+    pub declassified: Vec<Flow>,
     pub is_secure: bool,
 }
 
@@ -59,7 +82,12 @@ pub struct Output {
 #[tapi(path = "SecurityAnalysis")]
 pub struct Meta {
     pub lattice: SecurityLattice,
+    /// The Hasse diagram of the lattice as Graphviz dot.
+    pub hasse: String,
     pub targets: BTreeSet<TargetDef>,
+    /// The array elements with constant indices, which may be classified
+    /// individually.
+    pub elements: BTreeSet<String>,
 }
 
 impl Env for SecurityEnv {
@@ -81,9 +109,12 @@ impl Env for SecurityEnv {
             return Default::default();
         };
 
+        let lattice = input.lattice.lattice();
         Meta {
-            lattice: SecurityLattice::new(&input.lattice.rules),
-            targets: commands.fv().into_iter().map(|t| t.def()).collect(),
+            hasse: lattice.hasse_dot(),
+            lattice,
+            targets: commands.types().iter().map(|(t, ty)| t.def(*ty)).collect(),
+            elements: analysis::elements(&commands),
         }
     }
 
@@ -96,30 +127,51 @@ impl Env for SecurityEnv {
                     "failed to parse commands",
                 ))?;
 
-        let lattice = SecurityLattice::new(&input.lattice.rules);
+        if commands.procedures().next().is_some() {
+            return Err(ce_core::EnvError::InvalidInputForProgram {
+                message: "the security analysis does not support procedures".to_string(),
+                source: None,
+            });
+        }
 
-        let actual = commands.flows();
-        let allowed = lattice
-            .all_allowed(
-                &input
-                    .classification
-                    .iter()
-                    .map(|(k, v)| (Target::Variable(Variable(k.clone())), v.clone()))
-                    .collect(),
+        let lattice = input.lattice.lattice();
+        lattice.validate().map_err(invalid_lattice)?;
+
+        let classification = Classification(&input.classification);
+        // NOTE: a flow is justified by declassification only if every
+        // occurrence of it is
+        let mut actual: BTreeMap<Flow, bool> = BTreeMap::new();
+        for ((from, declassified), into) in commands.flows() {
+            let into = classification.name(&into);
+            let justified =
+                declassified.is_some_and(|c| classification.admits(&lattice, [c.as_str()], &into));
+            *actual
+                .entry(flow(classification.name(&from), into))
+                .or_insert(true) &= justified;
+        }
+        let names: BTreeSet<&str> = input
+            .classification
+            .keys()
+            .map(|n| n.as_str())
+            .chain(
+                actual
+                    .keys()
+                    .flat_map(|f| [f.from.as_str(), f.into.as_str()]),
             )
-            .collect_vec();
-        let violations = actual
+            .collect();
+        let allowed = classification.allowed(&lattice, names);
+        let (declassified, violations): (Vec<_>, Vec<_>) = actual
             .iter()
-            .filter(|f| !allowed.contains(f))
-            .cloned()
-            .collect_vec();
+            .filter(|(f, _)| !allowed.contains(f))
+            .partition(|(_, justified)| **justified);
 
         let is_secure = violations.is_empty();
 
         Ok(Output {
-            actual: actual.into_iter().collect(),
-            allowed: allowed.into_iter().collect(),
-            violations,
+            actual: actual.keys().cloned().collect(),
+            allowed,
+            violations: violations.into_iter().map(|(f, _)| f.clone()).collect(),
+            declassified: declassified.into_iter().map(|(f, _)| f.clone()).collect(),
             is_secure,
         })
     }
@@ -148,6 +200,11 @@ impl Env for SecurityEnv {
                 reason: "violations does not match reference".to_string(),
             });
         }
+        if !compare_sets(&output.declassified, &refernce.declassified) {
+            return Ok(ValidationResult::Mismatch {
+                reason: "declassified flows does not match reference".to_string(),
+            });
+        }
         if output.is_secure != refernce.is_secure {
             if refernce.is_secure {
                 return Ok(ValidationResult::Mismatch {
@@ -164,41 +221,20 @@ impl Env for SecurityEnv {
     }
 }
 
+pub fn invalid_lattice(err: LatticeError) -> ce_core::EnvError {
+    ce_core::EnvError::InvalidInputForProgram {
+        message: format!("the flows do not form a lattice: {err}"),
+        source: None,
+    }
+}
+
 impl Generate for Input {
     type Context = ();
 
     fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
         let commands = Commands::gn(&mut Default::default(), rng);
 
-        let lattice_options = [
-            // public < private
-            vec![flow("public", "private")],
-            // unclassified < classified, classified < secret, secret < top_secret
-            vec![
-                flow("unclassified", "classified"),
-                flow("classified", "secret"),
-                flow("secret", "top_secret"),
-            ],
-            // trusted < dubious
-            vec![flow("trusted", "dubious")],
-            // known_facts < conjecture, conjecture < alternative_facts
-            vec![
-                flow("known_facts", "conjecture"),
-                flow("conjecture", "alternative_facts"),
-            ],
-            // low < high
-            vec![flow("low", "high")],
-            // clean < Facebook, clean < Google, clean < Microsoft
-            vec![
-                flow("clean", "Facebook"),
-                flow("clean", "Google"),
-                flow("clean", "Microsoft"),
-            ],
-        ];
-
-        let lattice = SecurityLatticeInput {
-            rules: lattice_options.choose(rng).unwrap().clone(),
-        };
+        let lattice = SecurityLatticeInput::gn(&mut (), rng);
         let classes = lattice
             .rules
             .iter()
@@ -207,15 +243,20 @@ impl Generate for Input {
             .dedup()
             .collect_vec();
 
-        let classification = Memory::from_targets_with(
+        let mut classification: BTreeMap<String, String> = Memory::from_targets_with(
             commands.fv(),
-            rng,
+            &mut *rng,
             |rng, _| classes.choose(rng).unwrap().clone(),
             |rng, _| classes.choose(rng).unwrap().clone(),
         )
         .iter()
         .map(|r| (r.target().name().to_string(), r.value().clone()))
         .collect();
+        for e in analysis::elements(&commands) {
+            if rng.random_bool(0.25) {
+                classification.insert(e, classes.choose(rng).unwrap().clone());
+            }
+        }
 
         Input {
             commands: Stringify::new(commands),
@@ -224,3 +265,65 @@ impl Generate for Input {
         }
     }
 }
+
+/// Chains of classes, from the least to the most restricted.
+const CHAINS: &[&[&str]] = &[
+    &["public", "private"],
+    &["unclassified", "classified", "secret", "top_secret"],
+    &["trusted", "dubious"],
+    &["known_facts", "conjecture", "alternative_facts"],
+    &["low", "high"],
+];
+
+/// Owners of data, which may each receive clean data, and whose data may only
+/// be combined into a class shared by the owners.
+const OWNERS: &[&str] = &["Facebook", "Google", "Microsoft", "Apple"];
+
+impl Generate for SecurityLatticeInput {
+    type Context = ();
+
+    /// Generates either a chain, a diamond, or the product of two chains.
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let chain_rules = |chain: &[&str]| {
+            chain
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| flow(a, b))
+                .collect_vec()
+        };
+
+        let rules = match rng.random_range(0..3) {
+            0 => chain_rules(CHAINS.choose(rng).unwrap()),
+            1 => {
+                let n = rng.random_range(2..=3);
+                let owners = OWNERS.choose_multiple(rng, n).sorted().collect_vec();
+                let shared = owners.iter().join("_");
+                owners
+                    .iter()
+                    .flat_map(|o| [flow("clean", o), flow(o, &shared)])
+                    .collect()
+            }
+            _ => {
+                let chains = CHAINS
+                    .iter()
+                    .filter(|c| c.len() <= 3)
+                    .collect_vec()
+                    .choose_multiple(rng, 2)
+                    .map(|c| **c)
+                    .collect_vec();
+                let (outer, inner) = (chains[0], chains[1]);
+                let name = |i: usize, j: usize| format!("{}_{}", outer[i], inner[j]);
+                (0..outer.len())
+                    .cartesian_product(0..inner.len())
+                    .flat_map(|(i, j)| {
+                        let up = (i + 1 < outer.len()).then(|| flow(name(i, j), name(i + 1, j)));
+                        let right = (j + 1 < inner.len()).then(|| flow(name(i, j), name(i, j + 1)));
+                        up.into_iter().chain(right)
+                    })
+                    .collect()
+            }
+        };
+
+        SecurityLatticeInput { rules }
+    }
+}
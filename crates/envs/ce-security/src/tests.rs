@@ -0,0 +1,252 @@
+use ce_core::{
+    Env, Generate, ValidationResult,
+    rand::{SeedableRng, rngs::SmallRng},
+};
+use itertools::Itertools;
+use stdx::stringify::Stringify;
+
+use crate::{
+    Flow, Input, LatticeError, Output, SecurityEnv, SecurityLattice, SecurityLatticeInput, flow,
+};
+
+fn input(src: &str, classification: &[(&str, &str)]) -> Input {
+    Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        classification: classification
+            .iter()
+            .map(|(v, c)| (v.to_string(), c.to_string()))
+            .collect(),
+        lattice: SecurityLatticeInput {
+            rules: vec![flow("low", "high")],
+        },
+    }
+}
+
+fn run(src: &str, classification: &[(&str, &str)]) -> Output {
+    SecurityEnv::run(&input(src, classification)).unwrap()
+}
+
+fn flows(flows: &[(&str, &str)]) -> Vec<Flow> {
+    flows.iter().map(|(a, b)| flow(a, b)).collect()
+}
+
+fn lattice(rules: &[(&str, &str)]) -> SecurityLattice {
+    SecurityLattice::new(&rules.iter().map(|(a, b)| flow(a, b)).collect_vec())
+}
+
+#[test]
+fn lattice_validation() {
+    assert_eq!(lattice(&[("low", "high")]).validate(), Ok(()));
+    assert_eq!(
+        lattice(&[
+            ("clean", "Google"),
+            ("clean", "Apple"),
+            ("Google", "Apple_Google"),
+            ("Apple", "Apple_Google")
+        ])
+        .validate(),
+        Ok(())
+    );
+    assert!(matches!(
+        lattice(&[("low", "mid"), ("mid", "high"), ("high", "low")]).validate(),
+        Err(LatticeError::NotAntisymmetric { .. })
+    ));
+    assert_eq!(
+        lattice(&[("clean", "Google"), ("clean", "Apple")]).validate(),
+        Err(LatticeError::NoJoin {
+            a: "Apple".to_string(),
+            b: "Google".to_string()
+        })
+    );
+}
+
+#[test]
+fn joins() {
+    let l = lattice(&[
+        ("low", "mid"),
+        ("mid", "high"),
+        ("low", "other"),
+        ("other", "high"),
+    ]);
+    assert_eq!(l.join("low", "mid"), Some("mid"));
+    assert_eq!(l.join("mid", "mid"), Some("mid"));
+    assert_eq!(l.join("mid", "other"), Some("high"));
+    assert_eq!(l.join("mid", "unknown"), None);
+}
+
+#[test]
+fn hasse_diagram() {
+    let l = lattice(&[("low", "mid"), ("mid", "high"), ("low", "high")]);
+    assert_eq!(
+        l.covering().cloned().collect_vec(),
+        vec![flow("low", "mid"), flow("mid", "high")]
+    );
+    let dot = l.hasse_dot();
+    assert!(dot.contains("\"low\" -> \"mid\""));
+    assert!(!dot.contains("\"low\" -> \"high\""));
+}
+
+#[test]
+fn generated_lattices_are_valid() {
+    let mut rng = SmallRng::seed_from_u64(0xCEC34);
+    for _ in 0..100 {
+        let input = SecurityLatticeInput::gn(&mut (), &mut rng);
+        let l = SecurityLattice::new(&input.rules);
+        assert_eq!(l.validate(), Ok(()), "{:?}", input.rules);
+    }
+}
+
+#[test]
+fn invalid_lattices_are_rejected() {
+    let input = Input {
+        commands: Stringify::Unparsed("y := x".to_string()),
+        classification: [("x", "a"), ("y", "b")]
+            .into_iter()
+            .map(|(v, c)| (v.to_string(), c.to_string()))
+            .collect(),
+        lattice: SecurityLatticeInput {
+            rules: vec![flow("a", "b"), flow("b", "a")],
+        },
+    };
+    assert!(SecurityEnv::run(&input).is_err());
+
+    let input = Input {
+        lattice: SecurityLatticeInput {
+            rules: vec![flow("a", "b")],
+        },
+        ..input
+    };
+    let output = SecurityEnv::run(&input).unwrap();
+    assert_eq!(output.violations, Vec::<Flow>::new());
+}
+
+#[test]
+fn lattices_without_top_are_completed() {
+    let rules = SecurityLatticeInput {
+        rules: flows(&[
+            ("clean", "Facebook"),
+            ("clean", "Google"),
+            ("clean", "Microsoft"),
+        ]),
+    };
+    let l = rules.lattice();
+    assert_eq!(l.validate(), Ok(()));
+    assert_eq!(
+        l.join("Facebook", "Google"),
+        Some("Facebook_Google_Microsoft")
+    );
+    assert_eq!(l.join("clean", "Google"), Some("Google"));
+
+    let input = Input {
+        commands: Stringify::Unparsed("y := x ; z := x".to_string()),
+        classification: [("x", "clean"), ("y", "Google"), ("z", "Facebook")]
+            .into_iter()
+            .map(|(v, c)| (v.to_string(), c.to_string()))
+            .collect(),
+        lattice: rules,
+    };
+    let output = SecurityEnv::run(&input).unwrap();
+    assert!(output.is_secure);
+    assert_eq!(
+        SecurityEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let valid = lattice(&[("low", "mid"), ("mid", "high")]);
+    assert_eq!(valid.clone().with_top(), valid);
+}
+
+#[test]
+fn declassification() {
+    let classification = [("x", "high"), ("y", "low")];
+
+    let output = run("y := declassify(x, low)", &classification);
+    assert_eq!(output.actual, flows(&[("x", "y")]));
+    assert_eq!(output.declassified, flows(&[("x", "y")]));
+    assert_eq!(output.violations, vec![]);
+    assert!(output.is_secure);
+
+    // NOTE: every use of the source must be declassified
+    let output = run("y := declassify(x, low) + x", &classification);
+    assert_eq!(output.declassified, vec![]);
+    assert_eq!(output.violations, flows(&[("x", "y")]));
+
+    // NOTE: the class declassified to must be allowed into the target
+    let output = run("y := declassify(x, high)", &classification);
+    assert_eq!(output.violations, flows(&[("x", "y")]));
+
+    // NOTE: the outermost declassification decides the class
+    let output = run("y := declassify(declassify(x, high), low)", &classification);
+    assert_eq!(output.declassified, flows(&[("x", "y")]));
+}
+
+#[test]
+fn implicit_declassification() {
+    let classification = [("x", "high"), ("y", "low"), ("z", "low")];
+    let output = run(
+        "if declassify(x, low) > 0 -> y := 1 [] declassify(x, low) <= 0 -> y := z fi",
+        &classification,
+    );
+    assert_eq!(output.declassified, flows(&[("x", "y")]));
+    assert!(output.is_secure);
+
+    let output = run(
+        "if declassify(x, low) > 0 -> y := 1 [] x <= 0 -> y := z fi",
+        &classification,
+    );
+    assert_eq!(output.violations, flows(&[("x", "y")]));
+}
+
+#[test]
+fn array_elements() {
+    let src = "A[0] := x ; y := A[1] ; A[i] := 0";
+    let output = run(
+        src,
+        &[("A", "low"), ("x", "high"), ("y", "low"), ("i", "low")],
+    );
+    assert_eq!(output.violations, flows(&[("x", "A")]));
+
+    // NOTE: elements not classified individually have the class of their array
+    let classification = [
+        ("A", "low"),
+        ("A[0]", "high"),
+        ("x", "high"),
+        ("y", "low"),
+        ("i", "low"),
+    ];
+    let output = run(src, &classification);
+    assert_eq!(
+        output.actual,
+        flows(&[("A[1]", "y"), ("i", "A"), ("x", "A[0]")])
+    );
+    // NOTE: writing at an unknown index may write any element
+    assert!(output.allowed.contains(&flow("i", "A")));
+    assert!(output.allowed.contains(&flow("A[1]", "y")));
+    assert!(!output.allowed.contains(&flow("A", "y")));
+    assert!(output.is_secure);
+
+    let output = run("y := A[i]", &classification);
+    assert_eq!(output.violations, flows(&[("A", "y")]));
+}
+
+#[test]
+fn validation() {
+    let input = input(
+        "y := declassify(x, low) ; z := x",
+        &[("x", "high"), ("y", "low"), ("z", "low")],
+    );
+    let reference = SecurityEnv::run(&input).unwrap();
+    assert_eq!(
+        SecurityEnv::validate(&input, &reference).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let mut undeclassified = reference.clone();
+    undeclassified
+        .violations
+        .append(&mut undeclassified.declassified);
+    assert!(matches!(
+        SecurityEnv::validate(&input, &undeclassified).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+}
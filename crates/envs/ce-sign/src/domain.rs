@@ -0,0 +1,884 @@
+//! Analysis of program graphs over an abstract domain of integers.
+//!
+//! A memory assigns an abstract value to each variable and a set of abstract
+//! values to each array, describing the values of its elements. Booleans are
+//! kept concrete. The analysis keeps a set of memories at each node, unless
+//! the domain joins values, in which case memories with the same boolean
+//! variables are merged into one.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use ce_core::{EnvError, ValidationResult};
+use gcl::{
+    ast::{
+        AExpr, AExprKind, AOp, Array, BExpr, BExprKind, Call, Commands, Int, RelOp, Target,
+        Variable,
+    },
+    pg::{
+        Action, Determinism, Edge, Node, ProgramGraph, Signature,
+        analysis::{
+            Direction, FiFo, Lattice, MonotoneFramework, call_string_analysis, mono_analysis,
+        },
+    },
+    semantics::SemanticsError,
+    typeck::Type,
+};
+use indexmap::{IndexMap, IndexSet};
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::Bools;
+
+/// An abstract value, describing a set of integers.
+///
+/// The abstract operators are derived from the
+/// [representatives](AbstractDomain::representatives) of the values, by
+/// applying the concrete operator to every pair of representatives and
+/// abstracting the results. Domains whose values cannot be represented by
+/// finitely many integers override the operators instead.
+pub trait AbstractDomain: Debug + Clone + Ord + Hash + Serialize + DeserializeOwned {
+    /// The abstract value describing `n`.
+    fn alpha(n: Int) -> Self;
+    /// Abstract values which together describe every integer.
+    fn top() -> Vec<Self>;
+    /// Integers described by the value, such that applying an operator to
+    /// the representatives of two values exhibits every abstract result.
+    fn representatives(&self) -> Vec<Int>;
+
+    /// The least value describing both values, if memories are merged by
+    /// joining their values. Domains returning `None` keep every memory
+    /// apart, which is only guaranteed to terminate for finite domains.
+    fn join(&self, other: &Self) -> Option<Self> {
+        let _ = other;
+        None
+    }
+    fn binary(&self, op: AOp, other: &Self) -> BTreeSet<Self> {
+        self.representatives()
+            .into_iter()
+            .cartesian_product(other.representatives())
+            .filter_map(|(l, r)| op.semantic(l, r).ok())
+            .map(Self::alpha)
+            .collect()
+    }
+    fn negate(&self) -> BTreeSet<Self> {
+        self.representatives()
+            .into_iter()
+            .filter_map(|n| n.checked_neg())
+            .map(Self::alpha)
+            .collect()
+    }
+    fn relation(&self, op: RelOp, other: &Self) -> Bools {
+        self.representatives()
+            .into_iter()
+            .cartesian_product(other.representatives())
+            .map(|(l, r)| op.semantic(l, r))
+            .collect()
+    }
+    /// Whether the value describes any integer which may index an array.
+    fn may_be_index(&self) -> bool {
+        self.representatives().into_iter().any(|n| n >= 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = "V: Ord + Deserialize<'de>"))]
+pub struct AbstractMemory<V> {
+    pub variables: BTreeMap<Variable, V>,
+    pub arrays: BTreeMap<Array, BTreeSet<V>>,
+    pub bool_variables: BTreeMap<Variable, bool>,
+    pub bool_arrays: BTreeMap<Array, Bools>,
+}
+
+impl<V> Default for AbstractMemory<V> {
+    fn default() -> Self {
+        Self {
+            variables: Default::default(),
+            arrays: Default::default(),
+            bool_variables: Default::default(),
+            bool_arrays: Default::default(),
+        }
+    }
+}
+
+impl<V: AbstractDomain> AbstractMemory<V> {
+    pub fn with_bool_var(mut self, var: &Variable, value: bool) -> Self {
+        *self
+            .bool_variables
+            .get_mut(var)
+            .unwrap_or_else(|| panic!("boolean `{var}` not declared")) = value;
+        self
+    }
+    pub fn with_var(mut self, var: &Variable, value: V) -> Self {
+        *self
+            .variables
+            .get_mut(var)
+            .unwrap_or_else(|| panic!("variable `{var}` not declared")) = value;
+        self
+    }
+    pub fn get_var(&self, var: &Variable) -> Option<&V> {
+        self.variables.get(var)
+    }
+
+    /// Generates a memory for the targets of `commands`, with integers drawn
+    /// from `value`.
+    pub fn gn<R: ce_core::rand::Rng>(
+        commands: &Commands,
+        rng: &mut R,
+        mut value: impl FnMut(&mut R) -> V,
+    ) -> Self {
+        let mut mem = Self::default();
+        for (target, ty) in commands.types() {
+            match (target, ty) {
+                (Target::Variable(var), Type::Int) => {
+                    mem.variables.insert(var, value(rng));
+                }
+                (Target::Array(arr, ()), Type::Int) => {
+                    mem.arrays.insert(arr, [value(rng)].into_iter().collect());
+                }
+                (Target::Variable(var), Type::Bool) => {
+                    mem.bool_variables.insert(var, rng.random());
+                }
+                (Target::Array(arr, ()), Type::Bool) => {
+                    mem.bool_arrays
+                        .insert(arr, Bools::from(rng.random::<bool>()));
+                }
+            }
+        }
+        mem
+    }
+
+    /// Checks that the memory assigns every target of `commands`.
+    pub fn check_declared(&self, commands: &Commands) -> ce_core::Result<()> {
+        for (t, ty) in commands.types() {
+            let declared = match (&t, ty) {
+                (Target::Variable(var), Type::Int) => self.variables.contains_key(var),
+                (Target::Array(arr, _), Type::Int) => self.arrays.contains_key(arr),
+                (Target::Variable(var), Type::Bool) => self.bool_variables.contains_key(var),
+                (Target::Array(arr, _), Type::Bool) => self.bool_arrays.contains_key(arr),
+            };
+            if !declared {
+                let message = match t {
+                    Target::Variable(var) => format!("variable `{var}` was not in the given input"),
+                    Target::Array(arr, _) => format!("array `{arr}` was not in the given input"),
+                };
+                return Err(EnvError::InvalidInputForProgram {
+                    message,
+                    source: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The memory describing the memories of both, if they have the same
+    /// boolean variables and the domain joins values.
+    fn join(&self, other: &Self) -> Option<Self> {
+        if self.bool_variables != other.bool_variables
+            || !self.variables.keys().eq(other.variables.keys())
+        {
+            return None;
+        }
+        let variables = self
+            .variables
+            .iter()
+            .zip(other.variables.values())
+            .map(|((x, a), b)| Some((x.clone(), a.join(b)?)))
+            .collect::<Option<_>>()?;
+        let mut arrays = self.arrays.clone();
+        for (arr, values) in &other.arrays {
+            let joined = arrays.entry(arr.clone()).or_default();
+            joined.extend(values.iter().cloned());
+            *joined = collapse(std::mem::take(joined));
+        }
+        let mut bool_arrays = self.bool_arrays.clone();
+        for (arr, bools) in &other.bool_arrays {
+            *bool_arrays.entry(arr.clone()).or_default() |= *bools;
+        }
+        Some(Self {
+            variables,
+            arrays,
+            bool_variables: self.bool_variables.clone(),
+            bool_arrays,
+        })
+    }
+}
+
+/// Joins the values into one, if the domain joins values.
+fn collapse<V: AbstractDomain>(values: BTreeSet<V>) -> BTreeSet<V> {
+    let mut iter = values.iter();
+    let Some(first) = iter.next() else {
+        return values;
+    };
+    match iter.try_fold(first.clone(), |acc, v| acc.join(v)) {
+        Some(joined) => [joined].into_iter().collect(),
+        None => values,
+    }
+}
+
+/// The memories at a node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Memories<V: AbstractDomain>(#[serde(bound = "")] pub IndexSet<AbstractMemory<V>>);
+
+impl<V: AbstractDomain> Memories<V> {
+    /// Whether `mem` is described by the memories.
+    fn describes(&self, mem: &AbstractMemory<V>) -> bool {
+        self.0.contains(mem) || self.0.iter().any(|m| m.join(mem).as_ref() == Some(m))
+    }
+}
+
+impl<V: AbstractDomain> FromIterator<AbstractMemory<V>> for Memories<V> {
+    fn from_iter<T: IntoIterator<Item = AbstractMemory<V>>>(iter: T) -> Self {
+        let mut mems = Memories::bottom();
+        for mem in iter {
+            mems.insert(mem);
+        }
+        mems
+    }
+}
+
+impl<V: AbstractDomain> Memories<V> {
+    fn insert(&mut self, mem: AbstractMemory<V>) {
+        if self.describes(&mem) {
+            return;
+        }
+        let joined = self
+            .0
+            .iter()
+            .enumerate()
+            .find_map(|(idx, m)| Some((idx, m.join(&mem)?)));
+        match joined {
+            Some((idx, joined)) => {
+                self.0.swap_remove_index(idx);
+                self.insert(joined);
+            }
+            None => {
+                self.0.insert(mem);
+            }
+        }
+    }
+}
+
+impl<V: AbstractDomain> Lattice for Memories<V> {
+    fn bottom() -> Self {
+        Memories(IndexSet::default())
+    }
+
+    fn lub_extend(&mut self, other: &Self) {
+        for mem in &other.0 {
+            self.insert(mem.clone());
+        }
+    }
+
+    fn lub(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.lub_extend(other);
+        result
+    }
+
+    fn contains(&self, other: &Self) -> bool {
+        other.0.iter().all(|mem| self.describes(mem))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AbstractAnalysis<V> {
+    pub assignment: AbstractMemory<V>,
+}
+
+impl<V: AbstractDomain> MonotoneFramework for AbstractAnalysis<V> {
+    type Domain = Memories<V>;
+
+    fn semantic(&self, pg: &ProgramGraph, e: &Edge, prev: &Self::Domain) -> Self::Domain {
+        match e.action() {
+            Action::Assignment(t, x) => prev
+                .0
+                .iter()
+                .flat_map(|mem| assign(mem, t, x.abstract_semantics(mem)))
+                .collect(),
+            Action::BoolAssignment(Target::Variable(var), b) => prev
+                .0
+                .iter()
+                .flat_map(|mem| b.abstract_semantics(mem).bools().map(move |v| (mem, v)))
+                .map(|(mem, v)| mem.clone().with_bool_var(var, v))
+                .collect(),
+            Action::BoolAssignment(Target::Array(arr, idx), b) => {
+                prev.0
+                    .iter()
+                    .flat_map(|mem| {
+                        if may_index(idx, mem) {
+                            let array_bools = *mem.bool_arrays.get(arr).unwrap_or_else(|| {
+                                panic!("could not get booleans of array '{arr}'")
+                            });
+
+                            let mut new_possible = IndexSet::new();
+
+                            for v in std::iter::once(None).chain(array_bools.iter().map(Some)) {
+                                let mut bools = array_bools;
+                                if let Some(v) = v {
+                                    bools.remove(v);
+                                }
+                                for new_bool in b.abstract_semantics(mem).iter() {
+                                    let mut new_mem = mem.clone();
+                                    new_mem.bool_arrays.insert(arr.clone(), bools | new_bool);
+                                    new_possible.insert(new_mem);
+                                }
+                            }
+
+                            new_possible
+                        } else {
+                            Default::default()
+                        }
+                    })
+                    .collect()
+            }
+            Action::Skip => prev.clone(),
+            Action::Condition(b) => prev
+                .0
+                .iter()
+                .filter(|mem| b.abstract_semantics(mem).contains(Bools::TRUE))
+                .cloned()
+                .collect(),
+            Action::Call(call) => enter(pg, call, &prev.0),
+            // NOTE: without the memories at the call, the variables shadowed
+            // by the call could have any value after it
+            Action::Return(call) => {
+                let Some(signature) = pg.procedure(&call.name) else {
+                    return Memories::bottom();
+                };
+                prev.0
+                    .iter()
+                    .flat_map(|exit| {
+                        let results = results(signature, exit);
+                        signature
+                            .locals()
+                            .fold(vec![exit.clone()], |mems, local| {
+                                mems.into_iter()
+                                    .flat_map(|mem| {
+                                        V::top().into_iter().map(move |v| {
+                                            let mut mem = mem.clone();
+                                            mem.variables.insert(local.clone(), v);
+                                            mem
+                                        })
+                                    })
+                                    .collect()
+                            })
+                            .into_iter()
+                            .flat_map(move |mem| assign_results(call, &results, mem))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn ret(
+        &self,
+        pg: &ProgramGraph,
+        e: &Edge,
+        call: &Self::Domain,
+        prev: &Self::Domain,
+    ) -> Self::Domain {
+        let Action::Return(c) = e.action() else {
+            return self.semantic(pg, e, prev);
+        };
+        let Some(signature) = pg.procedure(&c.name) else {
+            return Memories::bottom();
+        };
+        prev.0
+            .iter()
+            .cartesian_product(&call.0)
+            .flat_map(|(exit, caller)| {
+                let mut mem = exit.clone();
+                for local in signature.locals() {
+                    match caller.variables.get(local) {
+                        Some(v) => mem.variables.insert(local.clone(), v.clone()),
+                        None => mem.variables.remove(local),
+                    };
+                }
+                assign_results(c, &results(signature, exit), mem)
+            })
+            .collect()
+    }
+
+    fn direction() -> Direction {
+        Direction::Forward
+    }
+
+    fn initial(&self, _pg: &ProgramGraph) -> Self::Domain {
+        [self.assignment.clone()].into_iter().collect()
+    }
+}
+
+/// The memories reaching each node of a program graph.
+pub type NodeMemories<V> = IndexMap<Node, IndexSet<AbstractMemory<V>>>;
+
+/// The memories at each node of the program graph of `commands`, starting
+/// from `assignment`, along with the program graph.
+pub fn analyze<V: AbstractDomain>(
+    commands: &Commands,
+    determinism: Determinism,
+    assignment: AbstractMemory<V>,
+) -> ce_core::Result<(ProgramGraph, NodeMemories<V>)> {
+    assignment.check_declared(commands)?;
+    let pg = ProgramGraph::new(determinism, commands);
+    let analysis = AbstractAnalysis { assignment };
+    // NOTE: calls are matched with their returns using the most recent call
+    let results = if commands.procedures().next().is_some() {
+        call_string_analysis(analysis, &pg, 1)
+    } else {
+        mono_analysis::<_, FiFo>(analysis, &pg)
+    };
+    let facts = results
+        .facts
+        .into_iter()
+        .map(|(n, mems)| (n, mems.0))
+        .collect();
+    Ok((pg, facts))
+}
+
+/// Checks that the memories of every node of `output` are the memories of
+/// some node of `reference`, and vice versa.
+pub fn validate_nodes<M: PartialEq + Debug>(
+    reference: &IndexMap<String, IndexSet<M>>,
+    output: &IndexMap<String, IndexSet<M>>,
+) -> ValidationResult
+where
+    IndexSet<M>: PartialEq,
+{
+    let mut pool = reference.values().collect_vec();
+
+    for (n, o) in output {
+        if let Some(idx) = pool.iter().position(|r| *r == o) {
+            pool.remove(idx);
+        } else {
+            tracing::error!(not_in_reference = format!("{o:?}"), "damn...");
+            return ValidationResult::Mismatch {
+                reason: format!("Produced world which did not exist in reference: {n:?} ~> {o:?}"),
+            };
+        }
+    }
+
+    if pool.is_empty() {
+        ValidationResult::Correct
+    } else {
+        tracing::error!(missing = format!("{pool:?}"), "oh no...");
+        ValidationResult::Mismatch {
+            reason: "Reference had world which was not present".to_string(),
+        }
+    }
+}
+
+/// Defines an environment analysing programs over the abstract domain
+/// `$domain`, along with its input, output and memory types under the
+/// `$path` namespace of the API.
+///
+/// The initial memories of generated inputs take their values from
+/// `$value`. In tests, `final_values(src, variables, x)` gives the values of
+/// `x` at the end of `src`, starting from the given values of `variables`.
+///
+/// The invoking crate must depend on `ce-core`, `gcl`, `indexmap`, `serde`,
+/// `serde_json`, `stdx` and `tapi`.
+#[macro_export]
+macro_rules! domain_env {
+    (
+        env: $env:ident,
+        path: $path:tt,
+        domain: $domain:ty,
+        memory: $memory:ident,
+        generate: |$rng:ident| $value:expr $(,)?
+    ) => {
+        ::ce_core::define_env!($env);
+
+        #[derive(
+            ::tapi::Tapi, Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize,
+        )]
+        #[tapi(path = $path)]
+        pub struct Input {
+            pub commands: ::stdx::stringify::Stringify<::gcl::ast::Commands>,
+            pub determinism: ::gcl::pg::Determinism,
+            pub assignment: $memory,
+        }
+
+        #[derive(
+            ::tapi::Tapi, Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize,
+        )]
+        #[tapi(path = $path)]
+        pub struct Output {
+            pub initial_node: String,
+            pub final_node: String,
+            pub nodes: ::indexmap::IndexMap<String, ::indexmap::IndexSet<$memory>>,
+            pub dot: String,
+        }
+
+        #[derive(
+            ::tapi::Tapi,
+            Debug,
+            Clone,
+            Default,
+            PartialEq,
+            Eq,
+            Hash,
+            ::serde::Serialize,
+            ::serde::Deserialize,
+        )]
+        #[tapi(path = $path)]
+        pub struct $memory {
+            pub variables: ::std::collections::BTreeMap<::gcl::ast::Variable, $domain>,
+            pub arrays: ::std::collections::BTreeMap<
+                ::gcl::ast::Array,
+                ::std::collections::BTreeSet<$domain>,
+            >,
+            #[serde(
+                default,
+                skip_serializing_if = "::std::collections::BTreeMap::is_empty"
+            )]
+            pub bool_variables: ::std::collections::BTreeMap<::gcl::ast::Variable, bool>,
+            #[serde(
+                default,
+                skip_serializing_if = "::std::collections::BTreeMap::is_empty"
+            )]
+            pub bool_arrays: ::std::collections::BTreeMap<::gcl::ast::Array, $crate::Bools>,
+        }
+
+        impl From<$memory> for $crate::domain::AbstractMemory<$domain> {
+            fn from(mem: $memory) -> Self {
+                Self {
+                    variables: mem.variables,
+                    arrays: mem.arrays,
+                    bool_variables: mem.bool_variables,
+                    bool_arrays: mem.bool_arrays,
+                }
+            }
+        }
+        impl From<$crate::domain::AbstractMemory<$domain>> for $memory {
+            fn from(mem: $crate::domain::AbstractMemory<$domain>) -> Self {
+                Self {
+                    variables: mem.variables,
+                    arrays: mem.arrays,
+                    bool_variables: mem.bool_variables,
+                    bool_arrays: mem.bool_arrays,
+                }
+            }
+        }
+
+        impl ::ce_core::Env for $env {
+            type Input = Input;
+
+            type Output = Output;
+
+            type Meta = ::std::collections::BTreeSet<::gcl::ast::TargetDef>;
+
+            fn meta(input: &Self::Input) -> Self::Meta {
+                if let Ok(commands) = input.commands.try_parse() {
+                    commands.types().iter().map(|(t, ty)| t.def(*ty)).collect()
+                } else {
+                    Default::default()
+                }
+            }
+
+            fn run(input: &Self::Input) -> ::ce_core::Result<Self::Output> {
+                let commands = input.commands.try_parse().map_err(
+                    ::ce_core::EnvError::invalid_input_for_program("failed to parse commands"),
+                )?;
+                let (pg, facts) = $crate::domain::analyze(
+                    &commands,
+                    input.determinism,
+                    input.assignment.clone().into(),
+                )?;
+                Ok(Output {
+                    initial_node: ::gcl::pg::Node::Start.to_string(),
+                    final_node: ::gcl::pg::Node::End.to_string(),
+                    nodes: facts
+                        .into_iter()
+                        .map(|(n, mems)| {
+                            (n.to_string(), mems.into_iter().map(Into::into).collect())
+                        })
+                        .collect(),
+                    dot: pg.dot(),
+                })
+            }
+
+            fn validate(
+                input: &Self::Input,
+                output: &Self::Output,
+            ) -> ::ce_core::Result<::ce_core::ValidationResult> {
+                let reference = Self::run(input)?;
+                Ok($crate::domain::validate_nodes(
+                    &reference.nodes,
+                    &output.nodes,
+                ))
+            }
+        }
+
+        impl ::ce_core::Generate for Input {
+            type Context = ();
+
+            fn gn<R: ::ce_core::rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+                use ::ce_core::rand::seq::IndexedRandom;
+
+                let commands =
+                    <::gcl::ast::Commands as ::ce_core::Generate>::gn(&mut Default::default(), rng);
+                let assignment =
+                    $crate::domain::AbstractMemory::gn(&commands, rng, |$rng| $value).into();
+                let determinism = *[
+                    ::gcl::pg::Determinism::Deterministic,
+                    ::gcl::pg::Determinism::NonDeterministic,
+                ]
+                .choose(rng)
+                .unwrap();
+
+                Input {
+                    commands: ::stdx::stringify::Stringify::new(commands),
+                    determinism,
+                    assignment,
+                }
+            }
+        }
+
+        #[cfg(test)]
+        fn final_values(
+            src: &str,
+            variables: &[(&str, $domain)],
+            x: &str,
+        ) -> ::std::collections::BTreeSet<$domain> {
+            let input = Input {
+                commands: ::stdx::stringify::Stringify::Unparsed(src.to_string()),
+                determinism: ::gcl::pg::Determinism::Deterministic,
+                assignment: $memory {
+                    variables: variables
+                        .iter()
+                        .map(|(v, value)| (::gcl::ast::Variable(v.to_string()), *value))
+                        .collect(),
+                    ..Default::default()
+                },
+            };
+            let output = <$env as ::ce_core::Env>::run(&input).unwrap();
+            output.nodes[&output.final_node]
+                .iter()
+                .map(|mem| mem.variables[&::gcl::ast::Variable(x.to_string())])
+                .collect()
+        }
+    };
+}
+
+fn assign<V: AbstractDomain>(
+    mem: &AbstractMemory<V>,
+    target: &Target<Box<AExpr>>,
+    value: BTreeSet<V>,
+) -> IndexSet<AbstractMemory<V>> {
+    match target {
+        Target::Variable(var) => value
+            .into_iter()
+            .map(|v| mem.clone().with_var(var, v))
+            .collect(),
+        Target::Array(arr, idx) => {
+            if may_index(idx, mem) {
+                let array_values = mem
+                    .arrays
+                    .get(arr)
+                    .unwrap_or_else(|| panic!("could not get values of array '{arr}'"));
+
+                let mut new_possible = IndexSet::new();
+
+                for v in std::iter::once(None).chain(array_values.iter().map(Some)) {
+                    let mut values = array_values.clone();
+                    if let Some(v) = v {
+                        values.remove(v);
+                    }
+                    for new_value in &value {
+                        let mut new_values = values.clone();
+                        new_values.insert(new_value.clone());
+                        let mut new_mem = mem.clone();
+                        new_mem.arrays.insert(arr.clone(), collapse(new_values));
+                        new_possible.insert(new_mem);
+                    }
+                }
+
+                new_possible
+            } else {
+                Default::default()
+            }
+        }
+    }
+}
+
+/// Whether `idx` may be a valid index of an array in `mem`.
+fn may_index<V: AbstractDomain>(idx: &AExpr, mem: &AbstractMemory<V>) -> bool {
+    idx.abstract_semantics(mem).iter().any(|v| v.may_be_index())
+}
+
+/// Binds the parameters of the procedure to the values of the arguments, and
+/// its results to zero.
+fn enter<V: AbstractDomain>(
+    pg: &ProgramGraph,
+    call: &Call,
+    prev: &IndexSet<AbstractMemory<V>>,
+) -> Memories<V> {
+    let Some(signature) = pg.procedure(&call.name) else {
+        return Memories::bottom();
+    };
+    prev.iter()
+        .flat_map(|mem| {
+            let bound = signature.params.iter().zip(&call.args).fold(
+                vec![mem.clone()],
+                |mems, (param, arg)| {
+                    let values = arg.abstract_semantics(mem);
+                    mems.into_iter()
+                        .flat_map(|m| {
+                            values.iter().map(move |v| {
+                                let mut m = m.clone();
+                                m.variables.insert(param.clone(), v.clone());
+                                m
+                            })
+                        })
+                        .collect()
+                },
+            );
+            bound.into_iter().map(|mut m| {
+                for r in &signature.results {
+                    m.variables.insert(r.clone(), V::alpha(0));
+                }
+                m
+            })
+        })
+        .collect()
+}
+
+/// The values of the results of the procedure at its exit.
+fn results<V: AbstractDomain>(signature: &Signature, exit: &AbstractMemory<V>) -> Vec<V> {
+    signature
+        .results
+        .iter()
+        .map(|r| {
+            exit.get_var(r)
+                .cloned()
+                .unwrap_or_else(|| panic!("could not get value of '{r}'"))
+        })
+        .collect()
+}
+
+/// Assigns the values of the results of the procedure to the targets of
+/// `call`.
+fn assign_results<V: AbstractDomain>(
+    call: &Call,
+    results: &[V],
+    mem: AbstractMemory<V>,
+) -> IndexSet<AbstractMemory<V>> {
+    call.results
+        .iter()
+        .zip(results)
+        .fold([mem].into_iter().collect(), |mems, (target, v)| {
+            mems.iter()
+                .flat_map(|m| assign(m, target, [v.clone()].into_iter().collect()))
+                .collect()
+        })
+}
+
+fn cartesian_flat_map<'a, L, R, T: Clone, Q>(
+    l: L,
+    r: R,
+    f: impl Fn(T, Option<T>) -> Q + 'a,
+) -> impl Iterator<Item = Q> + 'a
+where
+    L: 'a + IntoIterator<Item = T> + Clone,
+    L::IntoIter: Clone,
+    R: 'a + IntoIterator<Item = T> + Clone,
+    R::IntoIter: Clone,
+{
+    if r.clone().into_iter().next().is_none() {
+        Either::Left(l.into_iter().map(move |a| f(a, None)))
+    } else {
+        Either::Right(
+            l.into_iter()
+                .cartesian_product(r)
+                .map(move |(a, b)| f(a, Some(b))),
+        )
+    }
+}
+
+trait AbstractSemantics<V> {
+    type Items;
+
+    fn abstract_semantics(&self, mem: &AbstractMemory<V>) -> Self::Items;
+}
+
+impl<V: AbstractDomain> AbstractSemantics<V> for BExpr {
+    type Items = Bools;
+
+    fn abstract_semantics(&self, mem: &AbstractMemory<V>) -> Bools {
+        match &self.kind {
+            BExprKind::Bool(b) => [*b].into_iter().collect(),
+            BExprKind::Reference(Target::Variable(x)) => [*mem
+                .bool_variables
+                .get(x)
+                .unwrap_or_else(|| panic!("could not get value of '{x}'"))]
+            .into_iter()
+            .collect(),
+            BExprKind::Reference(Target::Array(arr, idx)) => {
+                if may_index(idx, mem) {
+                    mem.bool_arrays.get(arr).copied().unwrap_or_default()
+                } else {
+                    Default::default()
+                }
+            }
+            BExprKind::Rel(l, op, r) => {
+                let l = l.abstract_semantics(mem);
+                let r = r.abstract_semantics(mem);
+                l.iter()
+                    .cartesian_product(&r)
+                    .fold(Bools::empty(), |acc, (l, r)| acc | l.relation(*op, r))
+            }
+            BExprKind::Logic(l, op, r) => {
+                let l = l.abstract_semantics(mem);
+                let r = r.abstract_semantics(mem);
+                cartesian_flat_map(l.bools(), r.bools(), |l, r| {
+                    op.semantic(l, || r.ok_or(SemanticsError::NoProgression))
+                })
+                .flatten()
+                .collect()
+            }
+            BExprKind::Not(b) => b.abstract_semantics(mem).map(|i| !i),
+        }
+    }
+}
+
+impl<V: AbstractDomain> AbstractSemantics<V> for AExpr {
+    type Items = BTreeSet<V>;
+
+    fn abstract_semantics(&self, mem: &AbstractMemory<V>) -> BTreeSet<V> {
+        match &self.kind {
+            AExprKind::Number(n) => [V::alpha(*n)].into_iter().collect(),
+            AExprKind::Reference(Target::Variable(x)) => [mem
+                .get_var(x)
+                .cloned()
+                .unwrap_or_else(|| panic!("could not get value of '{x}'"))]
+            .into_iter()
+            .collect(),
+            AExprKind::Reference(Target::Array(arr, idx)) => {
+                if may_index(idx, mem) {
+                    mem.arrays.get(arr).cloned().unwrap_or_default()
+                } else {
+                    Default::default()
+                }
+            }
+            AExprKind::Binary(l, op, r) => {
+                let l = l.abstract_semantics(mem);
+                let r = r.abstract_semantics(mem);
+                l.iter()
+                    .cartesian_product(&r)
+                    .flat_map(|(l, r)| l.binary(*op, r))
+                    .collect()
+            }
+            AExprKind::Minus(n) => n
+                .abstract_semantics(mem)
+                .iter()
+                .flat_map(|v| v.negate())
+                .collect(),
+            AExprKind::Declassify(e, _) => e.abstract_semantics(mem),
+        }
+    }
+}
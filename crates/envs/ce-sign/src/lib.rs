@@ -1,23 +1,20 @@
 #![allow(non_snake_case)]
 
+pub mod domain;
 mod semantics;
 
 use std::collections::BTreeSet;
 
 use ce_core::{
-    Env, EnvError, Generate, ValidationResult, define_env,
+    Env, Generate, define_env,
     rand::{self, seq::IndexedRandom},
 };
+use domain::{AbstractMemory, analyze, validate_nodes};
 use gcl::{
-    ast::{Commands, Target, TargetDef},
-    memory::Memory,
-    pg::{
-        Determinism, Node, ProgramGraph,
-        analysis::{FiFo, mono_analysis},
-    },
+    ast::{Commands, TargetDef},
+    pg::{Determinism, Node},
 };
 use indexmap::{IndexMap, IndexSet};
-use itertools::Itertools;
 pub use semantics::{Bools, Sign, SignAnalysis, SignMemory, Signs};
 use serde::{Deserialize, Serialize};
 use stdx::stringify::Stringify;
@@ -50,52 +47,34 @@ impl Env for SignEnv {
 
     fn meta(input: &Self::Input) -> Self::Meta {
         if let Ok(commands) = input.commands.try_parse() {
-            commands.fv().into_iter().map(|t| t.def()).collect()
+            commands.types().iter().map(|(t, ty)| t.def(*ty)).collect()
         } else {
             Default::default()
         }
     }
 
     fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
-        let pg =
-            ProgramGraph::new(
-                input.determinism,
-                &input.commands.try_parse().map_err(
-                    ce_core::EnvError::invalid_input_for_program("failed to parse commands"),
-                )?,
-            );
-
-        for t in pg.fv() {
-            match t {
-                Target::Variable(var) => {
-                    if input.assignment.get_var(&var).is_none() {
-                        return Err(EnvError::InvalidInputForProgram {
-                            message: format!("variable `{var}` was not in the given input"),
-                            source: None,
-                        });
-                    }
-                }
-                Target::Array(arr, _) => {
-                    if input.assignment.get_arr(&arr).is_none() {
-                        return Err(EnvError::InvalidInputForProgram {
-                            message: format!("array `{arr}` was not in the given input"),
-                            source: None,
-                        });
-                    }
-                }
-            }
-        }
-
-        let nodes = mono_analysis::<_, FiFo>(
-            SignAnalysis {
-                assignment: input.assignment.clone(),
-            },
-            &pg,
-        )
-        .facts
-        .into_iter()
-        .map(|(k, v)| (format!("{k}"), v))
-        .collect();
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        let (pg, facts) = analyze(
+            &commands,
+            input.determinism,
+            input.assignment.clone().into(),
+        )?;
+        let nodes = facts
+            .into_iter()
+            .map(|(k, v)| {
+                (
+                    format!("{k}"),
+                    v.into_iter().map(SignMemory::from).collect(),
+                )
+            })
+            .collect();
         Ok(Output {
             initial_node: Node::Start.to_string(),
             final_node: Node::End.to_string(),
@@ -109,45 +88,16 @@ impl Env for SignEnv {
         output: &Self::Output,
     ) -> ce_core::Result<ce_core::ValidationResult> {
         let reference = Self::run(input)?;
-
-        let mut pool = reference.nodes.values().collect_vec();
-
-        for (n, o) in &output.nodes {
-            if let Some(idx) = pool.iter().position(|r| *r == o) {
-                pool.remove(idx);
-            } else {
-                tracing::error!(not_in_reference = format!("{o:?}"), "damn...");
-                return Ok(ValidationResult::Mismatch {
-                    reason: format!(
-                        "Produced world which did not exist in reference: {n:?} ~> {o:?}"
-                    ),
-                });
-            }
-        }
-
-        if pool.is_empty() {
-            Ok(ValidationResult::Correct)
-        } else {
-            tracing::error!(missing = format!("{pool:?}"), "oh no...");
-            Ok(ValidationResult::Mismatch {
-                reason: "Reference had world which was not present".to_string(),
-            })
-        }
+        Ok(validate_nodes(&reference.nodes, &output.nodes))
     }
 }
 
 impl Generate for Input {
     type Context = ();
 
-    fn gn<R: rand::Rng>(_cx: &mut Self::Context, mut rng: &mut R) -> Self {
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
         let commands = Commands::gn(&mut Default::default(), rng);
-        let assignment: SignMemory = Memory::from_targets_with(
-            commands.fv(),
-            &mut rng,
-            |rng, _| Generate::gn(&mut (), rng),
-            |rng, _| Generate::gn(&mut (), rng),
-        )
-        .into();
+        let assignment = AbstractMemory::gn(&commands, rng, |rng| Sign::gn(&mut (), rng)).into();
 
         let determinism = *[Determinism::Deterministic, Determinism::NonDeterministic]
             .choose(rng)
@@ -1,23 +1,12 @@
 use std::collections::BTreeMap;
 
-use gcl::{
-    ast::{AExpr, Array, BExpr, Int, Target, Variable},
-    pg::{
-        Action, Edge, ProgramGraph,
-        analysis::{Direction, MonotoneFramework},
-    },
-    semantics::SemanticsError,
-};
-use indexmap::IndexSet;
-use itertools::{Either, Itertools};
+use gcl::ast::{Array, Int, Variable};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-// use crate::analysis::{Direction, MonotoneFramework};
+use crate::domain::{AbstractAnalysis, AbstractDomain, AbstractMemory};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct SignAnalysis {
-    pub assignment: SignMemory,
-}
+pub type SignAnalysis = AbstractAnalysis<Sign>;
 
 #[derive(
     tapi::Tapi,
@@ -51,17 +40,6 @@ impl std::fmt::Display for Sign {
     }
 }
 
-impl Sign {
-    fn representative(self) -> impl Iterator<Item = Int> + Clone {
-        match self {
-            Sign::Positive => itertools::Either::Left([1, 2]),
-            Sign::Zero => itertools::Either::Right([0]),
-            Sign::Negative => itertools::Either::Left([-1, -2]),
-        }
-        .into_iter()
-    }
-}
-
 bitflags::bitflags! {
     // TODO: derive tapi::Tapi
     #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
@@ -156,6 +134,20 @@ bitflags::bitflags! {
     }
 }
 
+impl tapi::Tapi for Bools {
+    fn name() -> &'static str {
+        "Bools"
+    }
+
+    fn kind() -> tapi::kind::TypeKind {
+        tapi::kind::TypeKind::List(bool::boxed())
+    }
+
+    fn path() -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
 impl Default for Bools {
     fn default() -> Self {
         Self::empty()
@@ -219,8 +211,19 @@ impl FromIterator<bool> for Bools {
 pub struct SignMemory {
     pub variables: BTreeMap<Variable, Sign>,
     pub arrays: BTreeMap<Array, Signs>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bool_variables: BTreeMap<Variable, bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bool_arrays: BTreeMap<Array, Bools>,
 }
 impl SignMemory {
+    pub fn with_bool_var(mut self, var: &Variable, value: bool) -> Self {
+        *self
+            .bool_variables
+            .get_mut(var)
+            .unwrap_or_else(|| panic!("boolean `{var}` not declared")) = value;
+        self
+    }
     pub fn with_var(mut self, var: &Variable, value: Sign) -> Self {
         *self
             .variables
@@ -240,126 +243,54 @@ impl From<gcl::memory::Memory<Sign, Signs>> for SignMemory {
         Self {
             variables: mem.variables,
             arrays: mem.arrays,
+            ..Default::default()
         }
     }
 }
 
-impl MonotoneFramework for SignAnalysis {
-    type Domain = IndexSet<SignMemory>;
-
-    fn semantic(&self, _pg: &ProgramGraph, e: &Edge, prev: &Self::Domain) -> Self::Domain {
-        match e.action() {
-            Action::Assignment(Target::Variable(var), x) => prev
-                .iter()
-                .flat_map(|mem| x.semantics_sign(mem).signs().map(move |s| (mem, s)))
-                .map(|(mem, s)| mem.clone().with_var(var, s))
-                .collect(),
-            Action::Assignment(Target::Array(arr, idx), expr) => prev
-                .iter()
-                .flat_map(|mem| {
-                    let idx_signs = idx.semantics_sign(mem);
-                    if idx_signs.intersects(Signs::ZERO | Signs::POSITIVE) {
-                        let array_signs: Signs = mem
-                            .arrays
-                            .get(arr)
-                            .unwrap_or_else(|| panic!("could not get sign of array '{arr}'"))
-                            .iter()
-                            .collect();
-
-                        let mut new_possible = IndexSet::new();
-
-                        for s in std::iter::once(None).chain(array_signs.iter().map(Some)) {
-                            let mut signs = array_signs;
-                            if let Some(s) = s {
-                                signs.remove(s);
-                            }
-                            for new_sign in expr.semantics_sign(mem).iter() {
-                                let new_signs = signs | new_sign;
-                                let mut new_mem = mem.clone();
-                                new_mem.arrays.insert(arr.clone(), new_signs);
-                                new_possible.insert(new_mem);
-                            }
-                        }
-
-                        new_possible
-                    } else {
-                        Default::default()
-                    }
-                })
-                .collect(),
-            Action::Skip => prev.clone(),
-            Action::Condition(b) => prev
-                .iter()
-                .filter(|mem| b.semantics_sign(mem).contains(Bools::TRUE))
-                .cloned()
+impl From<SignMemory> for AbstractMemory<Sign> {
+    fn from(mem: SignMemory) -> Self {
+        Self {
+            variables: mem.variables,
+            arrays: mem
+                .arrays
+                .into_iter()
+                .map(|(arr, signs)| (arr, signs.signs().collect()))
                 .collect(),
+            bool_variables: mem.bool_variables,
+            bool_arrays: mem.bool_arrays,
         }
     }
-
-    fn direction() -> Direction {
-        Direction::Forward
-    }
-
-    fn initial(&self, _pg: &ProgramGraph) -> Self::Domain {
-        [self.assignment.clone()].into_iter().collect()
-    }
 }
-
-fn cartesian_flat_map<'a, L, R, T: Clone, Q>(
-    l: L,
-    r: R,
-    f: impl Fn(T, Option<T>) -> Q + 'a,
-) -> impl Iterator<Item = Q> + 'a
-where
-    L: 'a + IntoIterator<Item = T> + Clone,
-    L::IntoIter: Clone,
-    R: 'a + IntoIterator<Item = T> + Clone,
-    R::IntoIter: Clone,
-{
-    if r.clone().into_iter().next().is_none() {
-        Either::Left(l.into_iter().map(move |a| f(a, None)))
-    } else {
-        Either::Right(
-            l.into_iter()
-                .cartesian_product(r)
-                .map(move |(a, b)| f(a, Some(b))),
-        )
+impl From<AbstractMemory<Sign>> for SignMemory {
+    fn from(mem: AbstractMemory<Sign>) -> Self {
+        Self {
+            variables: mem.variables,
+            arrays: mem
+                .arrays
+                .into_iter()
+                .map(|(arr, signs)| (arr, signs.into_iter().collect()))
+                .collect(),
+            bool_variables: mem.bool_variables,
+            bool_arrays: mem.bool_arrays,
+        }
     }
 }
 
-trait SemanticSign {
-    type Items;
-
-    fn semantics_sign(&self, mem: &SignMemory) -> Self::Items;
-}
+impl AbstractDomain for Sign {
+    fn alpha(n: Int) -> Self {
+        sign_of(n)
+    }
 
-impl SemanticSign for BExpr {
-    type Items = Bools;
+    fn top() -> Vec<Self> {
+        Signs::all().signs().collect()
+    }
 
-    fn semantics_sign(&self, mem: &SignMemory) -> Bools {
+    fn representatives(&self) -> Vec<Int> {
         match self {
-            BExpr::Bool(b) => [*b].into_iter().collect(),
-            BExpr::Rel(l, op, r) => {
-                let l = l.semantics_sign(mem);
-                let r = r.semantics_sign(mem);
-                cartesian_flat_map(
-                    l.signs().flat_map(|s| s.representative()),
-                    r.signs().flat_map(|s| s.representative()),
-                    |l, r| Some(op.semantic(l, r?)),
-                )
-                .flatten()
-                .collect()
-            }
-            BExpr::Logic(l, op, r) => {
-                let l = l.semantics_sign(mem);
-                let r = r.semantics_sign(mem);
-                cartesian_flat_map(l.bools(), r.bools(), |l, r| {
-                    op.semantic(l, || r.ok_or(SemanticsError::NoProgression))
-                })
-                .flatten()
-                .collect()
-            }
-            BExpr::Not(b) => b.semantics_sign(mem).map(|i| !i),
+            Sign::Positive => vec![1, 2],
+            Sign::Zero => vec![0],
+            Sign::Negative => vec![-1, -2],
         }
     }
 }
@@ -383,57 +314,3 @@ impl std::ops::Neg for Sign {
         }
     }
 }
-
-impl SemanticSign for AExpr {
-    type Items = Signs;
-
-    fn semantics_sign(&self, mem: &SignMemory) -> Signs {
-        match self {
-            AExpr::Number(n) => [sign_of(*n)].into_iter().collect(),
-            AExpr::Reference(Target::Variable(x)) => [mem
-                .get_var(x)
-                .unwrap_or_else(|| panic!("could not get sign of '{x}'"))]
-            .into_iter()
-            .collect(),
-            AExpr::Binary(l, op, r) => cartesian_flat_map(
-                l.semantics_sign(mem)
-                    .signs()
-                    .flat_map(|x| x.representative()),
-                r.semantics_sign(mem)
-                    .signs()
-                    .flat_map(|x| x.representative()),
-                |l, r| Some(op.semantic(l, r?)),
-            )
-            .flatten()
-            .filter_map(|res| match res {
-                Ok(mem) => Some(mem),
-                Err(err) => match err {
-                    SemanticsError::DivisionByZero
-                    | SemanticsError::NegativeExponent
-                    | SemanticsError::EvaluateQuantifier => None,
-                    SemanticsError::VariableNotFound { .. }
-                    | SemanticsError::ArrayNotFound { .. }
-                    | SemanticsError::IndexOutOfBound { .. }
-                    | SemanticsError::NoProgression
-                    | SemanticsError::OutsideFunctionDomain
-                    | SemanticsError::ArithmeticOverflow => unreachable!(),
-                },
-            })
-            .map(sign_of)
-            .collect(),
-            AExpr::Reference(Target::Array(arr, idx)) => {
-                let idx_signs = idx.semantics_sign(mem);
-                if idx_signs.intersects(Signs::ZERO | Signs::POSITIVE) {
-                    if let Some(arr) = mem.arrays.get(arr) {
-                        arr.iter().collect()
-                    } else {
-                        Default::default()
-                    }
-                } else {
-                    Default::default()
-                }
-            }
-            AExpr::Minus(n) => n.semantics_sign(mem).map(|x| -x),
-        }
-    }
-}
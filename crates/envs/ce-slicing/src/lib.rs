@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use ce_core::{
+    Env, Generate, ValidationResult, define_env,
+    rand::{self, seq::IndexedRandom},
+};
+use gcl::{
+    ast::{Commands, Target},
+    pg::{Action, Determinism, Node, ProgramGraph},
+};
+use indexmap::IndexSet;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use stdx::stringify::Stringify;
+
+define_env!(SlicingEnv);
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Slicing")]
+pub struct Input {
+    pub commands: Stringify<Commands>,
+    pub determinism: Determinism,
+    /// The node to slice at, named as in the program graph.
+    pub node: String,
+    /// The variables and arrays whose values at the node are of interest.
+    pub variables: Vec<String>,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Slicing")]
+pub struct Output {
+    pub edges: Vec<SlicedEdge>,
+    /// The program graph with the slice highlighted.
+    pub dot: String,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[tapi(path = "Slicing")]
+pub struct SlicedEdge {
+    pub from: String,
+    pub action: String,
+    pub to: String,
+}
+
+#[derive(tapi::Tapi, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "Slicing")]
+pub struct Meta {
+    pub nodes: Vec<String>,
+    pub variables: Vec<String>,
+}
+
+impl Env for SlicingEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = Meta;
+
+    fn meta(input: &Self::Input) -> Self::Meta {
+        let Ok(commands) = input.commands.try_parse() else {
+            return Default::default();
+        };
+        let pg = ProgramGraph::new(input.determinism, &commands);
+        Meta {
+            nodes: pg.nodes().iter().sorted().map(|n| n.to_string()).collect(),
+            variables: pg
+                .fv()
+                .iter()
+                .map(|t| t.name().to_string())
+                .sorted()
+                .collect(),
+        }
+    }
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        let pg = ProgramGraph::new(input.determinism, &commands);
+
+        let node = Node::from_name(&input.node)
+            .filter(|n| pg.nodes().contains(n))
+            .ok_or_else(|| ce_core::EnvError::InvalidInputForProgram {
+                message: format!("the program graph has no node `{}`", input.node),
+                source: None,
+            })?;
+        let fv = pg.fv();
+        let vars = input
+            .variables
+            .iter()
+            .map(|name| {
+                fv.iter()
+                    .find(|t| t.name() == name)
+                    .cloned()
+                    .ok_or_else(|| ce_core::EnvError::InvalidInputForProgram {
+                        message: format!("`{name}` is not a variable or array of the program"),
+                        source: None,
+                    })
+            })
+            .collect::<Result<IndexSet<Target>, _>>()?;
+
+        let slice = pg.slice(node, &vars);
+        Ok(Output {
+            edges: slice
+                .edges()
+                .map(|e| SlicedEdge {
+                    from: e.from().to_string(),
+                    action: e.action().to_string(),
+                    to: e.to().to_string(),
+                })
+                .collect(),
+            dot: slice.dot(),
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let reference = Self::run(input)?;
+
+        let parse = |e: &SlicedEdge| {
+            let from = Node::from_name(&e.from).ok_or(format!("invalid node `{}`", e.from))?;
+            let to = Node::from_name(&e.to).ok_or(format!("invalid node `{}`", e.to))?;
+            let action = e
+                .action
+                .parse::<Action>()
+                .map_err(|err| format!("invalid action `{}`: {err}", e.action))?;
+            Ok::<_, String>((from, action, to))
+        };
+
+        let expected: BTreeSet<_> = reference
+            .edges
+            .iter()
+            .map(|e| parse(e).expect("the reference edges are valid"))
+            .collect();
+        let found = match output
+            .edges
+            .iter()
+            .map(parse)
+            .collect::<Result<BTreeSet<_>, _>>()
+        {
+            Ok(found) => found,
+            Err(reason) => return Ok(ValidationResult::Mismatch { reason }),
+        };
+
+        let describe = |(from, action, to): &(Node, Action, Node)| {
+            format!("{from} -> {to} labelled `{action}`")
+        };
+        if let Some(e) = expected.difference(&found).next() {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!("the edge {} is missing from the slice", describe(e)),
+            });
+        }
+        if let Some(e) = found.difference(&expected).next() {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!("the edge {} cannot affect the variables", describe(e)),
+            });
+        }
+
+        Ok(ValidationResult::Correct)
+    }
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let commands = Commands::gn(&mut Default::default(), rng);
+        let determinism = *[Determinism::Deterministic, Determinism::NonDeterministic]
+            .choose(rng)
+            .unwrap();
+        let pg = ProgramGraph::new(determinism, &commands);
+
+        let nodes = pg.nodes().iter().collect_vec();
+        let node = nodes.choose(rng).map_or(Node::End, |n| **n);
+        let variables = pg
+            .fv()
+            .iter()
+            .filter(|_| rng.random_bool(0.5))
+            .map(|t| t.name().to_string())
+            .collect();
+
+        Input {
+            commands: Stringify::new(commands),
+            determinism,
+            node: node.to_string(),
+            variables,
+        }
+    }
+}
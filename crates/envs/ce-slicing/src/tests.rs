@@ -0,0 +1,150 @@
+use ce_core::{Env, ValidationResult};
+use gcl::pg::{Action, Determinism};
+use stdx::stringify::Stringify;
+
+use crate::{Input, Output, SlicedEdge, SlicingEnv};
+
+fn input(src: &str, node: &str, variables: &[&str]) -> Input {
+    Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        determinism: Determinism::NonDeterministic,
+        node: node.to_string(),
+        variables: variables.iter().map(|v| v.to_string()).collect(),
+    }
+}
+
+#[track_caller]
+fn assert_slice(src: &str, node: &str, variables: &[&str], expected: &[&str]) {
+    let output = SlicingEnv::run(&input(src, node, variables)).unwrap();
+    let mut actions = output
+        .edges
+        .iter()
+        .map(|e| e.action.clone())
+        .collect::<Vec<_>>();
+    let mut expected = expected
+        .iter()
+        .map(|a| a.parse::<Action>().unwrap().to_string())
+        .collect::<Vec<_>>();
+    actions.sort();
+    expected.sort();
+    assert_eq!(actions, expected);
+}
+
+#[test]
+fn data_dependencies() {
+    let src = "x := 1 ; y := 2 ; z := x + 1";
+    assert_slice(src, "q◀", &["z"], &["x := 1", "z := x + 1"]);
+    assert_slice(src, "q◀", &["y"], &["y := 2"]);
+    assert_slice(src, "q◀", &["x", "y"], &["x := 1", "y := 2"]);
+    assert_slice(src, "q▷", &["z"], &[]);
+    // NOTE: later definitions replace earlier ones
+    assert_slice(
+        "x := 1 ; x := 2 ; y := x",
+        "q◀",
+        &["y"],
+        &["x := 2", "y := x"],
+    );
+}
+
+#[test]
+fn arrays() {
+    // NOTE: assignments to elements only replace the element assigned
+    assert_slice(
+        "A[0] := 1 ; A[1] := 2 ; x := A[0]",
+        "q◀",
+        &["x"],
+        &["A[0] := 1", "A[1] := 2", "x := A[0]"],
+    );
+    assert_slice(
+        "i := 1 ; y := 2 ; A[i] := y",
+        "q◀",
+        &["A"],
+        &["i := 1", "y := 2", "A[i] := y"],
+    );
+}
+
+#[test]
+fn control_dependencies() {
+    let src = "if a > 0 -> x := 1 [] a <= 0 -> x := 2 fi ; y := 3";
+    assert_slice(src, "q◀", &["x"], &["a > 0", "a <= 0", "x := 1", "x := 2"]);
+    assert_slice(src, "q◀", &["y"], &["y := 3"]);
+
+    let src = "i := 0 ; s := 0 ; do i < n -> s := s + i ; i := i + 1 od";
+    assert_slice(src, "q◀", &["i"], &["i := 0", "i < n", "i := i + 1"]);
+    assert_slice(
+        src,
+        "q◀",
+        &["s"],
+        &["i := 0", "s := 0", "i < n", "s := s + i", "i := i + 1"],
+    );
+}
+
+#[test]
+fn procedures() {
+    let src = "proc double(x; y) y := 2 * x ; z := 1 end ; a := 3 ; b := 4 ; call double(a; c)";
+    assert_slice(
+        src,
+        "q◀",
+        &["c"],
+        &[
+            "a := 3",
+            "call double(a; c)",
+            "y := 2 * x",
+            "return double(a; c)",
+        ],
+    );
+    assert_slice(src, "q◀", &["z"], &["z := 1"]);
+}
+
+#[test]
+fn validation() {
+    let src = "x := 1 ; y := 2 ; z := x + 1";
+    let input = input(src, "qFinal", &["z"]);
+    let reference = SlicingEnv::run(&input).unwrap();
+    assert_eq!(
+        SlicingEnv::validate(&input, &reference).unwrap(),
+        ValidationResult::Correct
+    );
+
+    // NOTE: edges may be given in any order, with nodes named as in dot
+    let mut reordered = reference.clone();
+    reordered.edges.reverse();
+    for e in &mut reordered.edges {
+        e.from = e.from.replace("q▷", "qStart");
+        e.action = e.action.replace(' ', "");
+    }
+    assert_eq!(
+        SlicingEnv::validate(&input, &reordered).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let mut missing = reference.clone();
+    missing.edges.pop();
+    assert!(matches!(
+        SlicingEnv::validate(&input, &missing).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+
+    let extra = Output {
+        edges: vec![SlicedEdge {
+            from: "q1".to_string(),
+            action: "y := 2".to_string(),
+            to: "q2".to_string(),
+        }]
+        .into_iter()
+        .chain(reference.edges.clone())
+        .collect(),
+        dot: String::new(),
+    };
+    assert!(matches!(
+        SlicingEnv::validate(&input, &extra).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+}
+
+#[test]
+fn invalid_criterion() {
+    let src = "x := 1";
+    assert!(SlicingEnv::run(&input(src, "q42", &["x"])).is_err());
+    assert!(SlicingEnv::run(&input(src, "q◀", &["y"])).is_err());
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ce_core::{Env, Generate, ValidationResult, define_env, rand};
+use ce_security::{Flow, Meta, SecurityLatticeInput, flow, invalid_lattice};
+use gcl::{
+    ast::{Commands, Int},
+    interpreter::{InterpreterMemory, taint::TaintedExecution},
+    pg::{Determinism, ProgramGraph},
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use stdx::stringify::Stringify;
+
+define_env!(TaintEnv);
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "TaintTracking")]
+pub struct Input {
+    pub commands: Stringify<Commands>,
+    pub classification: BTreeMap<String, String>,
+    pub lattice: SecurityLatticeInput,
+    pub assignment: InterpreterMemory,
+    /// The number of steps after which the execution is stopped.
+    pub trace_length: Int,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "TaintTracking")]
+pub struct Output {
+    /// The flows exercised by the execution.
+    pub observed: Vec<Flow>,
+    /// The observed flows which are not allowed by the classification.
+    pub violations: Vec<Flow>,
+    pub is_secure: bool,
+}
+
+impl Env for TaintEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = Meta;
+
+    fn meta(input: &Self::Input) -> Self::Meta {
+        let Ok(commands) = input.commands.try_parse() else {
+            return Default::default();
+        };
+
+        let lattice = input.lattice.lattice();
+        Meta {
+            hasse: lattice.hasse_dot(),
+            lattice,
+            targets: commands.types().iter().map(|(t, ty)| t.def(*ty)).collect(),
+            // NOTE: all elements of an array share a label, so elements are
+            // not classified individually
+            elements: Default::default(),
+        }
+    }
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+
+        if commands.procedures().next().is_some() {
+            return Err(ce_core::EnvError::InvalidInputForProgram {
+                message: "taint tracking does not support procedures".to_string(),
+                source: None,
+            });
+        }
+
+        if let Some(element) = input.classification.keys().find(|n| n.contains('[')) {
+            return Err(ce_core::EnvError::InvalidInputForProgram {
+                message: format!(
+                    "taint tracking does not classify array elements, but {element} is classified"
+                ),
+                source: None,
+            });
+        }
+
+        let lattice = input.lattice.lattice();
+        lattice.validate().map_err(invalid_lattice)?;
+
+        let pg = ProgramGraph::new(Determinism::Deterministic, &commands);
+
+        let mut exe = TaintedExecution::new(input.assignment.clone());
+        for _ in 0..input.trace_length {
+            match exe.nexts(&pg).into_iter().next() {
+                Some(next) => exe = next,
+                None => break,
+            }
+        }
+
+        let allowed: BTreeSet<Flow> = lattice.all_allowed(&input.classification).collect();
+
+        let observed = exe.flows().iter().map(|(o, t)| flow(o, t)).collect_vec();
+        let violations = observed
+            .iter()
+            .filter(|f| !allowed.contains(f))
+            .cloned()
+            .collect_vec();
+        let is_secure = violations.is_empty();
+
+        Ok(Output {
+            observed,
+            violations,
+            is_secure,
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let reference = Self::run(input)?;
+
+        let difference = |expected: &[Flow], found: &[Flow]| {
+            let expected: BTreeSet<_> = expected.iter().collect();
+            let found: BTreeSet<_> = found.iter().collect();
+            if let Some(f) = expected.difference(&found).next() {
+                Some(format!(
+                    "the flow from {} into {} is missing",
+                    f.from, f.into
+                ))
+            } else {
+                found
+                    .difference(&expected)
+                    .next()
+                    .map(|f| format!("the flow from {} into {} was not exercised", f.from, f.into))
+            }
+        };
+
+        if let Some(reason) = difference(&reference.observed, &output.observed) {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!("observed flows do not match reference: {reason}"),
+            });
+        }
+        if let Some(reason) = difference(&reference.violations, &output.violations) {
+            return Ok(ValidationResult::Mismatch {
+                reason: format!("violations do not match reference: {reason}"),
+            });
+        }
+        if output.is_secure != reference.is_secure {
+            return Ok(ValidationResult::Mismatch {
+                reason: if reference.is_secure {
+                    "expected secure, but got insecure".to_string()
+                } else {
+                    "expected insecure, but got secure".to_string()
+                },
+            });
+        }
+
+        Ok(ValidationResult::Correct)
+    }
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let ce_security::Input {
+            commands,
+            mut classification,
+            lattice,
+        } = ce_security::Input::gn(&mut (), rng);
+        classification.retain(|name, _| !name.contains('['));
+        let assignment = ce_core::gn::interpreter_memory(
+            &commands.try_parse().expect("generated programs parse"),
+            rng,
+        );
+
+        Input {
+            commands,
+            classification,
+            lattice,
+            assignment,
+            trace_length: rng.random_range(10..=20),
+        }
+    }
+}
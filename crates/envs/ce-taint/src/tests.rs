@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use ce_core::{
+    Env, Generate, ValidationResult,
+    rand::{SeedableRng, rngs::SmallRng},
+};
+use ce_security::{Flow, SecurityEnv, SecurityLatticeInput, flow};
+use gcl::{
+    ast::{Array, Variable},
+    integer::Integer,
+    interpreter::InterpreterMemory,
+};
+use stdx::stringify::Stringify;
+
+use crate::{Input, TaintEnv};
+
+fn input(src: &str, variables: &[(&str, i32)], classification: &[(&str, &str)]) -> Input {
+    Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        classification: classification
+            .iter()
+            .map(|(v, c)| (v.to_string(), c.to_string()))
+            .collect(),
+        lattice: SecurityLatticeInput {
+            rules: vec![flow("low", "high")],
+        },
+        assignment: InterpreterMemory {
+            variables: variables
+                .iter()
+                .map(|(v, n)| (Variable(v.to_string()), Integer::from(*n)))
+                .collect(),
+            ..Default::default()
+        },
+        trace_length: 100,
+    }
+}
+
+fn flows(flows: &[(&str, &str)]) -> Vec<Flow> {
+    flows.iter().map(|(a, b)| flow(a, b)).collect()
+}
+
+#[track_caller]
+fn assert_observes(src: &str, variables: &[(&str, i32)], expected: &[(&str, &str)]) {
+    let output = TaintEnv::run(&input(src, variables, &[])).unwrap();
+    let mut observed = output.observed;
+    observed.sort();
+    let mut expected = flows(expected);
+    expected.sort();
+    assert_eq!(observed, expected);
+}
+
+#[test]
+fn explicit_flows() {
+    assert_observes("y := x", &[("x", 1), ("y", 0)], &[("x", "y")]);
+    // NOTE: values are labelled with the initial values they are computed from
+    assert_observes(
+        "y := x ; z := y",
+        &[("x", 1), ("y", 0), ("z", 0)],
+        &[("x", "y"), ("x", "z")],
+    );
+    assert_observes("y := 1 ; z := y", &[("y", 0), ("z", 0)], &[]);
+}
+
+#[test]
+fn arrays() {
+    let mut input = input("A[0] := x ; y := A[1]", &[("x", 1), ("y", 0)], &[]);
+    input
+        .assignment
+        .arrays
+        .insert(Array("A".to_string()), vec![Integer::from(0); 2]);
+    // NOTE: all elements of an array share a label
+    let mut observed = TaintEnv::run(&input).unwrap().observed;
+    observed.sort();
+    assert_eq!(observed, flows(&[("A", "y"), ("x", "A"), ("x", "y")]));
+}
+
+#[test]
+fn implicit_flows() {
+    let src = "if x > 0 -> y := 1 [] x <= 0 -> skip fi ; z := 2";
+    assert_observes(src, &[("x", 1), ("y", 0), ("z", 0)], &[("x", "y")]);
+    // NOTE: the branch which is not taken exercises no flows
+    assert_observes(src, &[("x", -1), ("y", 0), ("z", 0)], &[]);
+    // NOTE: the only guard of an `if` exercises implicit flows, even though
+    // it only decides whether the execution gets stuck
+    assert_observes(
+        "if x > 0 -> y := 1 fi",
+        &[("x", 1), ("y", 0)],
+        &[("x", "y")],
+    );
+    assert_observes(
+        "if x > 0 -> if y > 0 -> z := 1 fi ; w := 2 fi ; v := 3",
+        &[("x", 1), ("y", 1), ("z", 0), ("w", 0), ("v", 0)],
+        &[("x", "z"), ("y", "z"), ("x", "w")],
+    );
+
+    assert_observes(
+        "i := 0 ; do i < n -> i := i + 1 od",
+        &[("i", 5), ("n", 2)],
+        &[("n", "i")],
+    );
+}
+
+#[test]
+fn elements_are_not_classified() {
+    let input = input("A[0] := x", &[("x", 1)], &[("A[0]", "low"), ("x", "high")]);
+    assert!(TaintEnv::run(&input).is_err());
+
+    let mut rng = SmallRng::seed_from_u64(0x7a1e7);
+    for _ in 0..100 {
+        let input = Input::gn(&mut (), &mut rng);
+        assert!(input.classification.keys().all(|n| !n.contains('[')));
+    }
+}
+
+#[test]
+fn dynamic_flows_are_statically_found() {
+    let src = "if x > 0 -> y := 1 [] x <= 0 -> skip fi";
+    let classification = [("x", "high"), ("y", "low")];
+    let input = input(src, &[("x", -1), ("y", 0)], &classification);
+    let dynamic = TaintEnv::run(&input).unwrap();
+    assert!(dynamic.is_secure);
+
+    let input = security_input(src, &classification);
+    let static_ = SecurityEnv::run(&input).unwrap();
+    assert!(!static_.is_secure);
+    assert_eq!(static_.violations, flows(&[("x", "y")]));
+}
+
+fn security_input(src: &str, classification: &[(&str, &str)]) -> ce_security::Input {
+    ce_security::Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        classification: classification
+            .iter()
+            .map(|(v, c)| (v.to_string(), c.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        lattice: SecurityLatticeInput {
+            rules: vec![flow("low", "high")],
+        },
+    }
+}
+
+#[test]
+fn violations() {
+    let classification = [("x", "high"), ("y", "low"), ("z", "high")];
+    let output = TaintEnv::run(&input(
+        "z := y ; y := x",
+        &[("x", 1), ("y", 0), ("z", 0)],
+        &classification,
+    ))
+    .unwrap();
+    assert_eq!(output.violations, flows(&[("x", "y")]));
+    assert!(!output.is_secure);
+}
+
+#[test]
+fn trace_length() {
+    let mut input = input("x := 1 ; y := z", &[("x", 0), ("y", 0), ("z", 0)], &[]);
+    input.trace_length = 1;
+    assert_eq!(TaintEnv::run(&input).unwrap().observed, vec![]);
+}
+
+#[test]
+fn validation() {
+    let input = input(
+        "y := x ; if y > 0 -> z := 1 [] y <= 0 -> skip fi",
+        &[("x", 1), ("y", 0), ("z", 0)],
+        &[("x", "high"), ("y", "high"), ("z", "low")],
+    );
+    let reference = TaintEnv::run(&input).unwrap();
+    assert_eq!(reference.observed, flows(&[("x", "y"), ("x", "z")]));
+    assert_eq!(
+        TaintEnv::validate(&input, &reference).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let mut missing = reference.clone();
+    missing.observed.pop();
+    assert!(matches!(
+        TaintEnv::validate(&input, &missing).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+
+    let mut extra = reference.clone();
+    extra.observed.push(flow("y", "z"));
+    assert!(matches!(
+        TaintEnv::validate(&input, &extra).unwrap(),
+        ValidationResult::Mismatch { .. }
+    ));
+}
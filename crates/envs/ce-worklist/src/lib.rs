@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ce_core::{
+    Env, Generate, ValidationResult, define_env,
+    rand::{self, seq::IndexedRandom},
+};
+use ce_sign::{Sign, SignAnalysis, SignMemory, domain::AbstractMemory};
+use gcl::{
+    ast::{Commands, TargetDef},
+    pg::{
+        Determinism, ProgramGraph,
+        analysis::{FiFo, LiFo, LoopAware, ReversePostOrder, Worklist, mono_analysis},
+    },
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use stdx::stringify::Stringify;
+
+define_env!(WorklistEnv);
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "WorklistAnalysis")]
+pub struct Input {
+    pub commands: Stringify<Commands>,
+    pub determinism: Determinism,
+    pub assignment: SignMemory,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[tapi(path = "WorklistAnalysis")]
+pub struct Output {
+    /// The number of times each node is extracted from the worklist when
+    /// computing the sign analysis, for each strategy.
+    pub iterations: BTreeMap<Strategy, IndexMap<String, usize>>,
+    pub dot: String,
+}
+
+/// The order in which nodes are extracted from the worklist.
+#[derive(
+    tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[tapi(path = "WorklistAnalysis")]
+pub enum Strategy {
+    Fifo,
+    Lifo,
+    ReversePostOrder,
+    LoopAware,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [
+        Strategy::Fifo,
+        Strategy::Lifo,
+        Strategy::ReversePostOrder,
+        Strategy::LoopAware,
+    ];
+
+    /// The number of times each node is extracted from the worklist when
+    /// computing the sign analysis of `pg` with this strategy.
+    pub fn iterations(self, pg: &ProgramGraph, assignment: &SignMemory) -> IndexMap<String, usize> {
+        fn run<W: Worklist>(pg: &ProgramGraph, assignment: &SignMemory) -> IndexMap<String, usize> {
+            let analysis = SignAnalysis {
+                assignment: assignment.clone().into(),
+            };
+            mono_analysis::<_, W>(analysis, pg)
+                .iterations
+                .into_iter()
+                .map(|(n, k)| (n.to_string(), k))
+                .collect()
+        }
+        match self {
+            Strategy::Fifo => run::<FiFo>(pg, assignment),
+            Strategy::Lifo => run::<LiFo>(pg, assignment),
+            Strategy::ReversePostOrder => run::<ReversePostOrder>(pg, assignment),
+            Strategy::LoopAware => run::<LoopAware>(pg, assignment),
+        }
+    }
+}
+
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::Fifo => write!(f, "FIFO"),
+            Strategy::Lifo => write!(f, "LIFO"),
+            Strategy::ReversePostOrder => write!(f, "reverse post-order"),
+            Strategy::LoopAware => write!(f, "loop-aware"),
+        }
+    }
+}
+
+impl Env for WorklistEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = BTreeSet<TargetDef>;
+
+    fn meta(input: &Self::Input) -> Self::Meta {
+        if let Ok(commands) = input.commands.try_parse() {
+            commands.types().iter().map(|(t, ty)| t.def(*ty)).collect()
+        } else {
+            Default::default()
+        }
+    }
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        AbstractMemory::from(input.assignment.clone()).check_declared(&commands)?;
+        // NOTE: the strategies order the worklist of the intraprocedural
+        // analysis, so calls are not matched with their returns
+        let pg = ProgramGraph::new(input.determinism, &commands);
+        Ok(Output {
+            iterations: Strategy::ALL
+                .into_iter()
+                .map(|s| (s, s.iterations(&pg, &input.assignment)))
+                .collect(),
+            dot: pg.dot(),
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let reference = Self::run(input)?;
+
+        for (strategy, expected) in &reference.iterations {
+            let Some(actual) = output.iterations.get(strategy) else {
+                return Ok(ValidationResult::Mismatch {
+                    reason: format!("no iterations were given for the {strategy} worklist"),
+                });
+            };
+            for (n, k) in expected {
+                let actual = actual.get(n).copied().unwrap_or_default();
+                if actual != *k {
+                    return Ok(ValidationResult::Mismatch {
+                        reason: format!(
+                            "with the {strategy} worklist, node {n} is not extracted {actual} times"
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(ValidationResult::Correct)
+    }
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let commands = Commands::gn(&mut Default::default(), rng);
+        let assignment = AbstractMemory::gn(&commands, rng, |rng| Sign::gn(&mut (), rng)).into();
+        let determinism = *[Determinism::Deterministic, Determinism::NonDeterministic]
+            .choose(rng)
+            .unwrap();
+
+        Input {
+            commands: Stringify::new(commands),
+            determinism,
+            assignment,
+        }
+    }
+}
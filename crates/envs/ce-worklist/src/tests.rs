@@ -0,0 +1,88 @@
+use ce_core::{Env, ValidationResult};
+use ce_sign::{Sign, SignAnalysis, SignMemory, domain::AbstractMemory};
+use gcl::{
+    ast::{Commands, Variable},
+    pg::{
+        Determinism, ProgramGraph,
+        analysis::{FiFo, LiFo, LoopAware, ReversePostOrder, mono_analysis},
+    },
+};
+use stdx::stringify::Stringify;
+
+use crate::{Input, Strategy, WorklistEnv};
+
+fn input(src: &str, variables: &[&str]) -> Input {
+    Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        determinism: Determinism::Deterministic,
+        assignment: SignMemory {
+            variables: variables
+                .iter()
+                .map(|v| (Variable(v.to_string()), Sign::Positive))
+                .collect(),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn straight_line() {
+    let output = WorklistEnv::run(&input("x := 1 ; y := x ; z := y", &["x", "y", "z"])).unwrap();
+    let rpo = &output.iterations[&Strategy::ReversePostOrder];
+    assert_eq!(rpo.len(), 4);
+    assert!(rpo.values().all(|k| *k == 1));
+    let fifo = &output.iterations[&Strategy::Fifo];
+    assert!(fifo.values().sum::<usize>() > rpo.values().sum::<usize>());
+}
+
+#[test]
+fn nested_loops() {
+    let src = "i := 0 ; do i < 10 -> j := 0 ; do j < i -> j := j + 1 od ; i := i + 1 od ; x := 1";
+    let output = WorklistEnv::run(&input(src, &["i", "j", "x"])).unwrap();
+    for iterations in output.iterations.values() {
+        assert!(iterations.values().all(|k| *k >= 1));
+    }
+    let steps = |s: Strategy| output.iterations[&s].values().sum::<usize>();
+    assert!(steps(Strategy::LoopAware) <= steps(Strategy::Fifo));
+    assert!(steps(Strategy::ReversePostOrder) <= steps(Strategy::Fifo));
+}
+
+#[test]
+fn strategies_agree() {
+    let src = "i := 0 ; do i < 10 -> j := 0 ; do j < i -> j := j + 1 od ; i := i - 1 od";
+    let commands: Commands = src.parse().unwrap();
+    let pg = ProgramGraph::new(Determinism::Deterministic, &commands);
+    let assignment: AbstractMemory<Sign> = input(src, &["i", "j"]).assignment.into();
+    let analysis = || SignAnalysis {
+        assignment: assignment.clone(),
+    };
+    let fifo = mono_analysis::<_, FiFo>(analysis(), &pg);
+    let lifo = mono_analysis::<_, LiFo>(analysis(), &pg);
+    let rpo = mono_analysis::<_, ReversePostOrder>(analysis(), &pg);
+    let loops = mono_analysis::<_, LoopAware>(analysis(), &pg);
+    for results in [&lifo, &rpo, &loops] {
+        for (n, fact) in &fifo.facts {
+            let (a, b) = (&fact.0, &results.facts[n].0);
+            assert!(a.is_subset(b) && b.is_subset(a), "facts differ at {n}");
+        }
+    }
+    assert_eq!(fifo.steps(), fifo.iterations.values().sum::<usize>());
+}
+
+#[test]
+fn validation() {
+    let input = input("i := 0 ; do i < 10 -> i := i + 1 od", &["i"]);
+    let output = WorklistEnv::run(&input).unwrap();
+    assert_eq!(
+        WorklistEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let mut wrong = output.clone();
+    let iterations = wrong.iterations.get_mut(&Strategy::LoopAware).unwrap();
+    *iterations.values_mut().next().unwrap() += 1;
+    assert!(matches!(
+        WorklistEnv::validate(&input, &wrong).unwrap(),
+        ValidationResult::Mismatch { reason } if reason.contains("loop-aware")
+    ));
+}
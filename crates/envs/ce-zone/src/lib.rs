@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use ce_core::{Env, Generate, ValidationResult, define_env, rand::seq::IndexedRandom};
+use gcl::{
+    ast::{Commands, TargetDef, Variable},
+    pg::{
+        Determinism, Node, ProgramGraph,
+        analysis::{
+            FiFo, Lattice, call_string_analysis, mono_analysis,
+            zone::{DifferenceConstraint, Zone, ZoneAnalysis},
+        },
+    },
+};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use stdx::stringify::Stringify;
+
+define_env!(ZoneEnv);
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[tapi(path = "ZoneAnalysis")]
+pub struct Input {
+    pub commands: Stringify<Commands>,
+    pub determinism: Determinism,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[tapi(path = "ZoneAnalysis")]
+pub struct Output {
+    pub initial_node: String,
+    pub final_node: String,
+    /// The integer variables constrained by the zones.
+    pub variables: Vec<Variable>,
+    /// The constraints at each node, or `None` if the node is unreachable.
+    ///
+    /// The zones are computed from the initial node by joining the zones
+    /// reaching each node. A [loop head](ProgramGraph::loop_heads) is instead
+    /// widened once its zone has grown
+    /// [`WIDENING_DELAY`](gcl::pg::analysis::WIDENING_DELAY) times, dropping
+    /// every bound which is still growing, and nodes which are not loop heads
+    /// are never widened.
+    pub nodes: IndexMap<String, Option<Vec<DifferenceConstraint>>>,
+    pub dot: String,
+}
+
+impl Env for ZoneEnv {
+    type Input = Input;
+
+    type Output = Output;
+
+    type Meta = BTreeSet<TargetDef>;
+
+    fn meta(input: &Self::Input) -> Self::Meta {
+        if let Ok(commands) = input.commands.try_parse() {
+            commands.types().iter().map(|(t, ty)| t.def(*ty)).collect()
+        } else {
+            Default::default()
+        }
+    }
+
+    fn run(input: &Self::Input) -> ce_core::Result<Self::Output> {
+        let commands =
+            input
+                .commands
+                .try_parse()
+                .map_err(ce_core::EnvError::invalid_input_for_program(
+                    "failed to parse commands",
+                ))?;
+        let pg = ProgramGraph::new(input.determinism, &commands);
+        let analysis = ZoneAnalysis::new(&commands);
+        let variables = analysis.variables().to_vec();
+        // NOTE: calls are matched with their returns using the most recent call
+        let results = if commands.procedures().next().is_some() {
+            call_string_analysis(analysis, &pg, 1)
+        } else {
+            mono_analysis::<_, FiFo>(analysis, &pg)
+        };
+        Ok(Output {
+            initial_node: Node::Start.to_string(),
+            final_node: Node::End.to_string(),
+            variables,
+            nodes: results
+                .facts
+                .into_iter()
+                .map(|(n, zone)| (n.to_string(), zone.constraints()))
+                .collect(),
+            dot: pg.dot(),
+        })
+    }
+
+    fn validate(input: &Self::Input, output: &Self::Output) -> ce_core::Result<ValidationResult> {
+        let reference = Self::run(input)?;
+        let zone = |constraints: &Option<Vec<DifferenceConstraint>>| match constraints {
+            Some(constraints) => Zone::from_constraints(reference.variables.clone(), constraints),
+            None => Zone::Empty,
+        };
+
+        for (n, expected) in &reference.nodes {
+            let Some(actual) = output.nodes.get(n) else {
+                return Ok(ValidationResult::Mismatch {
+                    reason: format!("no zone was given for node {n}"),
+                });
+            };
+            let (actual_zone, expected_zone) = (zone(actual), zone(expected));
+            if !actual_zone.equivalent(&expected_zone) {
+                let problem = if actual_zone.contains(&expected_zone) {
+                    "is not the least zone"
+                } else {
+                    "excludes memories which may reach it"
+                };
+                let actual = match actual {
+                    Some(constraints) => format!("{{{}}}", constraints.iter().format(", ")),
+                    None => "unreachable".to_string(),
+                };
+                return Ok(ValidationResult::Mismatch {
+                    reason: format!("the zone {actual} at node {n} {problem}"),
+                });
+            }
+        }
+
+        Ok(ValidationResult::Correct)
+    }
+}
+
+impl Generate for Input {
+    type Context = ();
+
+    fn gn<R: ce_core::rand::Rng>(_cx: &mut Self::Context, rng: &mut R) -> Self {
+        let commands = Commands::gn(&mut Default::default(), rng);
+        let determinism = *[Determinism::Deterministic, Determinism::NonDeterministic]
+            .choose(rng)
+            .unwrap();
+
+        Input {
+            commands: Stringify::new(commands),
+            determinism,
+        }
+    }
+}
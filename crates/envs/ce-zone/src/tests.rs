@@ -0,0 +1,123 @@
+use ce_core::{Env, ValidationResult};
+use gcl::{
+    ast::Variable,
+    pg::{Determinism, analysis::zone::DifferenceConstraint},
+};
+use stdx::stringify::Stringify;
+
+use crate::{Input, Output, ZoneEnv};
+
+fn run(src: &str) -> (Input, Output) {
+    let input = Input {
+        commands: Stringify::Unparsed(src.to_string()),
+        determinism: Determinism::Deterministic,
+    };
+    let output = ZoneEnv::run(&input).unwrap();
+    (input, output)
+}
+
+/// The constraint `left - right ≤ bound`, where `""` is zero.
+fn c(left: &str, right: &str, bound: i64) -> DifferenceConstraint {
+    let var = |x: &str| (!x.is_empty()).then(|| Variable(x.to_string()));
+    DifferenceConstraint {
+        left: var(left),
+        right: var(right),
+        bound,
+    }
+}
+
+#[test]
+fn loop_bounds() {
+    let (_, output) = run("i := 0 ; do i < n -> A[i] := 0 ; i := i + 1 od");
+
+    // NOTE: the index is within `0 ≤ i < n` in the body of the loop
+    assert!(output.nodes.values().flatten().any(|constraints| {
+        constraints.contains(&c("i", "n", -1)) && constraints.contains(&c("", "i", 0))
+    }));
+    let end = output.nodes[&output.final_node].as_ref().unwrap();
+    assert!(end.contains(&c("n", "i", 0)));
+    assert!(end.contains(&c("", "i", 0)));
+    assert!(
+        !end.iter()
+            .any(|c| c.left == Some(Variable("i".to_string())) && c.right.is_none())
+    );
+}
+
+#[test]
+fn assignments() {
+    let (_, output) = run("y := x + 1 ; z := y + 1 ; w := 2 * z");
+    let end = output.nodes[&output.final_node].as_ref().unwrap();
+    assert!(end.contains(&c("z", "x", 2)));
+    assert!(end.contains(&c("x", "z", -2)));
+    assert!(
+        !end.iter()
+            .any(|c| c.left == Some(Variable("w".to_string())))
+    );
+}
+
+#[test]
+fn unreachable_nodes() {
+    let (_, output) = run("x := 1 ; if x > 1 -> y := 1 [] x <= 1 -> y := 2 fi");
+    assert!(output.nodes.values().any(|zone| zone.is_none()));
+    let end = output.nodes[&output.final_node].as_ref().unwrap();
+    assert!(end.contains(&c("y", "", 2)));
+    assert!(end.contains(&c("", "y", -2)));
+}
+
+#[test]
+fn validation() {
+    let (input, output) = run("y := x + 1 ; z := y + 1");
+    assert_eq!(
+        ZoneEnv::validate(&input, &output).unwrap(),
+        ValidationResult::Correct
+    );
+
+    // NOTE: the implied constraints can be left out
+    let mut reduced = output.clone();
+    *reduced.nodes.get_mut(&output.final_node).unwrap() = Some(vec![
+        c("y", "x", 1),
+        c("x", "y", -1),
+        c("z", "y", 1),
+        c("y", "z", -1),
+    ]);
+    assert_eq!(
+        ZoneEnv::validate(&input, &reduced).unwrap(),
+        ValidationResult::Correct
+    );
+
+    let mut loose = output.clone();
+    *loose.nodes.get_mut(&output.final_node).unwrap() = Some(vec![c("z", "x", 2)]);
+    assert!(matches!(
+        ZoneEnv::validate(&input, &loose).unwrap(),
+        ValidationResult::Mismatch { reason } if reason.contains("not the least zone")
+    ));
+
+    let mut unsound = output.clone();
+    *unsound.nodes.get_mut(&output.final_node).unwrap() = None;
+    assert!(matches!(
+        ZoneEnv::validate(&input, &unsound).unwrap(),
+        ValidationResult::Mismatch { reason } if reason.contains("excludes memories")
+    ));
+}
+
+#[test]
+fn widening_is_only_at_loop_heads() {
+    let (_, output) =
+        run("i := 0 ; do i < 10 -> if i < 5 -> i := i + 1 [] i >= 5 -> i := i + 2 fi ; j := i od");
+
+    // NOTE: the loop head loses its upper bound, but the nodes of the body
+    // keep the bounds implied by the guards
+    let zones = output.nodes.values().flatten().collect::<Vec<_>>();
+    assert!(
+        zones
+            .iter()
+            .any(|z| z.contains(&c("", "i", 0)) && !z.iter().any(|c| c.right.is_none()))
+    );
+    assert!(zones.iter().any(|z| z.contains(&c("i", "", 9))));
+    assert!(zones.iter().any(|z| z.contains(&c("i", "", 4))));
+    assert!(
+        zones
+            .iter()
+            .any(|z| z.contains(&c("", "i", -1)) && z.contains(&c("i", "", 11)))
+    );
+}
@@ -2,6 +2,8 @@ use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
+use crate::parse::SourceSpan;
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Target<Idx = ()> {
     Variable(Variable),
@@ -14,6 +16,8 @@ pub enum Target<Idx = ()> {
 pub enum TargetKind {
     Variable,
     Array,
+    BoolVariable,
+    BoolArray,
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,8 +37,12 @@ pub struct Variable(pub String);
 #[serde(transparent)]
 pub struct Array(pub String);
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProcedureName(pub String);
+
 impl FromStr for Commands {
-    type Err = crate::parse::ParseError;
+    type Err = crate::parse::ParseErrors;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         crate::parse::parse_commands(s)
     }
@@ -55,25 +63,128 @@ impl FromStr for AExpr {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Commands(pub Vec<Command>);
 
+/// Implements comparison and hashing of syntax on everything but its spans,
+/// such that the same syntax parsed from differently formatted sources is
+/// equal.
+macro_rules! ignore_spans {
+    ($ty:ty, |$x:ident| -> $key:ty { $body:expr }) => {
+        impl $ty {
+            fn key($x: &$ty) -> $key {
+                $body
+            }
+        }
+        impl PartialEq for $ty {
+            fn eq(&self, other: &Self) -> bool {
+                Self::key(self) == Self::key(other)
+            }
+        }
+        impl Eq for $ty {}
+        impl PartialOrd for $ty {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for $ty {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                Self::key(self).cmp(&Self::key(other))
+            }
+        }
+        impl std::hash::Hash for $ty {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                Self::key(self).hash(state)
+            }
+        }
+    };
+}
+pub(crate) use ignore_spans;
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub kind: CommandKind,
+    pub span: SourceSpan,
+}
+ignore_spans!(Command, |c| -> &CommandKind { &c.kind });
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Command {
+pub enum CommandKind {
     Assignment(Target<Box<AExpr>>, AExpr),
+    BoolAssignment(Target<Box<AExpr>>, BExpr),
     Skip,
     If(Vec<Guard>),
     Loop(Vec<Guard>),
+    /// Only occurs at the start of a program, before its first other command.
+    Procedure(Procedure),
+    Call(Call),
+    /// A parallel composition `par C1 [] C2 rap`, whose components take steps
+    /// in any interleaving until all of them have terminated.
+    Par(Vec<Commands>),
+}
+
+impl From<CommandKind> for Command {
+    fn from(kind: CommandKind) -> Self {
+        Command {
+            kind,
+            span: SourceSpan::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Guard {
+    pub guard_span: SourceSpan,
+    pub guard: BExpr,
+    pub cmds: Commands,
 }
+ignore_spans!(Guard, |g| -> (&BExpr, &Commands) { (&g.guard, &g.cmds) });
 
+/// A declaration `proc name(params; results) body end`.
+///
+/// Parameters and results are integer variables local to each call. All other
+/// variables are global.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Guard(pub BExpr, pub Commands);
+pub struct Procedure {
+    pub name: ProcedureName,
+    pub params: Vec<Variable>,
+    pub results: Vec<Variable>,
+    pub body: Commands,
+}
+
+/// A call `call name(args; results)`, which on return assigns the results of
+/// the procedure to the targets in `results`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Call {
+    pub name: ProcedureName,
+    pub args: Vec<AExpr>,
+    pub results: Vec<Target<Box<AExpr>>>,
+}
 
 pub type Int = i32;
 
+#[derive(Debug, Clone)]
+pub struct AExpr {
+    pub kind: AExprKind,
+    pub span: SourceSpan,
+}
+ignore_spans!(AExpr, |a| -> &AExprKind { &a.kind });
+
+impl From<AExprKind> for AExpr {
+    fn from(kind: AExprKind) -> Self {
+        AExpr {
+            kind,
+            span: SourceSpan::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum AExpr {
+pub enum AExprKind {
     Number(Int),
     Reference(Target<Box<AExpr>>),
     Binary(Box<AExpr>, AOp, Box<AExpr>),
     Minus(Box<AExpr>),
+    /// Evaluates to the value of the expression, which the security analysis
+    /// considers to be of the given class.
+    Declassify(Box<AExpr>, String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -85,9 +196,26 @@ pub enum AOp {
     Pow,
 }
 
+#[derive(Debug, Clone)]
+pub struct BExpr {
+    pub kind: BExprKind,
+    pub span: SourceSpan,
+}
+ignore_spans!(BExpr, |b| -> &BExprKind { &b.kind });
+
+impl From<BExprKind> for BExpr {
+    fn from(kind: BExprKind) -> Self {
+        BExpr {
+            kind,
+            span: SourceSpan::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum BExpr {
+pub enum BExprKind {
     Bool(bool),
+    Reference(Target<Box<AExpr>>),
     Rel(AExpr, RelOp, AExpr),
     Logic(Box<BExpr>, LogicOp, Box<BExpr>),
     Not(Box<BExpr>),
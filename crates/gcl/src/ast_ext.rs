@@ -4,10 +4,13 @@ use indexmap::IndexSet;
 
 use crate::{
     ast::{
-        AExpr, AOp, Array, BExpr, Command, Commands, Flow, Guard, LogicOp, RelOp, Target,
-        TargetDef, TargetKind, Variable,
+        AExpr, AExprKind, AOp, Array, BExpr, BExprKind, Call, Command, CommandKind, Commands, Flow,
+        Guard, Int, LogicOp, Procedure, ProcedureName, RelOp, Target, TargetDef, TargetKind,
+        Variable,
     },
+    parse::SourceSpan,
     semantics::EmptySemanticsContext,
+    typeck::Type,
 };
 
 impl Target<()> {
@@ -47,15 +50,21 @@ impl<Idx> Target<Idx> {
             Target::Array(a, _) => a.is_logical(),
         }
     }
-    pub fn def(&self) -> TargetDef {
+    pub fn def(&self, ty: Type) -> TargetDef {
         match self {
             Target::Variable(v) => TargetDef {
                 name: Target::Variable(v.clone()),
-                kind: TargetKind::Variable,
+                kind: match ty {
+                    Type::Int => TargetKind::Variable,
+                    Type::Bool => TargetKind::BoolVariable,
+                },
             },
             Target::Array(a, _) => TargetDef {
                 name: Target::Array(a.clone(), ()),
-                kind: TargetKind::Array,
+                kind: match ty {
+                    Type::Int => TargetKind::Array,
+                    Type::Bool => TargetKind::BoolArray,
+                },
             },
         }
     }
@@ -158,23 +167,58 @@ impl Commands {
     pub fn fv(&self) -> IndexSet<Target> {
         self.0.iter().flat_map(|c| c.fv()).collect()
     }
+    /// The procedures declared by the program.
+    pub fn procedures(&self) -> impl Iterator<Item = &Procedure> {
+        self.0.iter().filter_map(|c| match &c.kind {
+            CommandKind::Procedure(p) => Some(p),
+            _ => None,
+        })
+    }
+    pub fn procedure(&self, name: &ProcedureName) -> Option<&Procedure> {
+        self.procedures().find(|p| &p.name == name)
+    }
 }
 impl Command {
     pub fn fv(&self) -> IndexSet<Target> {
-        match self {
-            Command::Assignment(x, a) => x.fv().union(&a.fv()).cloned().collect(),
-            Command::Skip => IndexSet::default(),
-            Command::If(c) => guards_fv(c),
-            Command::Loop(c) => guards_fv(c),
+        match &self.kind {
+            CommandKind::Assignment(x, a) => x.fv().union(&a.fv()).cloned().collect(),
+            CommandKind::BoolAssignment(x, b) => x.fv().union(&b.fv()).cloned().collect(),
+            CommandKind::Skip => IndexSet::default(),
+            CommandKind::If(c) => guards_fv(c),
+            CommandKind::Loop(c) => guards_fv(c),
+            CommandKind::Procedure(p) => p.fv(),
+            CommandKind::Call(call) => call.fv(),
+            CommandKind::Par(components) => components.iter().flat_map(|c| c.fv()).collect(),
         }
     }
 }
+impl Procedure {
+    /// The global variables and arrays used by the procedure.
+    pub fn fv(&self) -> IndexSet<Target> {
+        let locals: IndexSet<Target> = self
+            .params
+            .iter()
+            .chain(&self.results)
+            .map(|v| Target::Variable(v.clone()))
+            .collect();
+        self.body.fv().difference(&locals).cloned().collect()
+    }
+}
+impl Call {
+    pub fn fv(&self) -> IndexSet<Target> {
+        self.args
+            .iter()
+            .flat_map(|a| a.fv())
+            .chain(self.results.iter().flat_map(|t| t.fv()))
+            .collect()
+    }
+}
 fn guards_fv(guards: &[Guard]) -> IndexSet<Target> {
     guards.iter().flat_map(|g| g.fv()).collect()
 }
 impl Guard {
     pub fn fv(&self) -> IndexSet<Target> {
-        self.0.fv().union(&self.1.fv()).cloned().collect()
+        self.guard.fv().union(&self.cmds.fv()).cloned().collect()
     }
 }
 impl Target<Box<AExpr>> {
@@ -190,108 +234,169 @@ impl Target<Box<AExpr>> {
     }
 }
 impl AExpr {
+    pub fn number(n: Int) -> Self {
+        AExprKind::Number(n).into()
+    }
+    pub fn reference(t: Target<Box<AExpr>>) -> Self {
+        AExprKind::Reference(t).into()
+    }
     pub fn binary(lhs: Self, op: AOp, rhs: Self) -> Self {
-        Self::Binary(Box::new(lhs), op, Box::new(rhs))
+        AExprKind::Binary(Box::new(lhs), op, Box::new(rhs)).into()
+    }
+    pub fn minus(x: Self) -> Self {
+        AExprKind::Minus(Box::new(x)).into()
+    }
+    pub fn declassify(x: Self, class: String) -> Self {
+        AExprKind::Declassify(Box::new(x), class).into()
+    }
+    /// The same expression, located at `span` in the source.
+    pub fn with_span(self, span: SourceSpan) -> Self {
+        AExpr { span, ..self }
     }
     pub fn fv(&self) -> IndexSet<Target> {
-        match self {
-            AExpr::Number(_) => Default::default(),
-            AExpr::Reference(v) => v.fv(),
-            AExpr::Binary(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
-            AExpr::Minus(x) => x.fv(),
+        match &self.kind {
+            AExprKind::Number(_) => Default::default(),
+            AExprKind::Reference(v) => v.fv(),
+            AExprKind::Binary(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
+            AExprKind::Minus(x) | AExprKind::Declassify(x, _) => x.fv(),
         }
     }
 }
 impl BExpr {
+    pub fn bool(b: bool) -> Self {
+        BExprKind::Bool(b).into()
+    }
+    pub fn reference(t: Target<Box<AExpr>>) -> Self {
+        BExprKind::Reference(t).into()
+    }
     pub fn logic(lhs: Self, op: LogicOp, rhs: Self) -> Self {
-        Self::Logic(Box::new(lhs), op, Box::new(rhs))
+        BExprKind::Logic(Box::new(lhs), op, Box::new(rhs)).into()
     }
     pub fn rel(lhs: AExpr, op: RelOp, rhs: AExpr) -> Self {
-        Self::Rel(lhs, op, rhs)
+        BExprKind::Rel(lhs, op, rhs).into()
+    }
+    /// The same expression, located at `span` in the source.
+    pub fn with_span(self, span: SourceSpan) -> Self {
+        BExpr { span, ..self }
     }
     pub fn fv(&self) -> IndexSet<Target> {
-        match self {
-            BExpr::Bool(_) => Default::default(),
-            BExpr::Rel(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
-            BExpr::Logic(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
-            BExpr::Not(x) => x.fv(),
+        match &self.kind {
+            BExprKind::Bool(_) => Default::default(),
+            BExprKind::Reference(v) => v.fv(),
+            BExprKind::Rel(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
+            BExprKind::Logic(l, _, r) => l.fv().union(&r.fv()).cloned().collect(),
+            BExprKind::Not(x) => x.fv(),
         }
     }
 }
+impl std::ops::Not for BExpr {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        BExprKind::Not(Box::new(self)).into()
+    }
+}
 
 impl BExpr {
     pub fn subst_var<T>(&self, t: &Target<T>, x: &AExpr) -> BExpr {
-        match self {
-            BExpr::Bool(b) => BExpr::Bool(*b),
-            BExpr::Rel(l, op, r) => BExpr::Rel(l.subst_var(t, x), *op, r.subst_var(t, x)),
-            BExpr::Logic(l, op, r) => BExpr::logic(l.subst_var(t, x), *op, r.subst_var(t, x)),
-            BExpr::Not(e) => BExpr::Not(Box::new(e.subst_var(t, x))),
+        let kind = match &self.kind {
+            BExprKind::Bool(b) => BExprKind::Bool(*b),
+            BExprKind::Reference(v) => BExprKind::Reference(v.clone()),
+            BExprKind::Rel(l, op, r) => BExprKind::Rel(l.subst_var(t, x), *op, r.subst_var(t, x)),
+            BExprKind::Logic(l, op, r) => BExprKind::Logic(
+                Box::new(l.subst_var(t, x)),
+                *op,
+                Box::new(r.subst_var(t, x)),
+            ),
+            BExprKind::Not(e) => BExprKind::Not(Box::new(e.subst_var(t, x))),
+        };
+        BExpr {
+            kind,
+            span: self.span,
         }
     }
 
     pub fn simplify(&self) -> BExpr {
-        match self
+        let span = self.span;
+        let kind = match self
             .semantics(&EmptySemanticsContext)
-            .map(BExpr::Bool)
-            .unwrap_or_else(|_| self.clone())
+            .map(BExprKind::Bool)
+            .unwrap_or_else(|_| self.kind.clone())
         {
-            BExpr::Bool(b) => BExpr::Bool(b),
-            BExpr::Rel(l, op, r) => BExpr::Rel(l.simplify(), op, r.simplify()),
-            BExpr::Logic(l, op, r) => {
+            BExprKind::Bool(b) => BExprKind::Bool(b),
+            BExprKind::Reference(v) => BExprKind::Reference(v.simplify()),
+            BExprKind::Rel(l, op, r) => BExprKind::Rel(l.simplify(), op, r.simplify()),
+            BExprKind::Logic(l, op, r) => {
                 let l = l.simplify();
                 let r = r.simplify();
 
-                match (l, op, r) {
-                    (BExpr::Bool(true), LogicOp::And, x) | (x, LogicOp::And, BExpr::Bool(true)) => {
-                        x
-                    }
-                    (BExpr::Bool(false), LogicOp::And, _)
-                    | (_, LogicOp::And, BExpr::Bool(false)) => BExpr::Bool(false),
-                    (BExpr::Bool(false), LogicOp::Or, x) | (x, LogicOp::Or, BExpr::Bool(false)) => {
-                        x
-                    }
-                    (BExpr::Bool(true), LogicOp::Or, _) | (_, LogicOp::Or, BExpr::Bool(true)) => {
-                        BExpr::Bool(true)
-                    }
-                    (l, op, r) => BExpr::logic(l, op, r),
+                match (&l.kind, op, &r.kind) {
+                    (BExprKind::Bool(true), LogicOp::And, _) => return r,
+                    (_, LogicOp::And, BExprKind::Bool(true)) => return l,
+                    (BExprKind::Bool(false), LogicOp::And, _)
+                    | (_, LogicOp::And, BExprKind::Bool(false)) => BExprKind::Bool(false),
+                    (BExprKind::Bool(false), LogicOp::Or, _) => return r,
+                    (_, LogicOp::Or, BExprKind::Bool(false)) => return l,
+                    (BExprKind::Bool(true), LogicOp::Or, _)
+                    | (_, LogicOp::Or, BExprKind::Bool(true)) => BExprKind::Bool(true),
+                    _ => BExprKind::Logic(Box::new(l), op, Box::new(r)),
                 }
             }
-            BExpr::Not(x) => {
+            BExprKind::Not(x) => {
                 let x = x.simplify();
-                match x {
-                    BExpr::Bool(b) => BExpr::Bool(!b),
-                    x => BExpr::Not(Box::new(x)),
+                match x.kind {
+                    BExprKind::Bool(b) => BExprKind::Bool(!b),
+                    _ => BExprKind::Not(Box::new(x)),
                 }
             }
-        }
+        };
+        BExpr { kind, span }
     }
 }
 
 impl AExpr {
     pub fn subst_var<T>(&self, t: &Target<T>, x: &AExpr) -> AExpr {
-        match self {
-            AExpr::Number(n) => AExpr::Number(*n),
-            AExpr::Reference(v) if v.same_name(t) => x.clone(),
-            AExpr::Reference(v) => AExpr::Reference(v.clone()),
-            AExpr::Binary(l, op, r) => AExpr::binary(l.subst_var(t, x), *op, r.subst_var(t, x)),
-            AExpr::Minus(e) => AExpr::Minus(Box::new(e.subst_var(t, x))),
+        let kind = match &self.kind {
+            AExprKind::Number(n) => AExprKind::Number(*n),
+            AExprKind::Reference(v) if v.same_name(t) => return x.clone(),
+            AExprKind::Reference(v) => AExprKind::Reference(v.clone()),
+            AExprKind::Binary(l, op, r) => AExprKind::Binary(
+                Box::new(l.subst_var(t, x)),
+                *op,
+                Box::new(r.subst_var(t, x)),
+            ),
+            AExprKind::Minus(e) => AExprKind::Minus(Box::new(e.subst_var(t, x))),
+            AExprKind::Declassify(e, c) => {
+                AExprKind::Declassify(Box::new(e.subst_var(t, x)), c.clone())
+            }
+        };
+        AExpr {
+            kind,
+            span: self.span,
         }
     }
 
     pub fn simplify(&self) -> AExpr {
-        match self
+        let span = self.span;
+        let kind = match self
             .semantics(&EmptySemanticsContext)
-            .map(AExpr::Number)
-            .unwrap_or_else(|_| self.clone())
+            .ok()
+            .and_then(|n| n.to_int())
+            .map(AExprKind::Number)
+            .unwrap_or_else(|| self.kind.clone())
         {
-            AExpr::Number(n) => AExpr::Number(n),
-            AExpr::Reference(v) => AExpr::Reference(v.simplify()),
-            AExpr::Binary(l, op, r) => AExpr::binary(l.simplify(), op, r.simplify()),
-            AExpr::Minus(e) => match &*e {
-                AExpr::Minus(inner) => inner.simplify(),
-                _ => AExpr::Minus(Box::new(e.simplify())),
+            AExprKind::Number(n) => AExprKind::Number(n),
+            AExprKind::Reference(v) => AExprKind::Reference(v.simplify()),
+            AExprKind::Binary(l, op, r) => {
+                AExprKind::Binary(Box::new(l.simplify()), op, Box::new(r.simplify()))
+            }
+            AExprKind::Minus(e) => match &e.kind {
+                AExprKind::Minus(inner) => return inner.simplify(),
+                _ => AExprKind::Minus(Box::new(e.simplify())),
             },
-        }
+            AExprKind::Declassify(e, c) => AExprKind::Declassify(Box::new(e.simplify()), c),
+        };
+        AExpr { kind, span }
     }
 }
 
@@ -3,8 +3,8 @@ use std::fmt::{Debug, Display};
 use itertools::Itertools;
 
 use crate::ast::{
-    AExpr, AOp, Array, BExpr, Command, Commands, Flow, Guard, LogicOp, RelOp, SecurityClass,
-    Target, Variable,
+    AExpr, AExprKind, AOp, Array, BExpr, BExprKind, Call, Command, CommandKind, Commands, Flow,
+    Guard, LogicOp, Procedure, ProcedureName, RelOp, SecurityClass, Target, Variable,
 };
 
 impl Display for Variable {
@@ -17,6 +17,11 @@ impl Display for Array {
         write!(f, "{}", self.0)
     }
 }
+impl Display for ProcedureName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 impl std::fmt::Display for Target<Box<AExpr>> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -37,12 +42,51 @@ impl std::fmt::Display for Target<()> {
 
 impl Display for Command {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Command::Assignment(target, expr) => write!(f, "{target} := {expr}"),
-            Command::Skip => write!(f, "skip"),
-            Command::If(guards) => write!(f, "if {}\nfi", guards.iter().format("\n[] ")),
-            Command::Loop(guards) => write!(f, "do {}\nod", guards.iter().format("\n[] ")),
+        match &self.kind {
+            CommandKind::Assignment(target, expr) => write!(f, "{target} := {expr}"),
+            CommandKind::BoolAssignment(target, expr) => write!(f, "{target} := {expr}"),
+            CommandKind::Skip => write!(f, "skip"),
+            CommandKind::If(guards) => write!(f, "if {}\nfi", guards.iter().format("\n[] ")),
+            CommandKind::Loop(guards) => write!(f, "do {}\nod", guards.iter().format("\n[] ")),
+            CommandKind::Procedure(p) => write!(f, "{p}"),
+            CommandKind::Call(call) => write!(f, "call {call}"),
+            CommandKind::Par(components) => write!(
+                f,
+                "par\n{}\nrap",
+                components
+                    .iter()
+                    .map(|c| c.to_string().lines().map(|l| format!("   {l}")).join("\n"))
+                    .format("\n[]\n")
+            ),
+        }
+    }
+}
+
+impl Display for Procedure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "proc {}({}", self.name, self.params.iter().format(", "))?;
+        if !self.results.is_empty() {
+            write!(f, "; {}", self.results.iter().format(", "))?;
+        }
+        write!(
+            f,
+            ")\n{}\nend",
+            self.body
+                .to_string()
+                .lines()
+                .map(|l| format!("   {l}"))
+                .format("\n")
+        )
+    }
+}
+
+impl Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({}", self.name, self.args.iter().format(", "))?;
+        if !self.results.is_empty() {
+            write!(f, "; {}", self.results.iter().format(", "))?;
         }
+        write!(f, ")")
     }
 }
 
@@ -57,8 +101,8 @@ impl Display for Guard {
         write!(
             f,
             "{} ->\n{}",
-            self.0,
-            self.1
+            self.guard,
+            self.cmds
                 .to_string()
                 .lines()
                 .map(|l| format!("   {l}"))
@@ -69,11 +113,12 @@ impl Display for Guard {
 
 impl Display for AExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AExpr::Number(n) => write!(f, "{n}"),
-            AExpr::Reference(x) => write!(f, "{x}"),
-            AExpr::Binary(l, op, r) => write!(f, "({l} {op} {r})"),
-            AExpr::Minus(m) => write!(f, "-{m}"),
+        match &self.kind {
+            AExprKind::Number(n) => write!(f, "{n}"),
+            AExprKind::Reference(x) => write!(f, "{x}"),
+            AExprKind::Binary(l, op, r) => write!(f, "({l} {op} {r})"),
+            AExprKind::Minus(m) => write!(f, "-{m}"),
+            AExprKind::Declassify(e, c) => write!(f, "declassify({e}, {c})"),
         }
     }
 }
@@ -90,11 +135,12 @@ impl Display for AOp {
 }
 impl Display for BExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            BExpr::Bool(b) => write!(f, "{b}"),
-            BExpr::Rel(l, op, r) => write!(f, "({l} {op} {r})"),
-            BExpr::Logic(l, op, r) => write!(f, "({l} {op} {r})"),
-            BExpr::Not(b) => write!(f, "!{b}"),
+        match &self.kind {
+            BExprKind::Bool(b) => write!(f, "{b}"),
+            BExprKind::Reference(x) => write!(f, "{x}"),
+            BExprKind::Rel(l, op, r) => write!(f, "({l} {op} {r})"),
+            BExprKind::Logic(l, op, r) => write!(f, "({l} {op} {r})"),
+            BExprKind::Not(b) => write!(f, "!{b}"),
         }
     }
 }
@@ -0,0 +1,248 @@
+//! Integer values of the interpreter, and the models of arithmetic on them.
+//!
+//! Values are stored with arbitrary precision, and an [`IntegerSemantics`]
+//! decides which values arithmetic may produce: checked 32-bit integers,
+//! two's complement integers which wrap around at a chosen bit width, or
+//! unbounded integers.
+
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{AOp, Int},
+    semantics::SemanticsError,
+};
+
+/// An integer of arbitrary precision.
+///
+/// Serialized as a JSON number if it fits in an `i64`, and as a string of
+/// decimal digits otherwise.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(BigInt);
+
+impl Integer {
+    /// The value as an [`Int`], if it is in range.
+    pub fn to_int(&self) -> Option<Int> {
+        self.0.to_i32()
+    }
+
+    /// The value as an index into an array, if it is non-negative.
+    pub fn to_index(&self) -> Option<usize> {
+        self.0.to_usize()
+    }
+
+    fn checked(&self) -> Result<Int, SemanticsError> {
+        self.to_int().ok_or(SemanticsError::ArithmeticOverflow)
+    }
+}
+
+impl From<Int> for Integer {
+    fn from(n: Int) -> Self {
+        Integer(n.into())
+    }
+}
+
+impl From<usize> for Integer {
+    fn from(n: usize) -> Self {
+        Integer(n.into())
+    }
+}
+
+impl PartialEq<Int> for Integer {
+    fn eq(&self, other: &Int) -> bool {
+        self.to_int() == Some(*other)
+    }
+}
+
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::str::FromStr for Integer {
+    type Err = num_bigint::ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Integer)
+    }
+}
+
+impl Serialize for Integer {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.to_i64() {
+            Some(n) => serializer.serialize_i64(n),
+            None => serializer.collect_str(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Integer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Integer;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an integer or a string of decimal digits")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, n: i64) -> Result<Integer, E> {
+                Ok(Integer(n.into()))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, n: u64) -> Result<Integer, E> {
+                Ok(Integer(n.into()))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<Integer, E> {
+                s.trim().parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl tapi::Tapi for Integer {
+    fn name() -> &'static str {
+        <Int as tapi::Tapi>::name()
+    }
+
+    fn kind() -> tapi::kind::TypeKind {
+        <Int as tapi::Tapi>::kind()
+    }
+
+    fn path() -> Vec<&'static str> {
+        <Int as tapi::Tapi>::path()
+    }
+}
+
+/// The model of integers used to evaluate arithmetic.
+#[derive(
+    tapi::Tapi,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[tapi(path = "GCL")]
+#[serde(tag = "type")]
+pub enum IntegerSemantics {
+    /// 32-bit integers, where arithmetic which overflows is an error.
+    #[default]
+    Checked,
+    /// Two's complement integers of `bits` bits, where arithmetic wraps
+    /// around on overflow.
+    Wrapping { bits: u32 },
+    /// Integers of arbitrary precision, up to [`MAX_UNBOUNDED_BITS`] bits,
+    /// beyond which arithmetic is considered to overflow.
+    Unbounded,
+}
+
+impl std::fmt::Display for IntegerSemantics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegerSemantics::Checked => write!(f, "checked 32-bit integers"),
+            IntegerSemantics::Wrapping { bits } => write!(f, "wrapping {bits}-bit integers"),
+            IntegerSemantics::Unbounded => write!(f, "unbounded integers"),
+        }
+    }
+}
+
+/// The bit widths of [`IntegerSemantics::Wrapping`] which can be evaluated
+/// in. Wider integers would make every operation allocate accordingly.
+pub const WRAPPING_BITS: std::ops::RangeInclusive<u32> = 1..=64;
+
+/// The largest number of bits an integer may have under
+/// [`IntegerSemantics::Unbounded`]. Without a limit, a loop which keeps
+/// squaring a variable would exhaust memory within a short trace.
+pub const MAX_UNBOUNDED_BITS: u64 = 1 << 16;
+
+impl IntegerSemantics {
+    /// Checks that arithmetic can be evaluated in these semantics, which
+    /// requires wrapping integers to have a width in [`WRAPPING_BITS`].
+    pub fn check(self) -> Result<(), SemanticsError> {
+        match self {
+            IntegerSemantics::Wrapping { bits } if !WRAPPING_BITS.contains(&bits) => {
+                Err(SemanticsError::UnsupportedBitWidth { bits })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Maps `n` to the integer it represents under these semantics.
+    pub fn normalize(self, n: Integer) -> Result<Integer, SemanticsError> {
+        self.check()?;
+        match self {
+            IntegerSemantics::Checked => n.checked().map(Integer::from),
+            IntegerSemantics::Wrapping { bits } => {
+                let modulus = BigInt::one() << bits;
+                let mut n = ((n.0 % &modulus) + &modulus) % &modulus;
+                if n >= &modulus >> 1u32 {
+                    n -= modulus;
+                }
+                Ok(Integer(n))
+            }
+            IntegerSemantics::Unbounded if n.0.bits() > MAX_UNBOUNDED_BITS => {
+                Err(SemanticsError::ArithmeticOverflow)
+            }
+            IntegerSemantics::Unbounded => Ok(n),
+        }
+    }
+
+    pub fn binary(self, op: AOp, l: &Integer, r: &Integer) -> Result<Integer, SemanticsError> {
+        if let IntegerSemantics::Checked = self {
+            return op.semantic(l.checked()?, r.checked()?).map(Integer::from);
+        }
+
+        let (l, r) = (self.normalize(l.clone())?.0, self.normalize(r.clone())?.0);
+        let result = match op {
+            AOp::Plus => l + r,
+            AOp::Minus => l - r,
+            AOp::Times => l * r,
+            AOp::Divide => {
+                if r.is_zero() {
+                    return Err(SemanticsError::DivisionByZero);
+                }
+                l / r
+            }
+            AOp::Pow if r.is_negative() => return Err(SemanticsError::NegativeExponent),
+            AOp::Pow => match self {
+                IntegerSemantics::Wrapping { bits } => l.modpow(&r, &(BigInt::one() << bits)),
+                _ if l.abs() <= BigInt::one() => {
+                    if l.is_negative() && (&r % 2u32).is_one() {
+                        -BigInt::one()
+                    } else if l.is_zero() && !r.is_zero() {
+                        BigInt::zero()
+                    } else {
+                        BigInt::one()
+                    }
+                }
+                _ => {
+                    // NOTE: the size of a power is checked before computing
+                    // it, as it could exhaust memory long before normalizing
+                    let exp = r
+                        .to_u64()
+                        .filter(|exp| l.bits().saturating_mul(*exp) <= MAX_UNBOUNDED_BITS)
+                        .ok_or(SemanticsError::ArithmeticOverflow)?;
+                    l.pow(exp as u32)
+                }
+            },
+        };
+        self.normalize(Integer(result))
+    }
+
+    pub fn negate(self, n: &Integer) -> Result<Integer, SemanticsError> {
+        self.normalize(Integer(-&n.0))
+    }
+}
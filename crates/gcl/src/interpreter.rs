@@ -1,13 +1,17 @@
-use std::collections::BTreeMap;
+pub mod taint;
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use stdx::stringify::Stringify;
 
 use crate::{
-    ast::{Array, Int, Variable},
-    pg::{Edge, Node, ProgramGraph},
-    semantics::{SemanticsContext, SemanticsError},
+    ast::{Array, Call, Target, Variable},
+    integer::{Integer, IntegerSemantics},
+    parse::SourceSpan,
+    pg::{Action, Edge, Node, ProgramGraph},
+    semantics::{SemanticsContext, SemanticsError, WithIntegers},
 };
 
 #[derive(
@@ -15,14 +19,21 @@ use crate::{
 )]
 #[tapi(path = "Interpreter")]
 pub struct InterpreterMemory {
-    pub variables: BTreeMap<Variable, Int>,
-    pub arrays: BTreeMap<Array, Vec<Int>>,
+    pub variables: BTreeMap<Variable, Integer>,
+    pub arrays: BTreeMap<Array, Vec<Integer>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bool_variables: BTreeMap<Variable, bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bool_arrays: BTreeMap<Array, Vec<bool>>,
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[tapi(path = "Interpreter")]
 pub struct Step {
     pub action: Stringify<crate::pg::Action>,
+    /// The span of the command or guard the action originates from.
+    #[serde(default)]
+    pub span: SourceSpan,
     pub node: String,
     pub memory: InterpreterMemory,
 }
@@ -35,11 +46,11 @@ pub enum TerminationState {
     Terminated,
 }
 
-fn lookup_array<'a>(
-    mem: &'a InterpreterMemory,
+fn lookup_array<'a, T>(
+    arrays: &'a BTreeMap<Array, Vec<T>>,
     array: &Array,
-) -> Result<&'a [Int], SemanticsError> {
-    mem.arrays
+) -> Result<&'a [T], SemanticsError> {
+    arrays
         .get(array)
         .ok_or_else(|| SemanticsError::ArrayNotFound {
             name: array.to_string(),
@@ -47,17 +58,32 @@ fn lookup_array<'a>(
         .map(|data| &**data)
 }
 
+fn lookup_index<'a, T>(
+    arrays: &'a BTreeMap<Array, Vec<T>>,
+    array: &Array,
+    index: &Integer,
+) -> Result<(&'a [T], usize), SemanticsError> {
+    let data = lookup_array(arrays, array)?;
+    match index.to_index() {
+        Some(idx) if idx < data.len() => Ok((data, idx)),
+        _ => Err(SemanticsError::IndexOutOfBound {
+            name: array.to_string(),
+            index: index.clone(),
+        }),
+    }
+}
+
 impl SemanticsContext for InterpreterMemory {
-    fn variable(&self, var: &Variable) -> Result<Int, SemanticsError> {
+    fn variable(&self, var: &Variable) -> Result<Integer, SemanticsError> {
         self.variables
             .get(var)
             .ok_or_else(|| SemanticsError::VariableNotFound {
                 name: var.to_string(),
             })
-            .copied()
+            .cloned()
     }
 
-    fn set_variable(&self, var: &Variable, value: Int) -> Result<Self, SemanticsError> {
+    fn set_variable(&self, var: &Variable, value: Integer) -> Result<Self, SemanticsError> {
         if self.variables.contains_key(var) {
             let mut m2 = self.clone();
             m2.variables.insert(var.clone(), value);
@@ -69,66 +95,145 @@ impl SemanticsContext for InterpreterMemory {
         }
     }
 
-    fn array_element(&self, array: &Array, index: Int) -> Result<Int, SemanticsError> {
-        let data = lookup_array(self, array)?;
-        data.get(index as usize)
-            .ok_or_else(|| SemanticsError::IndexOutOfBound {
-                name: array.to_string(),
-                index,
-            })
-            .copied()
+    fn array_element(&self, array: &Array, index: &Integer) -> Result<Integer, SemanticsError> {
+        let (data, idx) = lookup_index(&self.arrays, array, index)?;
+        Ok(data[idx].clone())
     }
 
     fn set_array_element(
         &self,
         array: &Array,
-        index: Int,
-        value: Int,
+        index: &Integer,
+        value: Integer,
     ) -> Result<Self, SemanticsError> {
-        match self.arrays.get(array) {
-            Some(data) if 0 <= index && index < data.len() as _ => {
-                let mut m2 = self.clone();
-                let data = m2.arrays.get_mut(array).unwrap();
-                data[index as usize] = value;
-                Ok(m2)
-            }
-            Some(_) => Err(SemanticsError::ArrayNotFound {
-                name: array.to_string(),
-            }),
-            None => Err(SemanticsError::IndexOutOfBound {
-                name: array.to_string(),
-                index,
-            }),
+        let (_, idx) = lookup_index(&self.arrays, array, index)?;
+        let mut m2 = self.clone();
+        m2.arrays.get_mut(array).unwrap()[idx] = value;
+        Ok(m2)
+    }
+
+    fn array_length(&self, array: &Array) -> Result<Integer, SemanticsError> {
+        let data = lookup_array(&self.arrays, array)?;
+        Ok(data.len().into())
+    }
+
+    fn array_count(&self, array: &Array, element: &Integer) -> Result<Integer, SemanticsError> {
+        let data = lookup_array(&self.arrays, array)?;
+        Ok(data.iter().filter(|e| *e == element).count().into())
+    }
+
+    fn bool_variable(&self, var: &Variable) -> Result<bool, SemanticsError> {
+        self.bool_variables
+            .get(var)
+            .ok_or_else(|| SemanticsError::VariableNotFound {
+                name: var.to_string(),
+            })
+            .copied()
+    }
+
+    fn bool_array_element(&self, array: &Array, index: &Integer) -> Result<bool, SemanticsError> {
+        let (data, idx) = lookup_index(&self.bool_arrays, array, index)?;
+        Ok(data[idx])
+    }
+
+    fn set_bool_variable(&self, var: &Variable, value: bool) -> Result<Self, SemanticsError> {
+        if self.bool_variables.contains_key(var) {
+            let mut m2 = self.clone();
+            m2.bool_variables.insert(var.clone(), value);
+            Ok(m2)
+        } else {
+            Err(SemanticsError::VariableNotFound {
+                name: var.to_string(),
+            })
         }
     }
 
-    fn array_length(&self, array: &Array) -> Result<Int, SemanticsError> {
-        let data = lookup_array(self, array)?;
-        Ok(data.len() as _)
+    fn set_bool_array_element(
+        &self,
+        array: &Array,
+        index: &Integer,
+        value: bool,
+    ) -> Result<Self, SemanticsError> {
+        let (_, idx) = lookup_index(&self.bool_arrays, array, index)?;
+        let mut m2 = self.clone();
+        m2.bool_arrays.get_mut(array).unwrap()[idx] = value;
+        Ok(m2)
     }
+}
+
+/// The state of a call which has not yet returned.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frame {
+    /// The node to continue from when the call returns.
+    pub return_node: Node,
+    /// The values the local variables of the procedure had before the call,
+    /// if they were defined.
+    pub saved: Vec<(Variable, Option<Integer>)>,
+}
+
+/// The memory at the end of an execution which can take no further steps.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome {
+    Terminated(InterpreterMemory),
+    Stuck(InterpreterMemory),
+}
 
-    fn array_count(&self, array: &Array, element: Int) -> Result<Int, SemanticsError> {
-        let data = lookup_array(self, array)?;
-        Ok(data.iter().filter(|e| **e == element).count() as _)
+/// The configurations reachable from an execution, as found by
+/// [`Execution::explore`].
+///
+/// Each execution is a witness of a distinct outcome, with a shortest trace
+/// reaching it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exploration {
+    /// An execution reaching the final node for each distinct final memory.
+    pub terminated: Vec<Execution>,
+    /// An execution getting stuck for each distinct node and memory it can
+    /// get stuck in.
+    pub stuck: Vec<Execution>,
+    /// The number of distinct configurations visited.
+    pub visited: usize,
+    /// Whether every reachable configuration was visited within the bound.
+    pub complete: bool,
+}
+
+impl Exploration {
+    /// The memories the program can terminate in.
+    pub fn final_memories(&self) -> impl Iterator<Item = &InterpreterMemory> {
+        self.terminated.iter().map(|exe| exe.current_mem())
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Execution {
     initial_memory: InterpreterMemory,
+    integers: IntegerSemantics,
     trace: Vec<(Step, Node)>,
+    stack: Vec<Frame>,
 }
 
 impl Execution {
     pub fn new(initial_memory: InterpreterMemory) -> Self {
+        Self::with_integers(initial_memory, IntegerSemantics::default())
+    }
+    /// An execution which evaluates arithmetic in `integers`.
+    pub fn with_integers(initial_memory: InterpreterMemory, integers: IntegerSemantics) -> Self {
         Self {
             initial_memory,
+            integers,
             trace: vec![],
+            stack: vec![],
         }
     }
+    pub fn integers(&self) -> IntegerSemantics {
+        self.integers
+    }
     pub fn trace(&self) -> &[(Step, Node)] {
         &self.trace
     }
+    /// The calls which have not yet returned, with the most recent last.
+    pub fn stack(&self) -> &[Frame] {
+        &self.stack
+    }
     pub fn current_node(&self) -> Node {
         self.trace.last().map(|(_, n)| *n).unwrap_or(Node::Start)
     }
@@ -144,7 +249,7 @@ impl Execution {
     pub fn is_stuck(&self, pg: &ProgramGraph) -> bool {
         pg.outgoing(self.current_node())
             .iter()
-            .all(|Edge(_, action, _)| action.semantics(self.current_mem()).is_err())
+            .all(|edge| self.step(pg, edge).is_none())
     }
     pub fn state(&self, pg: &ProgramGraph) -> TerminationState {
         if self.is_stuck(pg) {
@@ -157,28 +262,191 @@ impl Execution {
             TerminationState::Running
         }
     }
-    pub fn nexts(&self, pg: &ProgramGraph) -> Vec<Execution> {
-        if self.is_stuck(pg) {
-            return vec![];
+    /// Visits the configurations reachable from this execution in
+    /// breadth-first order, visiting each distinct configuration once, until
+    /// all of them or `bound` of them have been visited.
+    pub fn explore(&self, pg: &ProgramGraph, bound: usize) -> Exploration {
+        let mut exploration = Exploration {
+            terminated: vec![],
+            stuck: vec![],
+            visited: 0,
+            complete: false,
+        };
+        let mut seen = BTreeSet::new();
+        let mut final_memories = BTreeSet::new();
+        let mut stuck = BTreeSet::new();
+        let mut pending = VecDeque::from([self.clone()]);
+
+        while let Some(exe) = pending.pop_front() {
+            if !seen.insert(exe.configuration()) {
+                continue;
+            }
+            if seen.len() > bound {
+                return exploration;
+            }
+            exploration.visited += 1;
+
+            let nexts = exe.nexts(pg);
+            if nexts.is_empty() {
+                if exe.is_finished() {
+                    if final_memories.insert(exe.current_mem().clone()) {
+                        exploration.terminated.push(exe);
+                    }
+                } else if stuck.insert((exe.current_node(), exe.current_mem().clone())) {
+                    exploration.stuck.push(exe);
+                }
+                continue;
+            }
+            pending.extend(nexts);
         }
 
-        let mem = self.current_mem();
+        exploration.complete = true;
+        exploration
+    }
+    /// The outcomes of all continuations of this execution, or `None` if
+    /// more than `bound` distinct configurations are reachable.
+    ///
+    /// Continuations which revisit a configuration loop forever, and have no
+    /// outcome.
+    pub fn outcomes(&self, pg: &ProgramGraph, bound: usize) -> Option<BTreeSet<Outcome>> {
+        let exploration = self.explore(pg, bound);
+        if !exploration.complete {
+            return None;
+        }
+        let terminated = exploration
+            .final_memories()
+            .cloned()
+            .map(Outcome::Terminated);
+        let stuck = exploration
+            .stuck
+            .iter()
+            .map(|exe| Outcome::Stuck(exe.current_mem().clone()));
+        Some(terminated.chain(stuck).collect())
+    }
+    /// The current node, stack and memory, which determine all continuations
+    /// of the execution.
+    fn configuration(&self) -> (Node, Vec<Frame>, InterpreterMemory) {
+        (
+            self.current_node(),
+            self.stack.clone(),
+            self.current_mem().clone(),
+        )
+    }
+    pub fn nexts(&self, pg: &ProgramGraph) -> Vec<Execution> {
         pg.outgoing(self.current_node())
             .iter()
-            .filter_map(|Edge(_, action, next_node)| {
-                action.semantics(mem).ok().map(|next_mem| {
-                    let mut next = self.clone();
-                    next.trace.push((
-                        Step {
-                            node: next_node.to_string(),
-                            action: Stringify::new(action.clone()),
-                            memory: next_mem,
-                        },
-                        *next_node,
-                    ));
-                    next
-                })
-            })
+            .filter_map(|edge| self.step(pg, edge))
             .collect_vec()
     }
+
+    /// Takes `edge` from the current node, if it is enabled.
+    fn step(&self, pg: &ProgramGraph, edge: &Edge) -> Option<Execution> {
+        let Edge(from, action, next_node, span) = edge;
+        let mem = WithIntegers {
+            context: self.current_mem().clone(),
+            integers: self.integers,
+        };
+        let mem = &mem;
+        let mut stack = self.stack.clone();
+
+        let next_mem = match action {
+            Action::Call(call) => {
+                let frame = Frame {
+                    return_node: pg.return_node(*from)?,
+                    saved: vec![],
+                };
+                let (next_mem, frame) = call_semantics(pg, call, mem, frame).ok()?;
+                stack.push(frame);
+                next_mem
+            }
+            Action::Return(call) => {
+                // NOTE: only the return to the most recent caller is enabled
+                if stack.last()?.return_node != *next_node {
+                    return None;
+                }
+                let frame = stack.pop()?;
+                return_semantics(pg, call, mem, frame).ok()?
+            }
+            _ => action.semantics(mem).ok()?,
+        }
+        .context;
+
+        let mut next = self.clone();
+        next.stack = stack;
+        next.trace.push((
+            Step {
+                node: next_node.to_string(),
+                action: Stringify::new(action.clone()),
+                span: *span,
+                memory: next_mem,
+            },
+            *next_node,
+        ));
+        Some(next)
+    }
+}
+
+/// Binds the arguments of `call` to the parameters of the procedure, and its
+/// results to zero, saving the values they shadow in `frame`.
+fn call_semantics(
+    pg: &ProgramGraph,
+    call: &Call,
+    mem: &WithIntegers<InterpreterMemory>,
+    mut frame: Frame,
+) -> Result<(WithIntegers<InterpreterMemory>, Frame), SemanticsError> {
+    let signature = pg
+        .procedure(&call.name)
+        .ok_or(SemanticsError::CallWithoutStack)?;
+    let args = call
+        .args
+        .iter()
+        .map(|a| a.semantics(mem))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut next = mem.clone();
+    let values = args
+        .into_iter()
+        .chain(std::iter::repeat(Integer::default()))
+        .take(signature.params.len() + signature.results.len());
+    for (local, value) in signature.locals().zip(values) {
+        let previous = next.context.variables.insert(local.clone(), value);
+        frame.saved.push((local.clone(), previous));
+    }
+    Ok((next, frame))
+}
+
+/// Restores the variables shadowed by the call, and assigns the results of
+/// the procedure to the targets of `call`.
+fn return_semantics(
+    pg: &ProgramGraph,
+    call: &Call,
+    mem: &WithIntegers<InterpreterMemory>,
+    frame: Frame,
+) -> Result<WithIntegers<InterpreterMemory>, SemanticsError> {
+    let signature = pg
+        .procedure(&call.name)
+        .ok_or(SemanticsError::CallWithoutStack)?;
+    let results = signature
+        .results
+        .iter()
+        .map(|r| mem.variable(r))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut next = mem.clone();
+    for (local, previous) in frame.saved.into_iter().rev() {
+        match previous {
+            Some(value) => next.context.variables.insert(local, value),
+            None => next.context.variables.remove(&local),
+        };
+    }
+    for (target, value) in call.results.iter().zip(results) {
+        next = match target {
+            Target::Variable(x) => next.set_variable(x, value)?,
+            Target::Array(arr, idx) => {
+                let idx = idx.semantics(&next)?;
+                next.set_array_element(arr, &idx, value)?
+            }
+        };
+    }
+    Ok(next)
 }
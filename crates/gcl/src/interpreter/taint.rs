@@ -0,0 +1,160 @@
+//! Dynamic tracking of information flow in executions.
+//!
+//! Every value is labelled with the targets whose initial values it was
+//! computed from. Assigning a value exercises a flow from each target in its
+//! label into the target assigned. Values computed within the branch of a
+//! guard which was taken are also labelled with the label of the guard, until
+//! the branch ends at its [`ProgramGraph::join_node`], as in the static
+//! analysis of `ce-security`. Only the guards actually taken exercise
+//! implicit flows, such that an execution may not exercise all the flows
+//! found by a static analysis.
+//!
+//! Procedures are not supported, and calls and returns exercise no flows.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Execution, InterpreterMemory};
+use crate::{
+    ast::{AExpr, Target},
+    pg::{Action, Edge, Node, ProgramGraph},
+};
+
+/// The targets whose initial values a value was computed from.
+pub type Label = BTreeSet<Target>;
+
+/// An [`Execution`] which labels the values of the memory with the flows they
+/// originate from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaintedExecution {
+    execution: Execution,
+    /// The label of the value of each target. All elements of an array share
+    /// a single label.
+    labels: BTreeMap<Target, Label>,
+    /// The branches currently executed, innermost last, as the node each
+    /// branch ends at along with the label of its guard.
+    branches: Vec<(Node, Label)>,
+    /// The flows exercised so far, from a target whose initial value is used
+    /// into the target assigned.
+    flows: BTreeSet<(Target, Target)>,
+}
+
+impl TaintedExecution {
+    /// An execution where the initial value of every target of
+    /// `initial_memory` is labelled with the target itself.
+    pub fn new(initial_memory: InterpreterMemory) -> Self {
+        Self::from_execution(Execution::new(initial_memory))
+    }
+    /// Tracks the flows of `execution` from its current memory, which is
+    /// considered its initial memory.
+    pub fn from_execution(execution: Execution) -> Self {
+        let mem = execution.current_mem();
+        let labels = mem
+            .variables
+            .keys()
+            .chain(mem.bool_variables.keys())
+            .map(|v| Target::Variable(v.clone()))
+            .chain(
+                mem.arrays
+                    .keys()
+                    .chain(mem.bool_arrays.keys())
+                    .map(|a| Target::Array(a.clone(), ())),
+            )
+            .map(|t| (t.clone(), Label::from([t])))
+            .collect();
+        TaintedExecution {
+            execution,
+            labels,
+            branches: vec![],
+            flows: Default::default(),
+        }
+    }
+
+    pub fn execution(&self) -> &Execution {
+        &self.execution
+    }
+    /// The label of the current value of `target`.
+    pub fn label(&self, target: &Target) -> Label {
+        self.labels.get(target).cloned().unwrap_or_default()
+    }
+    /// The flows exercised so far, from a target whose initial value is used
+    /// into the target assigned.
+    pub fn flows(&self) -> &BTreeSet<(Target, Target)> {
+        &self.flows
+    }
+
+    /// The continuations of the execution, as in [`Execution::nexts`].
+    pub fn nexts(&self, pg: &ProgramGraph) -> Vec<TaintedExecution> {
+        pg.outgoing(self.execution.current_node())
+            .iter()
+            .filter_map(|edge| {
+                let execution = self.execution.step(pg, edge)?;
+                let mut next = self.clone();
+                next.execution = execution;
+                next.propagate(pg, edge);
+                Some(next)
+            })
+            .collect()
+    }
+
+    /// The label of the values computed from the targets `uses`, including
+    /// the guards of the branches currently executed.
+    fn label_of(&self, uses: impl IntoIterator<Item = Target>) -> Label {
+        uses.into_iter()
+            .map(|t| self.label(&t))
+            .chain(self.branches.iter().map(|(_, label)| label.clone()))
+            .flatten()
+            .collect()
+    }
+
+    fn assign(&mut self, target: Target, label: Label, weak: bool) {
+        self.flows
+            .extend(label.iter().map(|o| (o.clone(), target.clone())));
+        let current = self.labels.entry(target).or_default();
+        if weak {
+            current.extend(label);
+        } else {
+            *current = label;
+        }
+    }
+
+    fn assign_target(&mut self, target: &Target<Box<AExpr>>, label: Label) {
+        match target {
+            Target::Variable(v) => self.assign(Target::Variable(v.clone()), label, false),
+            // NOTE: the other elements of the array keep their labels
+            Target::Array(a, _) => self.assign(Target::Array(a.clone(), ()), label, true),
+        }
+    }
+
+    fn propagate(&mut self, pg: &ProgramGraph, edge: &Edge) {
+        let index = |t: &Target<Box<AExpr>>| match t {
+            Target::Variable(_) => Default::default(),
+            Target::Array(_, idx) => idx.fv(),
+        };
+
+        match edge.action() {
+            Action::Assignment(x, a) => {
+                let label = self.label_of(index(x).into_iter().chain(a.fv()));
+                self.assign_target(x, label);
+            }
+            Action::BoolAssignment(x, b) => {
+                let label = self.label_of(index(x).into_iter().chain(b.fv()));
+                self.assign_target(x, label);
+            }
+            Action::Skip | Action::Call(_) | Action::Return(_) => {}
+            Action::Condition(b) => {
+                if let Some(join) = pg.join_node(edge.to()) {
+                    let label = self.label_of(b.fv());
+                    self.branches.push((join, label));
+                }
+            }
+        }
+
+        // NOTE: branches are nested, so the inner branches end first
+        while let Some((join, _)) = self.branches.last() {
+            if *join != edge.to() {
+                break;
+            }
+            self.branches.pop();
+        }
+    }
+}
@@ -1,9 +1,15 @@
+#[cfg(test)]
+mod tests;
+
 pub mod ast;
 mod ast_ext;
 mod fmt;
 mod gcl;
+pub mod integer;
 pub mod interpreter;
 pub mod memory;
 pub mod parse;
 pub mod pg;
 pub mod semantics;
+pub mod transform;
+pub mod typeck;
@@ -1,10 +1,31 @@
+use itertools::Itertools;
 use miette::Diagnostic;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::ast::{BExpr, Commands, Flow, SecurityClass};
+use crate::{
+    ast::{AExpr, BExpr, Commands, Flow, SecurityClass},
+    typeck::{ProcedureError, Type},
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A location in the source. Syntax constructed programmatically, rather than
+/// parsed, has the empty default span.
+#[derive(
+    tapi::Tapi,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[tapi(path = "GCL")]
 pub struct SourceSpan {
     /// The start of the span.
     offset: usize,
@@ -62,35 +83,94 @@ impl From<(usize, usize)> for SourceSpan {
     }
 }
 
-pub fn parse_commands(src: &str) -> Result<Commands, ParseError> {
+type Errors<'input> =
+    Vec<lalrpop_util::ErrorRecovery<usize, lalrpop_util::lexer::Token<'input>, CustomError>>;
+
+/// Parses and type checks a program, recovering from syntax errors such that
+/// all of them are reported at once.
+pub fn parse_commands(src: &str) -> Result<Commands, ParseErrors> {
     static PARSER: Lazy<crate::gcl::CommandsParser> = Lazy::new(crate::gcl::CommandsParser::new);
 
-    PARSER.parse(src).map_err(|e| ParseError::new(src, e))
+    let mut errors = Errors::new();
+    let result = PARSER.parse(&mut errors, src);
+    let mut errors = errors
+        .into_iter()
+        .map(|e| ParseError::new(src, e.error))
+        .collect::<Vec<_>>();
+    match result {
+        Ok(cmds) if errors.is_empty() => {
+            cmds.check_procedures().map_err(|errors| ParseErrors {
+                errors: errors
+                    .into_iter()
+                    .map(|e| ParseError::procedure(src, e))
+                    .collect(),
+            })?;
+            cmds.check_types().map_err(|errors| ParseErrors {
+                errors: errors
+                    .into_iter()
+                    .map(|e| ParseError::TypeMismatch {
+                        src: format!("{src}\n"),
+                        err_span: e.span,
+                        target: e.target.to_string(),
+                        expected: e.expected,
+                        found: e.found,
+                    })
+                    .collect(),
+            })
+        }
+        Ok(_) => Err(ParseErrors { errors }),
+        Err(e) => {
+            errors.push(ParseError::new(src, e));
+            Err(ParseErrors { errors })
+        }
+    }
 }
 
 pub fn parse_bexpr(src: &str) -> Result<BExpr, ParseError> {
     static PARSER: Lazy<crate::gcl::BExprParser> = Lazy::new(crate::gcl::BExprParser::new);
 
-    PARSER.parse(src).map_err(|e| ParseError::new(src, e))
+    PARSER
+        .parse(&mut Errors::new(), src)
+        .map_err(|e| ParseError::new(src, e))
 }
 
-pub fn parse_aexpr(src: &str) -> Result<crate::ast::AExpr, ParseError> {
+pub fn parse_aexpr(src: &str) -> Result<AExpr, ParseError> {
     static PARSER: Lazy<crate::gcl::AExprParser> = Lazy::new(crate::gcl::AExprParser::new);
 
-    PARSER.parse(src).map_err(|e| ParseError::new(src, e))
+    PARSER
+        .parse(&mut Errors::new(), src)
+        .map_err(|e| ParseError::new(src, e))
 }
 
 pub fn parse_action(src: &str) -> Result<crate::pg::Action, ParseError> {
     static PARSER: Lazy<crate::gcl::ActionParser> = Lazy::new(crate::gcl::ActionParser::new);
 
-    PARSER.parse(src).map_err(|e| ParseError::new(src, e))
+    PARSER
+        .parse(&mut Errors::new(), src)
+        .map_err(|e| ParseError::new(src, e))
 }
 
 pub fn parse_security_lattice(src: &str) -> Result<Vec<Flow<SecurityClass>>, ParseError> {
     static PARSER: Lazy<crate::gcl::SecurityLatticeParser> =
         Lazy::new(crate::gcl::SecurityLatticeParser::new);
 
-    PARSER.parse(src).map_err(|e| ParseError::new(src, e))
+    PARSER
+        .parse(&mut Errors::new(), src)
+        .map_err(|e| ParseError::new(src, e))
+}
+
+/// The syntax errors of a program, in the order they were encountered.
+#[derive(Debug, Error, Diagnostic, Clone)]
+#[error("{}", self.errors.iter().map(|e| e.describe()).format("\n"))]
+pub struct ParseErrors {
+    #[related]
+    errors: Vec<ParseError>,
+}
+
+impl ParseErrors {
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
 }
 
 #[derive(Debug, Error, Diagnostic, Clone)]
@@ -104,7 +184,7 @@ pub enum ParseError {
         err_span: SourceSpan,
     },
     #[error("Unrecognized Token")]
-    #[diagnostic(help("Expected tokens here are: {expected}{}", if let Some(hint) = hint { format!("\n{hint}") } else { "".to_string() }))]
+    #[diagnostic(help("Expected one of: {expected}{}", if let Some(hint) = hint { format!("\n{hint}") } else { "".to_string() }))]
     UnrecognizedToken {
         #[source_code]
         src: String,
@@ -115,7 +195,7 @@ pub enum ParseError {
         hint: Option<String>,
     },
     #[error("Unrecognized EOF")]
-    #[diagnostic(help("Expected tokens in this context are:\n{expected}"))]
+    #[diagnostic(help("Expected one of: {expected}"))]
     UnrecognizedEof {
         #[source_code]
         src: String,
@@ -123,6 +203,74 @@ pub enum ParseError {
         err_span: SourceSpan,
         expected: String,
     },
+    #[error("Extra Token")]
+    #[diagnostic(help("Remove the token, or add a `;` before it"))]
+    ExtraToken {
+        #[source_code]
+        src: String,
+        #[label = "The token \"{token}\" is not expected after the end of the program"]
+        err_span: SourceSpan,
+        token: String,
+    },
+    #[error("Expected a boolean")]
+    #[diagnostic(help("Compare the expression using `=`, `<`, ... to get a boolean"))]
+    ExpectedBoolean {
+        #[source_code]
+        src: String,
+        #[label = "This is an integer expression, but a boolean is expected here"]
+        err_span: SourceSpan,
+    },
+    #[error("Mismatched types")]
+    #[diagnostic(help("A variable or array holds either integers or booleans, but not both"))]
+    TypeMismatch {
+        #[source_code]
+        src: String,
+        #[label = "`{target}` is used as {found} here, but as {expected} elsewhere"]
+        err_span: SourceSpan,
+        target: String,
+        expected: Type,
+        found: Type,
+    },
+    #[error("Undeclared procedure")]
+    #[diagnostic(help(
+        "Declare the procedure with `proc {name}(...) ... end` at the start of the program"
+    ))]
+    UndeclaredProcedure {
+        #[source_code]
+        src: String,
+        #[label = "No procedure named `{name}` is declared"]
+        err_span: SourceSpan,
+        name: String,
+    },
+    #[error("Procedure declared more than once")]
+    #[diagnostic(help("Rename one of the procedures"))]
+    RedeclaredProcedure {
+        #[source_code]
+        src: String,
+        #[label = "A procedure named `{name}` is already declared"]
+        err_span: SourceSpan,
+        name: String,
+    },
+    #[error("Wrong number of arguments")]
+    #[diagnostic(help("Arguments and results are separated by `;`, as in `call {name}(x, y; z)`"))]
+    ArityMismatch {
+        #[source_code]
+        src: String,
+        #[label = "`{name}` takes {expected}, but is called with {found}"]
+        err_span: SourceSpan,
+        name: String,
+        expected: String,
+        found: String,
+    },
+    #[error("Call in parallel composition")]
+    #[diagnostic(help("Procedures can only be called outside of `par ... rap`"))]
+    ParallelCall {
+        #[source_code]
+        src: String,
+        #[label = "`{name}` is called inside a parallel composition"]
+        err_span: SourceSpan,
+        name: String,
+    },
     #[error("Integer is too large")]
     #[diagnostic(help("The integer is too large to be represented"))]
     IntegerTooLarge {
@@ -132,24 +280,125 @@ pub enum ParseError {
         err_span: SourceSpan,
     },
 }
-// impl ParseError {
-//     pub fn span(&self) -> Span {
-//         match self {
-//             ParseError::InvalidToken { err_span, .. }
-//             | ParseError::UnrecognizedToken { err_span, .. }
-//             | ParseError::UnrecognizedEof { err_span, .. } => Span {
-//                 start: err_span.offset(),
-//                 end: err_span.offset() + err_span.len(),
-//             },
-//         }
-//     }
-// }
+
+impl ParseError {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            ParseError::InvalidToken { err_span, .. }
+            | ParseError::UnrecognizedToken { err_span, .. }
+            | ParseError::UnrecognizedEof { err_span, .. }
+            | ParseError::ExtraToken { err_span, .. }
+            | ParseError::ExpectedBoolean { err_span, .. }
+            | ParseError::TypeMismatch { err_span, .. }
+            | ParseError::UndeclaredProcedure { err_span, .. }
+            | ParseError::RedeclaredProcedure { err_span, .. }
+            | ParseError::ArityMismatch { err_span, .. }
+            | ParseError::ParallelCall { err_span, .. }
+            | ParseError::IntegerTooLarge { err_span, .. } => *err_span,
+        }
+    }
+
+    /// The source code in which the error was found.
+    pub fn src(&self) -> &str {
+        match self {
+            ParseError::InvalidToken { src, .. }
+            | ParseError::UnrecognizedToken { src, .. }
+            | ParseError::UnrecognizedEof { src, .. }
+            | ParseError::ExtraToken { src, .. }
+            | ParseError::ExpectedBoolean { src, .. }
+            | ParseError::TypeMismatch { src, .. }
+            | ParseError::UndeclaredProcedure { src, .. }
+            | ParseError::RedeclaredProcedure { src, .. }
+            | ParseError::ArityMismatch { src, .. }
+            | ParseError::ParallelCall { src, .. }
+            | ParseError::IntegerTooLarge { src, .. } => src,
+        }
+    }
+
+    /// A single line description of the error with its location, such as
+    /// `2:5: Unrecognized Token. Expected one of: ...`
+    pub fn describe(&self) -> String {
+        let (line, column) = line_column(self.src(), self.span().offset());
+        match self.help() {
+            Some(help) => format!("{line}:{column}: {self}. {help}"),
+            None => format!("{line}:{column}: {self}"),
+        }
+    }
+}
+
+/// The 1-based line and column of the byte `offset` in `src`, counting columns
+/// in UTF-16 code units as editors do.
+pub fn line_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].encode_utf16().count() + 1,
+    )
+}
 
 pub(crate) enum CustomError {
     IntegerTooLarge { from: usize, to: usize },
+    ExpectedBoolean { from: usize, to: usize },
+}
+
+/// The right-hand side of an assignment, before it is known whether a bare
+/// reference is an integer or a boolean.
+pub(crate) enum Rhs {
+    Int(AExpr),
+    Bool(BExpr),
+}
+
+/// Names the tokens matched by regular expressions, rather than listing the
+/// expressions themselves.
+fn format_expected(expected: &[String]) -> String {
+    expected
+        .iter()
+        .map(|t| match t.as_str() {
+            r##"r#"[a-zA-Z][_a-zA-Z0-9]*"#"## => "identifier",
+            r##"r#"[0-9]+"#"## => "integer",
+            t => t,
+        })
+        .format(", ")
+        .to_string()
 }
 
 impl ParseError {
+    fn procedure(src: &str, e: ProcedureError) -> Self {
+        let src = format!("{src}\n");
+        let arity =
+            |(args, results): (usize, usize)| format!("{args} argument(s) and {results} result(s)");
+        match e {
+            ProcedureError::Undeclared { name, span } => ParseError::UndeclaredProcedure {
+                src,
+                err_span: span,
+                name: name.to_string(),
+            },
+            ProcedureError::Redeclared { name, span } => ParseError::RedeclaredProcedure {
+                src,
+                err_span: span,
+                name: name.to_string(),
+            },
+            ProcedureError::Arity {
+                name,
+                expected,
+                found,
+                span,
+            } => ParseError::ArityMismatch {
+                src,
+                err_span: span,
+                name: name.to_string(),
+                expected: arity(expected),
+                found: arity(found),
+            },
+            ProcedureError::Parallel { name, span } => ParseError::ParallelCall {
+                src,
+                err_span: span,
+                name: name.to_string(),
+            },
+        }
+    }
+
     pub(crate) fn new(
         src: &str,
         e: lalrpop_util::ParseError<usize, lalrpop_util::lexer::Token, CustomError>,
@@ -165,7 +414,7 @@ impl ParseError {
                 ParseError::UnrecognizedEof {
                     src: prep_src(),
                     err_span: (location, 0).into(),
-                    expected: expected.join(", "),
+                    expected: format_expected(&expected),
                 }
             }
             lalrpop_util::ParseError::UnrecognizedToken { token, expected } => {
@@ -173,15 +422,23 @@ impl ParseError {
                     src: prep_src(),
                     err_span: (token.0, token.2 - token.0).into(),
                     token: token.1.to_string(),
-                    expected: expected.join(", "),
+                    expected: format_expected(&expected),
                     hint: None,
                 }
             }
-            lalrpop_util::ParseError::ExtraToken { .. } => todo!(),
+            lalrpop_util::ParseError::ExtraToken { token } => ParseError::ExtraToken {
+                src: prep_src(),
+                err_span: (token.0, token.2 - token.0).into(),
+                token: token.1.to_string(),
+            },
             lalrpop_util::ParseError::User { error } => match error {
                 CustomError::IntegerTooLarge { from, to } => ParseError::IntegerTooLarge {
                     src: prep_src(),
-                    err_span: (from, to).into(),
+                    err_span: (from, to - from).into(),
+                },
+                CustomError::ExpectedBoolean { from, to } => ParseError::ExpectedBoolean {
+                    src: prep_src(),
+                    err_span: (from, to - from).into(),
                 },
             },
         }
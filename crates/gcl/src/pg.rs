@@ -1,22 +1,70 @@
 pub mod analysis;
+pub mod equivalence;
+pub mod formats;
+pub mod reduce;
+pub mod slice;
 
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     str::FromStr,
-    sync::atomic::AtomicU64,
 };
 
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::ast::{AExpr, BExpr, Command, Commands, Guard, LogicOp, Target};
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        AExpr, BExpr, Call, Command, CommandKind, Commands, Guard, LogicOp, Procedure,
+        ProcedureName, Target, Variable, ignore_spans,
+    },
+    parse::SourceSpan,
+};
 
 #[derive(Debug, Clone)]
 pub struct ProgramGraph {
     edges: Vec<Edge>,
     nodes: IndexSet<Node>,
     outgoing: IndexMap<Node, Vec<Edge>>,
+    procedures: IndexMap<ProcedureName, Signature>,
+    /// The node each call returns to, by the node the call is made from.
+    returns: IndexMap<Node, Node>,
+    /// The node the branch of each guard ends at, by the node the guard
+    /// leads to.
+    joins: IndexMap<Node, Node>,
+}
+
+/// The formal parameters and results of a procedure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub params: Vec<Variable>,
+    pub results: Vec<Variable>,
+}
+
+impl Signature {
+    /// The variables local to a call of the procedure.
+    pub fn locals(&self) -> impl Iterator<Item = &Variable> {
+        self.params.iter().chain(&self.results)
+    }
+}
+
+/// How calls of procedures are turned into edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallMode {
+    /// Each procedure has a single subgraph, entered by `call` edges from every
+    /// call and left by `return` edges to every caller.
+    CallReturn,
+    /// Each call has its own copy of the subgraph of the procedure, such that
+    /// it is only left by the `return` edge to the caller.
+    Inline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InlineError {
+    #[error("the procedure `{procedure}` is recursive, and cannot be inlined")]
+    Recursive { procedure: ProcedureName },
 }
 
 #[derive(
@@ -77,30 +125,28 @@ impl std::fmt::Display for Node {
     }
 }
 
-static NODE_ID: AtomicU64 = AtomicU64::new(0);
-impl Node {
-    fn fresh() -> Node {
-        Node::Node(NodeId(
-            NODE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
-        ))
-    }
-    fn reset() {
-        NODE_ID.store(0, std::sync::atomic::Ordering::Relaxed);
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Action {
     Assignment(Target<Box<AExpr>>, AExpr),
+    BoolAssignment(Target<Box<AExpr>>, BExpr),
     Skip,
     Condition(BExpr),
+    /// Evaluates the arguments and binds them to the parameters of the
+    /// procedure, as its first step.
+    Call(Call),
+    /// Assigns the results of the procedure to the targets of the call, as its
+    /// last step.
+    Return(Call),
 }
 impl Action {
     fn fv(&self) -> IndexSet<Target> {
         match self {
             Action::Assignment(x, a) => x.fv().union(&a.fv()).cloned().collect(),
+            Action::BoolAssignment(x, b) => x.fv().union(&b.fv()).cloned().collect(),
             Action::Skip => Default::default(),
             Action::Condition(b) => b.fv(),
+            Action::Call(call) => call.args.iter().flat_map(|a| a.fv()).collect(),
+            Action::Return(call) => call.results.iter().flat_map(|t| t.fv()).collect(),
         }
     }
 }
@@ -111,8 +157,11 @@ impl FromStr for Action {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Edge(pub Node, pub Action, pub Node);
+#[derive(Debug, Clone)]
+/// An edge from a node to another, along with the span of the command or
+/// guard it originates from.
+pub struct Edge(pub Node, pub Action, pub Node, pub SourceSpan);
+ignore_spans!(Edge, |e| -> (Node, &Action, Node) { (e.0, &e.1, e.2) });
 
 impl Edge {
     pub fn action(&self) -> &Action {
@@ -125,31 +174,67 @@ impl Edge {
     pub fn to(&self) -> Node {
         self.2
     }
+    pub fn span(&self) -> SourceSpan {
+        self.3
+    }
+}
+
+impl std::fmt::Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} -> {:?} labelled `{}`", self.0, self.2, self.1)
+    }
 }
 
 impl std::fmt::Display for Action {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Action::Assignment(v, x) => write!(f, "{v} := {x}"),
+            Action::BoolAssignment(v, b) => write!(f, "{v} := {b}"),
             Action::Skip => write!(f, "skip"),
             Action::Condition(b) => write!(f, "{b}"),
+            Action::Call(call) => write!(f, "call {call}"),
+            Action::Return(call) => write!(f, "return {call}"),
         }
     }
 }
 
+/// The state of the construction of a graph, shared by all commands.
+struct Builder<'a> {
+    det: Determinism,
+    mode: CallMode,
+    procedures: IndexMap<&'a ProcedureName, &'a Procedure>,
+    /// The entry and exit nodes of each procedure in [`CallMode::CallReturn`].
+    subgraphs: IndexMap<&'a ProcedureName, (Node, Node)>,
+    /// The procedures currently being inlined in [`CallMode::Inline`].
+    inlining: Vec<&'a ProcedureName>,
+    returns: IndexMap<Node, Node>,
+    joins: IndexMap<Node, Node>,
+    error: Option<InlineError>,
+    /// The id of the next fresh node. Ids are local to the construction, such
+    /// that graphs constructed concurrently do not share a counter.
+    next_id: u64,
+}
+
 trait Edges {
-    fn edges(&self, det: Determinism, s: Node, t: Node) -> Vec<Edge>;
+    fn edges<'a>(&'a self, b: &mut Builder<'a>, s: Node, t: Node) -> Vec<Edge>;
 }
 
 impl Edges for Commands {
-    fn edges(&self, det: Determinism, s: Node, t: Node) -> Vec<Edge> {
+    fn edges<'a>(&'a self, b: &mut Builder<'a>, s: Node, t: Node) -> Vec<Edge> {
         let mut edges = vec![];
 
+        // NOTE: declarations do not execute, and are handled by `Builder::call`
+        let cmds = self
+            .0
+            .iter()
+            .filter(|cmd| !matches!(cmd.kind, CommandKind::Procedure(_)))
+            .collect_vec();
+
         let mut prev = s;
-        for (idx, cmd) in self.0.iter().enumerate() {
-            let is_last = idx + 1 == self.0.len();
-            let next = if is_last { t } else { Node::fresh() };
-            edges.extend(cmd.edges(det, prev, next));
+        for (idx, cmd) in cmds.iter().enumerate() {
+            let is_last = idx + 1 == cmds.len();
+            let next = if is_last { t } else { b.fresh() };
+            edges.extend(cmd.edges(b, prev, next));
             prev = next;
         }
 
@@ -157,41 +242,135 @@ impl Edges for Commands {
     }
 }
 
+impl<'a> Builder<'a> {
+    fn fresh(&mut self) -> Node {
+        self.next_id += 1;
+        Node::Node(NodeId(self.next_id - 1))
+    }
+    fn call(&mut self, call: &Call, span: SourceSpan, s: Node, t: Node) -> Vec<Edge> {
+        let Some(&procedure) = self.procedures.get(&call.name) else {
+            // NOTE: calls of undeclared procedures are rejected by the parser,
+            // so this only happens for constructed programs
+            return vec![];
+        };
+        self.returns.insert(s, t);
+
+        let (entry, exit, mut edges) = match self.mode {
+            CallMode::CallReturn => {
+                let (entry, exit) = self.subgraphs[&call.name];
+                (entry, exit, vec![])
+            }
+            CallMode::Inline => {
+                if self.inlining.contains(&&procedure.name) {
+                    self.error.get_or_insert(InlineError::Recursive {
+                        procedure: procedure.name.clone(),
+                    });
+                    return vec![];
+                }
+                let (entry, exit) = (self.fresh(), self.fresh());
+                self.inlining.push(&procedure.name);
+                let edges = procedure.body.edges(self, entry, exit);
+                self.inlining.pop();
+                (entry, exit, edges)
+            }
+        };
+
+        edges.push(Edge(s, Action::Call(call.clone()), entry, span));
+        edges.push(Edge(exit, Action::Return(call.clone()), t, span));
+        edges
+    }
+
+    /// The product of the graphs of the components, where each edge is a step
+    /// of one component while the others stay at their node.
+    fn par(&mut self, components: &'a [Commands], s: Node, t: Node) -> Vec<Edge> {
+        let graphs = components
+            .iter()
+            .map(|c| {
+                let (entry, exit) = (self.fresh(), self.fresh());
+                let mut outgoing: IndexMap<Node, Vec<Edge>> = Default::default();
+                for e in c.edges(self, entry, exit) {
+                    outgoing.entry(e.from()).or_default().push(e);
+                }
+                (entry, exit, outgoing)
+            })
+            .collect_vec();
+
+        let initial = graphs.iter().map(|(entry, _, _)| *entry).collect_vec();
+        let terminated = graphs.iter().map(|(_, exit, _)| *exit).collect_vec();
+        let mut names: IndexMap<Vec<Node>, Node> = [(initial.clone(), s), (terminated, t)]
+            .into_iter()
+            .collect();
+
+        let mut edges = vec![];
+        let mut pending = vec![initial];
+        while let Some(state) = pending.pop() {
+            let from = names[&state];
+            for (idx, (_, _, outgoing)) in graphs.iter().enumerate() {
+                for Edge(_, action, to, span) in outgoing.get(&state[idx]).into_iter().flatten() {
+                    let mut next = state.clone();
+                    next[idx] = *to;
+                    let to = match names.get(&next) {
+                        Some(to) => *to,
+                        None => {
+                            let to = self.fresh();
+                            names.insert(next.clone(), to);
+                            pending.push(next);
+                            to
+                        }
+                    };
+                    edges.push(Edge(from, action.clone(), to, *span));
+                }
+            }
+        }
+
+        edges
+    }
+}
+
 /// Computes the edges and the condition which is true iff all guards are false
-fn guard_edges(det: Determinism, guards: &[Guard], s: Node, t: Node) -> (Vec<Edge>, BExpr) {
-    match det {
+fn guard_edges<'a>(
+    builder: &mut Builder<'a>,
+    guards: &'a [Guard],
+    s: Node,
+    t: Node,
+) -> (Vec<Edge>, BExpr) {
+    match builder.det {
         Determinism::Deterministic => {
             // See the "if" and "do" Commands on Page 25 of Formal Methods
-            let mut prev = BExpr::Bool(false);
+            let mut prev = BExpr::bool(false);
 
             let mut edges = vec![];
 
-            for Guard(b, c) in guards {
-                let q = Node::fresh();
+            for Guard {
+                guard_span,
+                guard: b,
+                cmds: c,
+            } in guards
+            {
+                let q = builder.fresh();
+                builder.joins.insert(q, t);
 
                 edges.push(Edge(
                     s,
-                    Action::Condition(BExpr::logic(
-                        b.clone(),
-                        LogicOp::Land,
-                        BExpr::Not(Box::new(prev.clone())),
-                    )),
+                    Action::Condition(BExpr::logic(b.clone(), LogicOp::Land, !prev.clone())),
                     q,
+                    *guard_span,
                 ));
-                edges.extend(c.edges(det, q, t));
+                edges.extend(c.edges(builder, q, t));
                 prev = BExpr::logic(b.to_owned().clone(), LogicOp::Lor, prev);
             }
 
             // Wraps in "not" so that the "d" part can be used directly by "do"
-            (edges, BExpr::Not(Box::new(prev)))
+            (edges, !prev)
         }
         Determinism::NonDeterministic => {
             let e = guards
                 .iter()
-                .flat_map(|Guard(b, c)| {
-                    let q = Node::fresh();
-                    let mut edges = c.edges(det, q, t);
-                    edges.push(Edge(s, Action::Condition(b.clone()), q));
+                .flat_map(|g| {
+                    let q = builder.fresh();
+                    builder.joins.insert(q, t);
+                    let mut edges = g.cmds.edges(builder, q, t);
+                    edges.push(Edge(s, Action::Condition(g.guard.clone()), q, g.guard_span));
                     edges
                 })
                 .collect();
@@ -201,18 +380,34 @@ fn guard_edges(det: Determinism, guards: &[Guard], s: Node, t: Node) -> (Vec<Edg
 }
 
 impl Edges for Command {
-    fn edges(&self, det: Determinism, s: Node, t: Node) -> Vec<Edge> {
-        match self {
-            Command::Assignment(v, expr) => {
-                vec![Edge(s, Action::Assignment(v.clone(), expr.clone()), t)]
+    fn edges<'a>(&'a self, b: &mut Builder<'a>, s: Node, t: Node) -> Vec<Edge> {
+        match &self.kind {
+            CommandKind::Assignment(v, expr) => {
+                vec![Edge(
+                    s,
+                    Action::Assignment(v.clone(), expr.clone()),
+                    t,
+                    self.span,
+                )]
+            }
+            CommandKind::BoolAssignment(v, expr) => {
+                vec![Edge(
+                    s,
+                    Action::BoolAssignment(v.clone(), expr.clone()),
+                    t,
+                    self.span,
+                )]
             }
-            Command::Skip => vec![Edge(s, Action::Skip, t)],
-            Command::If(guards) => guard_edges(det, guards, s, t).0,
-            Command::Loop(guards) => {
-                let (mut edges, b) = guard_edges(det, guards, s, s);
-                edges.push(Edge(s, Action::Condition(b), t));
+            CommandKind::Skip => vec![Edge(s, Action::Skip, t, self.span)],
+            CommandKind::If(guards) => guard_edges(b, guards, s, t).0,
+            CommandKind::Loop(guards) => {
+                let (mut edges, d) = guard_edges(b, guards, s, s);
+                edges.push(Edge(s, Action::Condition(d), t, self.span));
                 edges
             }
+            CommandKind::Procedure(_) => vec![],
+            CommandKind::Call(call) => b.call(call, self.span, s, t),
+            CommandKind::Par(components) => b.par(components, s, t),
         }
     }
 }
@@ -220,15 +415,75 @@ impl Edges for Command {
 fn done(guards: &[Guard]) -> BExpr {
     guards
         .iter()
-        .map(|Guard(b, _c)| BExpr::Not(Box::new(b.clone())))
+        .map(|g| !g.guard.clone())
         .reduce(|a, b| BExpr::logic(a, LogicOp::Land, b))
-        .unwrap_or(BExpr::Bool(true))
+        .unwrap_or(BExpr::bool(true))
 }
 
 impl ProgramGraph {
+    /// Constructs the graph of `cmds`, with procedures in
+    /// [`CallMode::CallReturn`].
     pub fn new(det: Determinism, cmds: &Commands) -> Self {
-        Node::reset();
-        let edges = cmds.edges(det, Node::Start, Node::End);
+        Self::with_mode(det, CallMode::CallReturn, cmds)
+            .expect("only inlining of recursive procedures fails")
+    }
+    /// Constructs the graph of `cmds`, with procedures in [`CallMode::Inline`].
+    pub fn inlined(det: Determinism, cmds: &Commands) -> Result<Self, InlineError> {
+        Self::with_mode(det, CallMode::Inline, cmds)
+    }
+    pub fn with_mode(
+        det: Determinism,
+        mode: CallMode,
+        cmds: &Commands,
+    ) -> Result<Self, InlineError> {
+        let procedures: IndexMap<_, _> = cmds.procedures().map(|p| (&p.name, p)).collect();
+        let mut b = Builder {
+            det,
+            mode,
+            subgraphs: Default::default(),
+            procedures,
+            inlining: vec![],
+            returns: Default::default(),
+            joins: Default::default(),
+            error: None,
+            next_id: 0,
+        };
+        for name in b.procedures.keys().copied().collect_vec() {
+            let subgraph = (b.fresh(), b.fresh());
+            b.subgraphs.insert(name, subgraph);
+        }
+        let mut edges = cmds.edges(&mut b, Node::Start, Node::End);
+        if mode == CallMode::CallReturn {
+            for (name, (entry, exit)) in b.subgraphs.clone() {
+                edges.extend(b.procedures[name].body.edges(&mut b, entry, exit));
+            }
+        }
+        if let Some(err) = b.error {
+            return Err(err);
+        }
+
+        let mut pg = Self::from_edges(edges);
+        pg.procedures = cmds
+            .procedures()
+            .map(|p| {
+                let signature = Signature {
+                    params: p.params.clone(),
+                    results: p.results.clone(),
+                };
+                (p.name.clone(), signature)
+            })
+            .collect();
+        pg.returns = b.returns;
+        // NOTE: the nodes of parallel components are replaced by the nodes of
+        // their product, so guards within them have no joins
+        pg.joins = b
+            .joins
+            .into_iter()
+            .filter(|(q, t)| pg.nodes.contains(q) && pg.nodes.contains(t))
+            .collect();
+        Ok(pg.rename_with_reverse_post_order())
+    }
+    fn from_edges(edges: Vec<Edge>) -> Self {
         let mut outgoing: IndexMap<Node, Vec<Edge>> = Default::default();
         let mut nodes: IndexSet<Node> = Default::default();
 
@@ -242,8 +497,31 @@ impl ProgramGraph {
             outgoing,
             edges,
             nodes,
+            procedures: Default::default(),
+            returns: Default::default(),
+            joins: Default::default(),
         }
-        .rename_with_reverse_post_order()
+    }
+    /// The signature of the procedure called `name`.
+    pub fn procedure(&self, name: &ProcedureName) -> Option<&Signature> {
+        self.procedures.get(name)
+    }
+    /// The node to continue from when returning from a call made from `node`.
+    pub fn return_node(&self, node: Node) -> Option<Node> {
+        self.returns.get(&node).copied()
+    }
+    /// The node where the branch of the guard leading to `node` ends, which
+    /// is the end of the `if`, or the start of the `do`, the guard is part
+    /// of.
+    pub fn join_node(&self, node: Node) -> Option<Node> {
+        self.joins.get(&node).copied()
+    }
+    /// The nodes calls are made from which return to `node`.
+    pub fn call_nodes(&self, node: Node) -> impl Iterator<Item = Node> + '_ {
+        self.returns
+            .iter()
+            .filter(move |(_, r)| **r == node)
+            .map(|(c, _)| *c)
     }
     pub fn edges(&self) -> &[Edge] {
         &self.edges
@@ -267,7 +545,7 @@ impl ProgramGraph {
             "digraph G {{\n{}\n}}",
             self.edges
                 .iter()
-                .map(|Edge(a, t, b)| format!(
+                .map(|Edge(a, t, b, _)| format!(
                     "  {a:?}[label=\"{a}\"]; {a:?} -> {b:?}[label={:?}]; {b:?}[label=\"{b}\"];",
                     t.to_string(),
                 ))
@@ -293,29 +571,75 @@ impl ProgramGraph {
         let node_mapping_rev: BTreeMap<petgraph::graph::NodeIndex, Node> =
             node_mapping.iter().map(|(a, b)| (*b, *a)).collect();
 
-        for Edge(from, action, to) in &self.edges {
+        for Edge(from, action, to, _) in &self.edges {
             g.add_edge(node_mapping[from], node_mapping[to], action.clone());
         }
 
         (g, node_mapping, node_mapping_rev)
     }
 
-    pub fn rename_with_reverse_post_order(&self) -> Self {
+    /// The nodes of the graph in reverse post-order of a depth-first search
+    /// from the initial node, or `None` if the graph has no initial node.
+    pub fn reverse_post_order(&self) -> Option<Vec<Node>> {
         let (g, node_mapping, node_mapping_rev) = self.as_petgraph();
 
-        let initial_node = if let Some(n) = node_mapping.get(&Node::Start) {
-            *n
-        } else {
+        let initial_node = *node_mapping.get(&Node::Start)?;
+        let mut dfs = petgraph::visit::DfsPostOrder::empty(&g);
+
+        // NOTE: procedures which are never called are not reachable from the
+        // initial node, and are ordered after the rest of the graph
+        let mut order = Vec::new();
+        for root in std::iter::once(initial_node).chain(g.node_indices()) {
+            if petgraph::visit::VisitMap::is_visited(&dfs.discovered, &root) {
+                continue;
+            }
+            dfs.move_to(root);
+            let mut tree = VecDeque::new();
+            while let Some(n) = dfs.next(&g) {
+                tree.push_front(node_mapping_rev[&n]);
+            }
+            order.extend(tree);
+        }
+        Some(order)
+    }
+
+    /// The strongly connected components of the graph, such that every edge
+    /// between two components is from an earlier to a later component.
+    pub fn components(&self) -> Vec<Vec<Node>> {
+        let (g, _, node_mapping_rev) = self.as_petgraph();
+        let mut components = petgraph::algo::tarjan_scc(&g);
+        // NOTE: Tarjan's algorithm finds the components in reverse
+        // topological order
+        components.reverse();
+        components
+            .into_iter()
+            .map(|c| c.into_iter().map(|n| node_mapping_rev[&n]).collect())
+            .collect()
+    }
+
+    /// The targets of the edges which do not lead forward in
+    /// [reverse post-order](ProgramGraph::reverse_post_order), such that every
+    /// cycle of the graph passes through a loop head.
+    pub fn loop_heads(&self) -> BTreeSet<Node> {
+        let order: BTreeMap<Node, usize> = self
+            .reverse_post_order()
+            .unwrap_or_else(|| self.nodes.iter().copied().collect())
+            .into_iter()
+            .enumerate()
+            .map(|(idx, n)| (n, idx))
+            .collect();
+        self.edges
+            .iter()
+            .filter(|e| order[&e.to()] <= order[&e.from()])
+            .map(|e| e.to())
+            .collect()
+    }
+
+    pub fn rename_with_reverse_post_order(&self) -> Self {
+        let Some(new_order) = self.reverse_post_order() else {
             tracing::warn!("graph did not have a start node");
             return self.clone();
         };
-        let mut dfs = petgraph::visit::DfsPostOrder::new(&g, initial_node);
-
-        let mut new_order = VecDeque::new();
-
-        while let Some(n) = dfs.next(&g) {
-            new_order.push_front(node_mapping_rev[&n]);
-        }
 
         let mut node_mapping_new: BTreeMap<Node, Node> = Default::default();
 
@@ -345,30 +669,30 @@ impl ProgramGraph {
             }
         }
 
-        Self {
-            edges: self
-                .edges
-                .iter()
-                .map(|Edge(a, action, b)| {
-                    Edge(node_mapping_new[a], action.clone(), node_mapping_new[b])
-                })
-                .collect(),
-            nodes: node_mapping_new.values().copied().collect(),
-            outgoing: self
-                .outgoing
+        self.rename(&node_mapping_new)
+    }
+
+    /// Renames every node `n` of the graph to `names[n]`, where `names` has
+    /// exactly the nodes of the graph as keys.
+    fn rename(&self, names: &BTreeMap<Node, Node>) -> Self {
+        let mut pg = Self::from_edges(
+            self.edges
                 .iter()
-                .map(|(n, outgoing)| {
-                    (
-                        node_mapping_new[n],
-                        outgoing
-                            .iter()
-                            .map(|Edge(a, action, b)| {
-                                Edge(node_mapping_new[a], action.clone(), node_mapping_new[b])
-                            })
-                            .collect(),
-                    )
-                })
+                .map(|Edge(a, action, b, span)| Edge(names[a], action.clone(), names[b], *span))
                 .collect(),
-        }
+        );
+        pg.nodes = names.values().copied().collect();
+        pg.procedures = self.procedures.clone();
+        pg.returns = self
+            .returns
+            .iter()
+            .map(|(c, r)| (names[c], names[r]))
+            .collect();
+        pg.joins = self
+            .joins
+            .iter()
+            .map(|(q, t)| (names[q], names[t]))
+            .collect();
+        pg
     }
 }
@@ -1,9 +1,11 @@
-use std::collections::VecDeque;
+pub mod zone;
+
+use std::collections::{BTreeSet, VecDeque};
 
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 
-use crate::pg::{Edge, Node, ProgramGraph};
+use crate::pg::{Action, Edge, Node, ProgramGraph};
 
 pub enum Direction {
     Forward,
@@ -16,6 +18,24 @@ pub trait MonotoneFramework {
     fn direction() -> Direction;
     fn initial(&self, pg: &ProgramGraph) -> Self::Domain;
     fn debug(&self, _item: &Self::Domain) {}
+    /// The fact at the entry of the procedure called along `e`, in
+    /// [`call_string_analysis`].
+    fn call(&self, pg: &ProgramGraph, e: &Edge, prev: &Self::Domain) -> Self::Domain {
+        self.semantic(pg, e, prev)
+    }
+    /// The fact after returning along `e`, in [`call_string_analysis`], where
+    /// `call` is the fact before the matching call and `prev` the fact at the
+    /// exit of the procedure.
+    fn ret(
+        &self,
+        pg: &ProgramGraph,
+        e: &Edge,
+        call: &Self::Domain,
+        prev: &Self::Domain,
+    ) -> Self::Domain {
+        let _ = call;
+        self.semantic(pg, e, prev)
+    }
 }
 
 pub trait Lattice: Sized + Clone {
@@ -25,6 +45,29 @@ pub trait Lattice: Sized + Clone {
     }
     fn lub(&self, other: &Self) -> Self;
     fn contains(&self, other: &Self) -> bool;
+    /// An upper bound of both elements, which may be less precise than
+    /// [`Lattice::lub`], such that repeated widening reaches a fixpoint in
+    /// lattices with infinite ascending chains. The analyses widen the facts
+    /// of a [loop head](ProgramGraph::loop_heads) once it has been updated
+    /// [`WIDENING_DELAY`] times, and never widen the facts of other nodes.
+    fn widen(&self, other: &Self) -> Self {
+        self.lub(other)
+    }
+}
+
+/// The number of times the facts of a loop head are updated by
+/// [`Lattice::lub_extend`] before they are updated by [`Lattice::widen`].
+pub const WIDENING_DELAY: usize = 3;
+
+/// Updates `target` with `constraint`, widening if it is the fact of a loop
+/// head which has been updated [`WIDENING_DELAY`] times already.
+fn update<D: Lattice>(target: &mut D, constraint: &D, updates: &mut usize, loop_head: bool) {
+    if !loop_head || *updates < WIDENING_DELAY {
+        target.lub_extend(constraint);
+    } else {
+        *target = target.widen(constraint);
+    }
+    *updates += 1;
 }
 
 pub trait Worklist {
@@ -63,37 +106,93 @@ impl Worklist for LiFo {
     }
 }
 
-// pub struct RoundRobin(VecDeque<Node>, IndexSet<Node>);
-// impl Worklist for RoundRobin {
-//     fn empty() -> Self {
-//         RoundRobin(Default::default(), Default::default())
-//     }
-
-//     fn insert(&mut self, n: Node) {
-//         let RoundRobin(v, p) = self;
-//         if !v.contains(&n) {
-//             p.insert(n);
-//         }
-//     }
-
-//     fn extract(&mut self, pg: &ProgramGraph) -> Option<Node> {
-//         let RoundRobin(v, p) = self;
-//         match (v.is_empty(), p.is_empty()) {
-//             (true, true) => return None,
-//             (true, false) => {
-//                 *v = pg.reverse_post_order().filter(|n|
-// p.contains(n)).collect();                 p.clear();
-//                 v.pop_back()
-//             }
-//             _ => v.pop_back(),
-//         }
-//     }
-// }
+/// Extracts the pending node which is first in reverse post-order, such that
+/// a node is usually processed after the nodes preceding it. Suited to
+/// forward analyses.
+pub struct ReversePostOrder(Ordered);
+impl Worklist for ReversePostOrder {
+    fn empty() -> Self {
+        ReversePostOrder(Ordered::default())
+    }
+
+    fn insert(&mut self, n: Node) {
+        self.0.pending.insert(n);
+    }
+
+    fn extract(&mut self, pg: &ProgramGraph) -> Option<Node> {
+        self.0
+            .extract(|| pg.reverse_post_order().unwrap_or_default())
+    }
+}
+
+/// Extracts the pending node of the earliest strongly connected component,
+/// in reverse post-order within the component, such that every loop is
+/// stabilized before the nodes after it are processed. Suited to forward
+/// analyses.
+pub struct LoopAware(Ordered);
+impl Worklist for LoopAware {
+    fn empty() -> Self {
+        LoopAware(Ordered::default())
+    }
+
+    fn insert(&mut self, n: Node) {
+        self.0.pending.insert(n);
+    }
+
+    fn extract(&mut self, pg: &ProgramGraph) -> Option<Node> {
+        self.0.extract(|| {
+            let rpo = pg.reverse_post_order().unwrap_or_default();
+            pg.components()
+                .into_iter()
+                .flat_map(|mut component| {
+                    component.sort_by_key(|n| rpo.iter().position(|m| m == n));
+                    component
+                })
+                .collect()
+        })
+    }
+}
+
+/// Pending nodes without duplicates, extracted in the order of the graph
+/// computed on the first extraction.
+#[derive(Default)]
+struct Ordered {
+    order: Option<IndexMap<Node, usize>>,
+    pending: IndexSet<Node>,
+}
+
+impl Ordered {
+    fn extract(&mut self, order: impl FnOnce() -> Vec<Node>) -> Option<Node> {
+        let order = self.order.get_or_insert_with(|| {
+            order()
+                .into_iter()
+                .enumerate()
+                .map(|(idx, n)| (n, idx))
+                .collect()
+        });
+        let (idx, _) = self
+            .pending
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, n)| order.get(*n).copied().unwrap_or(usize::MAX))?;
+        self.pending.shift_remove_index(idx)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AnalysisResults<A: MonotoneFramework> {
     pub facts: IndexMap<Node, A::Domain>,
     pub semantic_calls: usize,
+    /// The number of times each node was extracted from the worklist.
+    pub iterations: IndexMap<Node, usize>,
+}
+
+impl<A: MonotoneFramework> AnalysisResults<A> {
+    /// The number of nodes extracted from the worklist before the fixpoint
+    /// was reached.
+    pub fn steps(&self) -> usize {
+        self.iterations.values().sum()
+    }
 }
 
 pub fn mono_analysis<A: MonotoneFramework, W: Worklist>(
@@ -105,8 +204,10 @@ pub fn mono_analysis<A: MonotoneFramework, W: Worklist>(
     let bot = A::Domain::bottom();
 
     let mut facts: IndexMap<Node, A::Domain> = IndexMap::default();
+    let mut iterations: IndexMap<Node, usize> = IndexMap::default();
     for &n in pg.nodes() {
         facts.insert(n, bot.clone());
+        iterations.insert(n, 0);
         worklist.insert(n);
     }
 
@@ -118,8 +219,11 @@ pub fn mono_analysis<A: MonotoneFramework, W: Worklist>(
     facts.insert(initial_node, initial);
 
     let mut calls = 0;
+    let mut updates: IndexMap<Node, usize> = IndexMap::default();
+    let loop_heads = pg.loop_heads();
 
     while let Some(n) = worklist.extract(pg) {
+        *iterations.entry(n).or_default() += 1;
         for e in pg.edges() {
             let (from, to) = match A::direction() {
                 Direction::Forward => (e.from(), e.to()),
@@ -135,7 +239,12 @@ pub fn mono_analysis<A: MonotoneFramework, W: Worklist>(
             let target = facts.get_mut(&to).unwrap();
 
             if !target.contains(&constraint) {
-                target.lub_extend(&constraint);
+                update(
+                    target,
+                    &constraint,
+                    updates.entry(to).or_default(),
+                    loop_heads.contains(&to),
+                );
                 worklist.insert(to);
             }
         }
@@ -144,6 +253,162 @@ pub fn mono_analysis<A: MonotoneFramework, W: Worklist>(
     AnalysisResults {
         facts,
         semantic_calls: calls,
+        iterations,
+    }
+}
+
+/// The most recent calls leading to a node, as the nodes they were made from.
+type CallString = Vec<Node>;
+
+/// An interprocedural analysis, which only follows paths where every return is
+/// to the most recent call, distinguishing facts by their last `k` calls.
+///
+/// Edges other than calls and returns are handled by
+/// [`MonotoneFramework::semantic`]. The facts of each node are the least upper
+/// bound of its facts in every call string. Only forward analyses are
+/// supported.
+pub fn call_string_analysis<A: MonotoneFramework>(
+    a: A,
+    pg: &ProgramGraph,
+    k: usize,
+) -> AnalysisResults<A> {
+    assert!(
+        matches!(A::direction(), Direction::Forward),
+        "call strings are only supported for forward analyses"
+    );
+
+    let mut worklist: VecDeque<(CallString, Node)> = VecDeque::new();
+    let mut facts: IndexMap<(CallString, Node), (A::Domain, usize)> = IndexMap::default();
+    let mut calls = 0;
+    let mut iterations: IndexMap<Node, usize> = pg.nodes().iter().map(|&n| (n, 0)).collect();
+
+    let loop_heads = pg.loop_heads();
+
+    fn propagate<D: Lattice>(
+        facts: &mut IndexMap<(CallString, Node), (D, usize)>,
+        worklist: &mut VecDeque<(CallString, Node)>,
+        loop_heads: &BTreeSet<Node>,
+        key: (CallString, Node),
+        constraint: D,
+    ) {
+        let loop_head = loop_heads.contains(&key.1);
+        let (target, updates) = facts.entry(key.clone()).or_insert_with(|| (D::bottom(), 0));
+        if !target.contains(&constraint) {
+            update(target, &constraint, updates, loop_head);
+            worklist.push_back(key);
+        }
+    }
+
+    facts.insert((vec![], Node::Start), (a.initial(pg), 0));
+    worklist.push_back((vec![], Node::Start));
+
+    while let Some((cs, n)) = worklist.pop_front() {
+        *iterations.entry(n).or_default() += 1;
+        let fact = facts[&(cs.clone(), n)].0.clone();
+        for e in pg.outgoing(n) {
+            match e.action() {
+                Action::Call(_) => {
+                    let mut callee_cs = cs.clone();
+                    callee_cs.push(n);
+                    if callee_cs.len() > k {
+                        callee_cs.remove(0);
+                    }
+                    let entry = a.call(pg, e, &fact);
+                    calls += 1;
+                    propagate(
+                        &mut facts,
+                        &mut worklist,
+                        &loop_heads,
+                        (callee_cs.clone(), e.to()),
+                        entry,
+                    );
+
+                    // NOTE: the procedure may already have been analysed in
+                    // this call string, in which case the new fact at the call
+                    // must be combined with the facts at its exits
+                    let Some(return_node) = pg.return_node(n) else {
+                        continue;
+                    };
+                    for ret in pg.edges() {
+                        if !matches!(ret.action(), Action::Return(_)) || ret.to() != return_node {
+                            continue;
+                        }
+                        let Some((exit, _)) = facts.get(&(callee_cs.clone(), ret.from())) else {
+                            continue;
+                        };
+                        let constraint = a.ret(pg, ret, &fact, exit);
+                        calls += 1;
+                        propagate(
+                            &mut facts,
+                            &mut worklist,
+                            &loop_heads,
+                            (cs.clone(), return_node),
+                            constraint,
+                        );
+                    }
+                }
+                Action::Return(_) => {
+                    for call_node in pg.call_nodes(e.to()) {
+                        if k > 0 && cs.last() != Some(&call_node) {
+                            continue;
+                        }
+                        // NOTE: every call string which leads to `cs` when
+                        // extended by the call is a possible caller
+                        let callers = facts
+                            .iter()
+                            .filter(|((caller_cs, node), _)| {
+                                *node == call_node && {
+                                    let mut extended = caller_cs.clone();
+                                    extended.push(call_node);
+                                    extended[extended.len().saturating_sub(k)..] == cs[..]
+                                }
+                            })
+                            .map(|((caller_cs, _), (fact, _))| (caller_cs.clone(), fact.clone()))
+                            .collect::<Vec<_>>();
+                        for (caller_cs, call_fact) in callers {
+                            let constraint = a.ret(pg, e, &call_fact, &fact);
+                            calls += 1;
+                            propagate(
+                                &mut facts,
+                                &mut worklist,
+                                &loop_heads,
+                                (caller_cs, e.to()),
+                                constraint,
+                            );
+                        }
+                    }
+                }
+                _ => {
+                    let constraint = a.semantic(pg, e, &fact);
+                    calls += 1;
+                    propagate(
+                        &mut facts,
+                        &mut worklist,
+                        &loop_heads,
+                        (cs.clone(), e.to()),
+                        constraint,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut joined: IndexMap<Node, A::Domain> = pg
+        .nodes()
+        .iter()
+        .map(|&n| (n, A::Domain::bottom()))
+        .collect();
+    for ((_, n), (fact, _)) in facts {
+        joined
+            .entry(n)
+            .or_insert_with(A::Domain::bottom)
+            .lub_extend(&fact);
+    }
+
+    AnalysisResults {
+        facts: joined,
+        semantic_calls: calls,
+        iterations,
     }
 }
 
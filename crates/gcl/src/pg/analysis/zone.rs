@@ -0,0 +1,628 @@
+//! Zones, the relational abstract domain of difference-bound matrices.
+//!
+//! A zone describes the memories satisfying a conjunction of constraints of
+//! the form `x - y ≤ c` and `±x ≤ c`, which is enough to relate loop counters
+//! to their bounds, such as `i < n`. The constraints are stored as a matrix
+//! with a row and a column for every variable and for the constant zero,
+//! where the entry at `(x, y)` is the least known bound of `x - y`.
+//!
+//! Only integer variables are tracked. Arrays and booleans can have any
+//! value, and arithmetic is on unbounded integers, which describes every
+//! execution which does not overflow.
+
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::{Direction, Lattice, MonotoneFramework};
+use crate::{
+    ast::{
+        AExpr, AExprKind, AOp, BExpr, BExprKind, Call, Commands, Int, LogicOp, RelOp, Target,
+        Variable,
+    },
+    pg::{Action, Edge, ProgramGraph},
+    typeck::Type,
+};
+
+/// An upper bound of a difference, which may be infinite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Bound {
+    Finite(i64),
+    Infinite,
+}
+
+impl std::ops::Add for Bound {
+    type Output = Bound;
+
+    // NOTE: a sum too large to represent is no bound at all, which is always
+    // sound for an upper bound
+    fn add(self, rhs: Bound) -> Bound {
+        match (self, rhs) {
+            (Bound::Finite(a), Bound::Finite(b)) => {
+                a.checked_add(b).map_or(Bound::Infinite, Bound::Finite)
+            }
+            _ => Bound::Infinite,
+        }
+    }
+}
+
+impl Bound {
+    fn finite(self) -> Option<i64> {
+        match self {
+            Bound::Finite(n) => Some(n),
+            Bound::Infinite => None,
+        }
+    }
+}
+
+/// The constraint `left - right ≤ bound`, where a missing variable is zero.
+#[derive(
+    tapi::Tapi, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[tapi(path = "GCL")]
+pub struct DifferenceConstraint {
+    pub left: Option<Variable>,
+    pub right: Option<Variable>,
+    pub bound: i64,
+}
+
+impl std::fmt::Display for DifferenceConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.left, &self.right) {
+            (Some(l), Some(r)) => write!(f, "{l} - {r} ≤ {}", self.bound),
+            (Some(l), None) => write!(f, "{l} ≤ {}", self.bound),
+            (None, Some(r)) => write!(f, "{r} ≥ {}", -(self.bound as i128)),
+            (None, None) => write!(f, "0 ≤ {}", self.bound),
+        }
+    }
+}
+
+/// A set of memories described by difference constraints.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Zone {
+    /// No memories, such as at unreachable nodes.
+    Empty,
+    Bounds(Dbm),
+}
+
+/// A difference-bound matrix over a fixed set of variables.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Dbm {
+    variables: Vec<Variable>,
+    /// The bounds of `x - y` for every `x` and `y`, row by row, where index
+    /// `0` is the constant zero and index `i + 1` is `variables[i]`.
+    bounds: Vec<Bound>,
+}
+
+impl Dbm {
+    /// The matrix without constraints.
+    pub fn top(variables: Vec<Variable>) -> Dbm {
+        let n = variables.len() + 1;
+        let mut bounds = vec![Bound::Infinite; n * n];
+        for i in 0..n {
+            bounds[i * n + i] = Bound::Finite(0);
+        }
+        Dbm { variables, bounds }
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    fn size(&self) -> usize {
+        self.variables.len() + 1
+    }
+    fn get(&self, i: usize, j: usize) -> Bound {
+        self.bounds[i * self.size() + j]
+    }
+    fn set(&mut self, i: usize, j: usize, b: Bound) {
+        let n = self.size();
+        self.bounds[i * n + j] = b;
+    }
+    /// Tightens the bound of `x_i - x_j` to at most `b`.
+    fn tighten(&mut self, i: usize, j: usize, b: Bound) {
+        if b < self.get(i, j) {
+            self.set(i, j, b);
+        }
+    }
+    fn index(&self, x: &Variable) -> Option<usize> {
+        self.variables.iter().position(|v| v == x).map(|i| i + 1)
+    }
+
+    /// The matrix where every bound is the tightest bound implied by the
+    /// others, or `None` if the constraints are unsatisfiable.
+    pub fn close(mut self) -> Option<Dbm> {
+        let n = self.size();
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    let through = self.get(i, k) + self.get(k, j);
+                    self.tighten(i, j, through);
+                }
+            }
+        }
+        if (0..n).any(|i| self.get(i, i) < Bound::Finite(0)) {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    /// Removes every constraint on `x_i`, keeping the matrix closed.
+    fn forget(&mut self, i: usize) {
+        for j in 0..self.size() {
+            if i != j {
+                self.set(i, j, Bound::Infinite);
+                self.set(j, i, Bound::Infinite);
+            }
+        }
+    }
+
+    /// The range of the value of `x_i`.
+    fn range(&self, i: usize) -> Interval {
+        Interval {
+            lo: self.get(0, i).finite().and_then(i64::checked_neg),
+            hi: self.get(i, 0).finite(),
+        }
+    }
+
+    /// The constraints of the matrix, excluding those without bounds.
+    pub fn constraints(&self) -> Vec<DifferenceConstraint> {
+        let var = |i: usize| i.checked_sub(1).map(|i| self.variables[i].clone());
+        (0..self.size())
+            .cartesian_product(0..self.size())
+            .filter(|(i, j)| i != j)
+            .filter_map(|(i, j)| {
+                Some(DifferenceConstraint {
+                    left: var(i),
+                    right: var(j),
+                    bound: self.get(i, j).finite()?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Zone {
+    /// The zone of every memory of `variables`.
+    pub fn top(variables: Vec<Variable>) -> Zone {
+        Zone::Bounds(Dbm::top(variables))
+    }
+
+    /// The zone satisfying `constraints`. Constraints on variables other than
+    /// `variables` are ignored.
+    pub fn from_constraints<'a>(
+        variables: Vec<Variable>,
+        constraints: impl IntoIterator<Item = &'a DifferenceConstraint>,
+    ) -> Zone {
+        let mut dbm = Dbm::top(variables);
+        for c in constraints {
+            let index = |x: &Option<Variable>| match x {
+                Some(x) => dbm.index(x),
+                None => Some(0),
+            };
+            if let (Some(i), Some(j)) = (index(&c.left), index(&c.right)) {
+                dbm.tighten(i, j, Bound::Finite(c.bound));
+            }
+        }
+        Zone::closed(dbm)
+    }
+
+    fn closed(dbm: Dbm) -> Zone {
+        dbm.close().map_or(Zone::Empty, Zone::Bounds)
+    }
+
+    /// The closed matrix of the zone, or `None` if the zone is empty.
+    pub fn dbm(&self) -> Option<Dbm> {
+        match self {
+            Zone::Empty => None,
+            Zone::Bounds(dbm) => dbm.clone().close(),
+        }
+    }
+
+    /// The tightest constraints of the zone, or `None` if the zone is empty.
+    pub fn constraints(&self) -> Option<Vec<DifferenceConstraint>> {
+        self.dbm().map(|dbm| dbm.constraints())
+    }
+
+    /// Whether the zones describe the same memories.
+    pub fn equivalent(&self, other: &Zone) -> bool {
+        self.dbm() == other.dbm()
+    }
+
+    /// The zone after assigning the value of `a` to `x`.
+    pub fn assign(&self, x: &Variable, a: &AExpr) -> Zone {
+        let Some(mut dbm) = self.dbm() else {
+            return Zone::Empty;
+        };
+        let Some(i) = dbm.index(x) else {
+            return Zone::Bounds(dbm);
+        };
+        match linear(&dbm, a) {
+            // NOTE: incrementing a variable shifts its constraints, which
+            // keeps the relations to the other variables
+            Some((Some(j), c)) if i == j => {
+                for k in 0..dbm.size() {
+                    if k != i {
+                        dbm.set(i, k, dbm.get(i, k) + Bound::Finite(c));
+                        dbm.set(k, i, dbm.get(k, i) + Bound::Finite(-c));
+                    }
+                }
+                Zone::Bounds(dbm)
+            }
+            Some((j, c)) => {
+                let j = j.unwrap_or(0);
+                dbm.forget(i);
+                dbm.set(i, j, Bound::Finite(c));
+                dbm.set(j, i, Bound::Finite(-c));
+                Zone::closed(dbm)
+            }
+            None => {
+                let range = interval(&dbm, a);
+                dbm.forget(i);
+                dbm.bound_range(i, range);
+                Zone::closed(dbm)
+            }
+        }
+    }
+
+    /// The zone of the memories where `b` may evaluate to `value`.
+    pub fn constrain(&self, b: &BExpr, value: bool) -> Zone {
+        let Some(mut dbm) = self.dbm() else {
+            return Zone::Empty;
+        };
+        match &b.kind {
+            BExprKind::Bool(b) if *b == value => Zone::Bounds(dbm),
+            BExprKind::Bool(_) => Zone::Empty,
+            BExprKind::Reference(_) => Zone::Bounds(dbm),
+            BExprKind::Rel(l, op, r) => {
+                let op = if value { *op } else { negate(*op) };
+                let (l, r) = (side(&dbm, l), side(&dbm, r));
+                // NOTE: `x + l ≤ y + r` holds for some offsets if
+                // `x - y ≤ max(r - l)`
+                let le = |(i, l): (usize, Interval), (j, r): (usize, Interval), strict: bool| {
+                    let bound = r.hi.zip(l.lo).and_then(|(r, l)| r.checked_sub(l));
+                    let bound = bound.and_then(|b| if strict { b.checked_sub(1) } else { Some(b) });
+                    (i, j, bound.map_or(Bound::Infinite, Bound::Finite))
+                };
+                let constraints = match op {
+                    RelOp::Le => vec![le(l, r, false)],
+                    RelOp::Lt => vec![le(l, r, true)],
+                    RelOp::Ge => vec![le(r, l, false)],
+                    RelOp::Gt => vec![le(r, l, true)],
+                    RelOp::Eq => vec![le(l, r, false), le(r, l, false)],
+                    RelOp::Ne => match (l, r) {
+                        ((i, l), (j, r)) if i == j && l.point().is_some() && l == r => {
+                            return Zone::Empty;
+                        }
+                        _ => vec![],
+                    },
+                };
+                for (i, j, b) in constraints {
+                    dbm.tighten(i, j, b);
+                }
+                Zone::closed(dbm)
+            }
+            BExprKind::Logic(l, op, r) => {
+                let conjunction = matches!(op, LogicOp::And | LogicOp::Land) == value;
+                if conjunction {
+                    self.constrain(l, value).constrain(r, value)
+                } else {
+                    // NOTE: short-circuiting operators only evaluate the right
+                    // operand if the left does not decide the result
+                    let right = match op {
+                        LogicOp::Lor | LogicOp::Land => self.constrain(l, !value),
+                        LogicOp::Or | LogicOp::And => self.clone(),
+                    };
+                    self.constrain(l, value).lub(&right.constrain(r, value))
+                }
+            }
+            BExprKind::Not(b) => self.constrain(b, !value),
+        }
+    }
+}
+
+impl Dbm {
+    /// Bounds `x_i` to `range`.
+    fn bound_range(&mut self, i: usize, range: Interval) {
+        if let Some(hi) = range.hi {
+            self.tighten(i, 0, Bound::Finite(hi));
+        }
+        if let Some(lo) = range.lo.and_then(i64::checked_neg) {
+            self.tighten(0, i, Bound::Finite(lo));
+        }
+    }
+}
+
+fn negate(op: RelOp) -> RelOp {
+    match op {
+        RelOp::Eq => RelOp::Ne,
+        RelOp::Ne => RelOp::Eq,
+        RelOp::Gt => RelOp::Le,
+        RelOp::Ge => RelOp::Lt,
+        RelOp::Lt => RelOp::Ge,
+        RelOp::Le => RelOp::Gt,
+    }
+}
+
+/// The range of an integer expression, where a missing end is unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval {
+    lo: Option<i64>,
+    hi: Option<i64>,
+}
+
+impl Interval {
+    const TOP: Interval = Interval { lo: None, hi: None };
+
+    fn point(self) -> Option<i64> {
+        match (self.lo, self.hi) {
+            (Some(lo), Some(hi)) if lo == hi => Some(lo),
+            _ => None,
+        }
+    }
+    fn neg(self) -> Interval {
+        Interval {
+            lo: self.hi.and_then(i64::checked_neg),
+            hi: self.lo.and_then(i64::checked_neg),
+        }
+    }
+    fn add(self, other: Interval) -> Interval {
+        Interval {
+            lo: self.lo.zip(other.lo).and_then(|(a, b)| a.checked_add(b)),
+            hi: self.hi.zip(other.hi).and_then(|(a, b)| a.checked_add(b)),
+        }
+    }
+    fn mul(self, other: Interval) -> Interval {
+        let products = [
+            (self.lo, other.lo),
+            (self.lo, other.hi),
+            (self.hi, other.lo),
+            (self.hi, other.hi),
+        ]
+        .map(|(a, b)| a.zip(b).and_then(|(a, b)| a.checked_mul(b)));
+        if products.iter().any(|p| p.is_none()) {
+            return Interval::TOP;
+        }
+        let products = products.map(Option::unwrap);
+        Interval {
+            lo: products.iter().min().copied(),
+            hi: products.iter().max().copied(),
+        }
+    }
+}
+
+/// The expression as `x + c`, where a missing `x` is zero, if it is of that
+/// form.
+fn linear(dbm: &Dbm, a: &AExpr) -> Option<(Option<usize>, i64)> {
+    Some(match &a.kind {
+        AExprKind::Number(n) => (None, *n as i64),
+        AExprKind::Reference(Target::Variable(x)) => (Some(dbm.index(x)?), 0),
+        AExprKind::Reference(Target::Array(_, _)) => return None,
+        AExprKind::Binary(l, AOp::Plus, r) => match (linear(dbm, l)?, linear(dbm, r)?) {
+            ((Some(_), _), (Some(_), _)) => return None,
+            ((x, a), (y, b)) => (x.or(y), a.checked_add(b)?),
+        },
+        AExprKind::Binary(l, AOp::Minus, r) => match (linear(dbm, l)?, linear(dbm, r)?) {
+            ((x, a), (None, b)) => (x, a.checked_sub(b)?),
+            _ => return None,
+        },
+        AExprKind::Binary(_, _, _) => return None,
+        AExprKind::Minus(a) => match linear(dbm, a)? {
+            (None, a) => (None, a.checked_neg()?),
+            _ => return None,
+        },
+        AExprKind::Declassify(a, _) => linear(dbm, a)?,
+    })
+}
+
+/// The range of the values of the expression in the memories of `dbm`.
+fn interval(dbm: &Dbm, a: &AExpr) -> Interval {
+    match &a.kind {
+        AExprKind::Number(n) => Interval {
+            lo: Some(*n as i64),
+            hi: Some(*n as i64),
+        },
+        AExprKind::Reference(Target::Variable(x)) => match dbm.index(x) {
+            Some(i) => dbm.range(i),
+            None => Interval::TOP,
+        },
+        AExprKind::Reference(Target::Array(_, _)) => Interval::TOP,
+        AExprKind::Binary(l, op, r) => {
+            let (l, r) = (interval(dbm, l), interval(dbm, r));
+            match op {
+                AOp::Plus => l.add(r),
+                AOp::Minus => l.add(r.neg()),
+                AOp::Times => l.mul(r),
+                AOp::Divide | AOp::Pow => {
+                    let value = l.point().zip(r.point()).and_then(|(l, r)| {
+                        op.semantic(Int::try_from(l).ok()?, Int::try_from(r).ok()?)
+                            .ok()
+                    });
+                    match value {
+                        Some(n) => Interval {
+                            lo: Some(n as i64),
+                            hi: Some(n as i64),
+                        },
+                        None => Interval::TOP,
+                    }
+                }
+            }
+        }
+        AExprKind::Minus(a) => interval(dbm, a).neg(),
+        AExprKind::Declassify(a, _) => interval(dbm, a),
+    }
+}
+
+/// The expression as a variable (or zero) plus an offset within a range.
+fn side(dbm: &Dbm, a: &AExpr) -> (usize, Interval) {
+    match linear(dbm, a) {
+        Some((x, c)) => (
+            x.unwrap_or(0),
+            Interval {
+                lo: Some(c),
+                hi: Some(c),
+            },
+        ),
+        None => (0, interval(dbm, a)),
+    }
+}
+
+impl Lattice for Zone {
+    fn bottom() -> Self {
+        Zone::Empty
+    }
+
+    fn lub(&self, other: &Self) -> Self {
+        match (self.dbm(), other.dbm()) {
+            (None, _) => other.clone(),
+            (_, None) => self.clone(),
+            (Some(mut a), Some(b)) => {
+                for (x, y) in a.bounds.iter_mut().zip(b.bounds) {
+                    *x = (*x).max(y);
+                }
+                Zone::Bounds(a)
+            }
+        }
+    }
+
+    fn contains(&self, other: &Self) -> bool {
+        match (self, other.dbm()) {
+            (_, None) => true,
+            (Zone::Empty, Some(_)) => false,
+            (Zone::Bounds(a), Some(b)) => b.bounds.iter().zip(&a.bounds).all(|(b, a)| b <= a),
+        }
+    }
+
+    // NOTE: the widened matrix is not closed, since closing it could tighten
+    // the bounds dropped by the widening again
+    fn widen(&self, other: &Self) -> Self {
+        match (self, other.dbm()) {
+            (Zone::Empty, _) => other.clone(),
+            (_, None) => self.clone(),
+            (Zone::Bounds(a), Some(b)) => {
+                let mut a = a.clone();
+                for (x, y) in a.bounds.iter_mut().zip(b.bounds) {
+                    if y > *x {
+                        *x = Bound::Infinite;
+                    }
+                }
+                Zone::Bounds(a)
+            }
+        }
+    }
+}
+
+/// The zone analysis of the integer variables of a program, starting from no
+/// constraints.
+#[derive(Debug, Clone)]
+pub struct ZoneAnalysis {
+    variables: Vec<Variable>,
+}
+
+impl ZoneAnalysis {
+    pub fn new(commands: &Commands) -> ZoneAnalysis {
+        let globals = commands
+            .types()
+            .into_iter()
+            .filter_map(|(t, ty)| match (t, ty) {
+                (Target::Variable(x), Type::Int) => Some(x),
+                _ => None,
+            });
+        let locals = commands
+            .procedures()
+            .flat_map(|p| p.params.iter().chain(&p.results).cloned());
+        let variables: BTreeSet<Variable> = globals.chain(locals).collect();
+        ZoneAnalysis {
+            variables: variables.into_iter().collect(),
+        }
+    }
+
+    /// The integer variables tracked by the analysis.
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+}
+
+impl MonotoneFramework for ZoneAnalysis {
+    type Domain = Zone;
+
+    fn semantic(&self, pg: &ProgramGraph, e: &Edge, prev: &Self::Domain) -> Self::Domain {
+        match e.action() {
+            Action::Assignment(Target::Variable(x), a) => prev.assign(x, a),
+            Action::Assignment(Target::Array(_, _), _)
+            | Action::BoolAssignment(_, _)
+            | Action::Skip => prev.clone(),
+            Action::Condition(b) => prev.constrain(b, true),
+            Action::Call(call) => enter(pg, call, prev),
+            Action::Return(call) => leave(pg, call, prev),
+        }
+    }
+
+    fn direction() -> Direction {
+        Direction::Forward
+    }
+
+    fn initial(&self, _pg: &ProgramGraph) -> Self::Domain {
+        Zone::top(self.variables.clone())
+    }
+}
+
+/// Binds the parameters of the procedure to the ranges of the arguments, and
+/// the results to zero.
+fn enter(pg: &ProgramGraph, call: &Call, prev: &Zone) -> Zone {
+    let (Some(signature), Some(mut dbm)) = (pg.procedure(&call.name), prev.dbm()) else {
+        return Zone::Empty;
+    };
+    // NOTE: the arguments are evaluated before any parameter is bound
+    let ranges = call.args.iter().map(|a| interval(&dbm, a)).collect_vec();
+    for (param, range) in signature.params.iter().zip(ranges) {
+        if let Some(i) = dbm.index(param) {
+            dbm.forget(i);
+            dbm.bound_range(i, range);
+        }
+    }
+    for r in &signature.results {
+        if let Some(i) = dbm.index(r) {
+            dbm.forget(i);
+            dbm.bound_range(
+                i,
+                Interval {
+                    lo: Some(0),
+                    hi: Some(0),
+                },
+            );
+        }
+    }
+    Zone::closed(dbm)
+}
+
+/// Assigns the ranges of the results of the procedure to the targets of the
+/// call, where the variables shadowed by the call can have any value.
+fn leave(pg: &ProgramGraph, call: &Call, prev: &Zone) -> Zone {
+    let (Some(signature), Some(mut dbm)) = (pg.procedure(&call.name), prev.dbm()) else {
+        return Zone::Empty;
+    };
+    let ranges = signature
+        .results
+        .iter()
+        .map(|r| dbm.index(r).map_or(Interval::TOP, |i| dbm.range(i)))
+        .collect_vec();
+    for local in signature.locals() {
+        if let Some(i) = dbm.index(local) {
+            dbm.forget(i);
+        }
+    }
+    for (target, range) in call.results.iter().zip(ranges) {
+        let index = match target {
+            Target::Variable(x) => dbm.index(x),
+            Target::Array(_, _) => None,
+        };
+        if let Some(i) = index {
+            dbm.forget(i);
+            dbm.bound_range(i, range);
+        }
+    }
+    Zone::closed(dbm)
+}
@@ -0,0 +1,227 @@
+//! Structural equivalence of [`ProgramGraph`]s.
+//!
+//! Two graphs are equivalent if there is a bijection between their nodes,
+//! reachable from the initial node, which maps every edge to an edge with a
+//! syntactically equal action. Actions are compared as printed, such that
+//! different parses of the same text, like `-8` as a literal or as a negation,
+//! are considered equal.
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use super::{Action, Edge, Node, ProgramGraph};
+
+/// The reason two graphs are not equivalent, as found by the matching which
+/// got the furthest.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Inequivalence {
+    /// The edge of the left graph has no counterpart in the right graph.
+    /// `counterpart` is the node of the right graph matched with the source of
+    /// the edge, if any.
+    #[error("no edge corresponds to {edge}")]
+    Missing {
+        edge: Box<Edge>,
+        counterpart: Option<Node>,
+    },
+    /// The edge of the right graph has no counterpart in the left graph.
+    #[error("the edge {edge} has no counterpart")]
+    Extra { edge: Box<Edge> },
+}
+
+#[derive(Default)]
+struct Mapping {
+    forward: BTreeMap<Node, Node>,
+    backward: BTreeMap<Node, Node>,
+}
+
+impl Mapping {
+    fn insert(&mut self, l: Node, r: Node) {
+        self.forward.insert(l, r);
+        self.backward.insert(r, l);
+    }
+    fn remove(&mut self, l: Node, r: Node) {
+        self.forward.remove(&l);
+        self.backward.remove(&r);
+    }
+}
+
+struct Search<'a> {
+    left: &'a ProgramGraph,
+    right: &'a ProgramGraph,
+    /// The right graph only constrains the final node if it has one.
+    right_has_end: bool,
+    failure: Option<(usize, Inequivalence)>,
+}
+
+impl Search<'_> {
+    fn fail(&mut self, map: &Mapping, reason: Inequivalence) {
+        let depth = map.forward.len();
+        if self.failure.as_ref().is_none_or(|(d, _)| *d < depth) {
+            self.failure = Some((depth, reason));
+        }
+    }
+
+    /// Matches the edges of all pending pairs of nodes. Restores `map` and
+    /// `pending` if no matching is found.
+    fn solve(&mut self, map: &mut Mapping, pending: &mut Vec<(Node, Node)>) -> bool {
+        let Some((l, r)) = pending.pop() else {
+            return true;
+        };
+        let mut used = vec![false; self.right.outgoing(r).len()];
+        if self.match_edges(map, pending, (l, r), 0, &mut used) {
+            true
+        } else {
+            pending.push((l, r));
+            false
+        }
+    }
+
+    /// Matches the `idx`th and later outgoing edges of `l` with the unused
+    /// outgoing edges of `r`, and then the remaining pending pairs.
+    fn match_edges(
+        &mut self,
+        map: &mut Mapping,
+        pending: &mut Vec<(Node, Node)>,
+        (l, r): (Node, Node),
+        idx: usize,
+        used: &mut [bool],
+    ) -> bool {
+        let (left, right) = (self.left, self.right);
+        let (ls, rs) = (left.outgoing(l), right.outgoing(r));
+
+        let Some(Edge(_, action, lt, _)) = ls.get(idx) else {
+            if let Some(j) = used.iter().position(|u| !u) {
+                self.fail(
+                    map,
+                    Inequivalence::Extra {
+                        edge: Box::new(rs[j].clone()),
+                    },
+                );
+                return false;
+            }
+            return self.solve(map, pending);
+        };
+
+        for (j, Edge(_, r_action, rt, _)) in rs.iter().enumerate() {
+            if used[j] || !same_action(action, r_action) {
+                continue;
+            }
+            if self.right_has_end && (*lt == Node::End) != (*rt == Node::End) {
+                continue;
+            }
+            let new = match (map.forward.get(lt), map.backward.get(rt)) {
+                (Some(m), _) if m == rt => false,
+                (None, None) => true,
+                _ => continue,
+            };
+
+            used[j] = true;
+            if new {
+                map.insert(*lt, *rt);
+                pending.push((*lt, *rt));
+            }
+            if self.match_edges(map, pending, (l, r), idx + 1, used) {
+                return true;
+            }
+            if new {
+                pending.pop();
+                map.remove(*lt, *rt);
+            }
+            used[j] = false;
+        }
+
+        self.fail(
+            map,
+            Inequivalence::Missing {
+                edge: Box::new(ls[idx].clone()),
+                counterpart: Some(r),
+            },
+        );
+        false
+    }
+}
+
+fn same_action(a: &Action, b: &Action) -> bool {
+    a == b || a.to_string() == b.to_string()
+}
+
+impl ProgramGraph {
+    /// Finds a mapping from the nodes of `self` to the nodes of `other` under
+    /// which the graphs have the same edges.
+    ///
+    /// If `other` has a [`Node::Start`] or [`Node::End`], these are mapped to
+    /// the corresponding nodes of `self`. Otherwise every node of `other` is
+    /// tried as the initial node.
+    pub fn find_isomorphism(
+        &self,
+        other: &ProgramGraph,
+    ) -> Result<BTreeMap<Node, Node>, Inequivalence> {
+        let mut search = Search {
+            left: self,
+            right: other,
+            right_has_end: other.nodes.contains(&Node::End),
+            failure: None,
+        };
+
+        let candidates: Vec<Node> = if other.nodes.contains(&Node::Start) {
+            vec![Node::Start]
+        } else {
+            other.nodes.iter().copied().collect()
+        };
+
+        for start in candidates {
+            let mut map = Mapping::default();
+            map.insert(Node::Start, start);
+            if !search.solve(&mut map, &mut vec![(Node::Start, start)]) {
+                continue;
+            }
+
+            // NOTE: the search only visits nodes reachable from the initial
+            // node, so any remaining edges are unmatched
+            if let Some(edge) = other
+                .edges
+                .iter()
+                .find(|e| !map.backward.contains_key(&e.from()))
+            {
+                search.fail(
+                    &map,
+                    Inequivalence::Extra {
+                        edge: Box::new(edge.clone()),
+                    },
+                );
+                continue;
+            }
+            if let Some(edge) = self
+                .edges
+                .iter()
+                .find(|e| !map.forward.contains_key(&e.from()))
+            {
+                search.fail(
+                    &map,
+                    Inequivalence::Missing {
+                        edge: Box::new(edge.clone()),
+                        counterpart: None,
+                    },
+                );
+                continue;
+            }
+
+            return Ok(map.forward);
+        }
+
+        Err(match search.failure {
+            Some((_, reason)) => reason,
+            None => match (self.edges.first(), other.edges.first()) {
+                (Some(edge), _) => Inequivalence::Missing {
+                    edge: Box::new(edge.clone()),
+                    counterpart: None,
+                },
+                (None, Some(edge)) => Inequivalence::Extra {
+                    edge: Box::new(edge.clone()),
+                },
+                (None, None) => return Ok(BTreeMap::new()),
+            },
+        })
+    }
+}
@@ -0,0 +1,327 @@
+//! Structured serialisations of [`ProgramGraph`]s, as alternatives to
+//! [`ProgramGraph::dot`].
+//!
+//! Nodes are named as in their [`Debug`] representation, that is `qStart`,
+//! `q1`, `q2`, ..., `qFinal`.
+
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Action, Edge, Node, NodeId, ProgramGraph};
+use crate::{
+    ast::{Array, Target, Variable},
+    parse::{ParseError, parse_action, parse_aexpr, parse_bexpr},
+};
+
+/// A program graph as a JSON edge list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonProgramGraph {
+    #[serde(default)]
+    pub nodes: Vec<String>,
+    pub edges: Vec<JsonEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonEdge {
+    pub from: String,
+    pub action: JsonAction,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum JsonAction {
+    Assignment {
+        target: String,
+        /// The index expression if `target` is an array.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<String>,
+        expr: String,
+    },
+    Skip,
+    Condition {
+        condition: String,
+    },
+    /// A call, as `p(args; results)`.
+    Call {
+        call: String,
+    },
+    /// A return from a call, as `p(args; results)`.
+    Return {
+        call: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to parse `{src}`: {error}")]
+    Parse { src: String, error: Box<ParseError> },
+    #[error("the graph does not have a `qStart` node")]
+    MissingStart,
+}
+
+impl From<&Action> for JsonAction {
+    fn from(action: &Action) -> Self {
+        match action {
+            Action::Assignment(Target::Variable(x), a) => JsonAction::Assignment {
+                target: x.to_string(),
+                index: None,
+                expr: a.to_string(),
+            },
+            Action::Assignment(Target::Array(arr, idx), a) => JsonAction::Assignment {
+                target: arr.to_string(),
+                index: Some(idx.to_string()),
+                expr: a.to_string(),
+            },
+            Action::BoolAssignment(Target::Variable(x), b) => JsonAction::Assignment {
+                target: x.to_string(),
+                index: None,
+                expr: b.to_string(),
+            },
+            Action::BoolAssignment(Target::Array(arr, idx), b) => JsonAction::Assignment {
+                target: arr.to_string(),
+                index: Some(idx.to_string()),
+                expr: b.to_string(),
+            },
+            Action::Skip => JsonAction::Skip,
+            Action::Condition(b) => JsonAction::Condition {
+                condition: b.to_string(),
+            },
+            Action::Call(call) => JsonAction::Call {
+                call: call.to_string(),
+            },
+            Action::Return(call) => JsonAction::Return {
+                call: call.to_string(),
+            },
+        }
+    }
+}
+
+impl TryFrom<&JsonAction> for Action {
+    type Error = FormatError;
+
+    fn try_from(action: &JsonAction) -> Result<Self, Self::Error> {
+        let parse_error = |src: &str| {
+            let src = src.to_string();
+            move |error| FormatError::Parse {
+                src,
+                error: Box::new(error),
+            }
+        };
+        Ok(match action {
+            JsonAction::Assignment {
+                target,
+                index,
+                expr,
+            } => {
+                let target = match index {
+                    None => Target::Variable(Variable(target.trim().to_string())),
+                    Some(index) => Target::Array(
+                        Array(target.trim().to_string()),
+                        Box::new(parse_aexpr(index).map_err(parse_error(index))?),
+                    ),
+                };
+                // NOTE: a bare reference is taken to be an integer, as there
+                // is no program to infer its type from
+                match (parse_aexpr(expr), parse_bexpr(expr)) {
+                    (Ok(a), _) => Action::Assignment(target, a),
+                    (Err(_), Ok(b)) => Action::BoolAssignment(target, b),
+                    (Err(error), Err(_)) => return Err(parse_error(expr)(error)),
+                }
+            }
+            JsonAction::Skip => Action::Skip,
+            JsonAction::Condition { condition } => {
+                Action::Condition(parse_bexpr(condition).map_err(parse_error(condition))?)
+            }
+            JsonAction::Call { call } => {
+                parse_action(&format!("call {call}")).map_err(parse_error(call))?
+            }
+            JsonAction::Return { call } => {
+                parse_action(&format!("return {call}")).map_err(parse_error(call))?
+            }
+        })
+    }
+}
+
+impl Node {
+    /// Parses node names as produced by the [`Debug`] and [`Display`]
+    /// implementations.
+    ///
+    /// [`Display`]: std::fmt::Display
+    pub fn from_name(name: &str) -> Option<Node> {
+        match name {
+            "qStart" | "q▷" => Some(Node::Start),
+            "qFinal" | "q◀" => Some(Node::End),
+            _ => name
+                .strip_prefix('q')
+                .and_then(|n| n.parse().ok())
+                .map(|n| Node::Node(NodeId(n))),
+        }
+    }
+}
+
+impl ProgramGraph {
+    pub fn to_json_graph(&self) -> JsonProgramGraph {
+        JsonProgramGraph {
+            nodes: self.nodes.iter().map(|n| format!("{n:?}")).collect(),
+            edges: self
+                .edges
+                .iter()
+                .map(|Edge(a, action, b, _)| JsonEdge {
+                    from: format!("{a:?}"),
+                    action: action.into(),
+                    to: format!("{b:?}"),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json_graph()).expect("graphs serialize to JSON")
+    }
+
+    /// Constructs a program graph from edges between named nodes.
+    ///
+    /// Nodes named `qStart` and `qFinal` are the initial and final nodes, and
+    /// nodes named `q<n>` keep their number. All other names are assigned
+    /// fresh numbers in order of appearance. Returns the graph along with the
+    /// node of each name.
+    pub fn from_named_edges<'a>(
+        names: impl IntoIterator<Item = &'a str>,
+        edges: impl IntoIterator<Item = (&'a str, Action, &'a str)>,
+    ) -> (Self, IndexMap<&'a str, Node>) {
+        let edges = edges.into_iter().collect_vec();
+        let names: IndexSet<&str> = names
+            .into_iter()
+            .chain(edges.iter().flat_map(|(a, _, b)| [*a, *b]))
+            .collect();
+
+        let mut next_id = names
+            .iter()
+            .filter_map(|n| match Node::from_name(n) {
+                Some(Node::Node(NodeId(id))) => Some(id + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(1);
+        let nodes: IndexMap<&str, Node> = names
+            .iter()
+            .map(|&name| {
+                let node = Node::from_name(name).unwrap_or_else(|| {
+                    next_id += 1;
+                    Node::Node(NodeId(next_id - 1))
+                });
+                (name, node)
+            })
+            .collect();
+
+        let mut pg = ProgramGraph::from_edges(
+            edges
+                .into_iter()
+                .map(|(a, action, b)| Edge(nodes[a], action, nodes[b], Default::default()))
+                .collect(),
+        );
+        pg.nodes = nodes.values().copied().collect();
+        (pg, nodes)
+    }
+
+    /// Constructs the program graph described by `graph`, naming nodes as in
+    /// [`ProgramGraph::from_named_edges`].
+    pub fn from_json_graph(graph: &JsonProgramGraph) -> Result<Self, FormatError> {
+        let edges = graph
+            .edges
+            .iter()
+            .map(|e| Ok((e.from.as_str(), (&e.action).try_into()?, e.to.as_str())))
+            .collect::<Result<Vec<_>, FormatError>>()?;
+        let (pg, _) = Self::from_named_edges(graph.nodes.iter().map(|n| n.as_str()), edges);
+
+        if !pg.nodes.contains(&Node::Start) {
+            return Err(FormatError::MissingStart);
+        }
+
+        Ok(pg)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, FormatError> {
+        Self::from_json_graph(&serde_json::from_str(json)?)
+    }
+
+    pub fn graphml(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        }
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|n| {
+                format!(
+                    "    <node id=\"{n:?}\"><data key=\"label\">{}</data></node>",
+                    escape(&n.to_string())
+                )
+            })
+            .format("\n");
+        let edges = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(idx, Edge(a, action, b, _))| {
+                let kind = match action {
+                    Action::Assignment(_, _) | Action::BoolAssignment(_, _) => "Assignment",
+                    Action::Skip => "Skip",
+                    Action::Condition(_) => "Condition",
+                    Action::Call(_) => "Call",
+                    Action::Return(_) => "Return",
+                };
+                format!(
+                    "    <edge id=\"e{idx}\" source=\"{a:?}\" target=\"{b:?}\"><data key=\"kind\">{kind}</data><data key=\"action\">{}</data></edge>",
+                    escape(&action.to_string())
+                )
+            })
+            .format("\n");
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="kind" for="edge" attr.name="kind" attr.type="string"/>
+  <key id="action" for="edge" attr.name="action" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+{nodes}
+{edges}
+  </graph>
+</graphml>
+"#
+        )
+    }
+
+    pub fn mermaid(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        }
+
+        format!(
+            "flowchart TD\n{}\n{}\n",
+            self.nodes
+                .iter()
+                .map(|n| format!("  {n:?}[\"{}\"]", escape(&n.to_string())))
+                .format("\n"),
+            self.edges
+                .iter()
+                .map(|Edge(a, action, b, _)| format!(
+                    "  {a:?} -->|\"{}\"| {b:?}",
+                    escape(&action.to_string())
+                ))
+                .format("\n"),
+        )
+    }
+}
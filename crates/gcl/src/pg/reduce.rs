@@ -0,0 +1,242 @@
+//! Reduced and canonical forms of [`ProgramGraph`]s.
+//!
+//! The graph of a program has a node between any two commands, such that a
+//! sequence of commands is a chain of edges, and `skip` is an edge which does
+//! nothing. [`ProgramGraph::reduce`] merges such chains into single edges
+//! labelled with the actions along them.
+//!
+//! [`ProgramGraph::canonical`] numbers the nodes by the structure of the graph
+//! alone, such that graphs which only differ in the names of their nodes, or
+//! in the order of their edges, are numbered alike.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use indexmap::IndexSet;
+use itertools::Itertools;
+
+use super::{Action, Edge, Node, NodeId, ProgramGraph};
+use crate::parse::SourceSpan;
+
+/// An edge labelled with the actions of a path in a [`ProgramGraph`], which
+/// are executed in order. An edge without actions does nothing, like `skip`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReducedEdge {
+    pub from: Node,
+    pub actions: Vec<Action>,
+    pub to: Node,
+    /// The span of the commands and guards the actions originate from.
+    pub span: SourceSpan,
+}
+
+impl ReducedEdge {
+    /// The actions separated by `;`, or `skip` if there are none.
+    pub fn label(&self) -> String {
+        if self.actions.is_empty() {
+            Action::Skip.to_string()
+        } else {
+            self.actions.iter().format("; ").to_string()
+        }
+    }
+
+    fn is_call_or_return(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|a| matches!(a, Action::Call(_) | Action::Return(_)))
+    }
+}
+
+impl From<&Edge> for ReducedEdge {
+    fn from(Edge(from, action, to, span): &Edge) -> Self {
+        ReducedEdge {
+            from: *from,
+            actions: match action {
+                Action::Skip => vec![],
+                action => vec![action.clone()],
+            },
+            to: *to,
+            span: *span,
+        }
+    }
+}
+
+/// A [`ProgramGraph`] without chains of edges, as computed by
+/// [`ProgramGraph::reduce`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReducedProgramGraph {
+    edges: Vec<ReducedEdge>,
+}
+
+impl ReducedProgramGraph {
+    pub fn edges(&self) -> &[ReducedEdge] {
+        &self.edges
+    }
+    pub fn nodes(&self) -> IndexSet<Node> {
+        self.edges.iter().flat_map(|e| [e.from, e.to]).collect()
+    }
+    pub fn outgoing(&self, node: Node) -> impl Iterator<Item = &ReducedEdge> {
+        self.edges.iter().filter(move |e| e.from == node)
+    }
+
+    pub fn dot(&self) -> String {
+        format!(
+            "digraph G {{\n{}\n}}",
+            self.edges
+                .iter()
+                .map(|e| {
+                    let (a, b) = (e.from, e.to);
+                    format!(
+                        "  {a:?}[label=\"{a}\"]; {a:?} -> {b:?}[label={:?}]; {b:?}[label=\"{b}\"];",
+                        e.label(),
+                    )
+                })
+                .format("  \n")
+        )
+    }
+}
+
+impl ProgramGraph {
+    /// The graph with its nodes numbered in breadth-first order from the
+    /// initial node, and its edges sorted by their nodes.
+    ///
+    /// The successors of a node are visited in the order of the printed
+    /// actions of the edges leading to them, and only edges with the same
+    /// action are visited in the order of [`ProgramGraph::edges`]. Nodes which
+    /// are not reachable from the initial node, such as those of procedures
+    /// which are never called, are numbered last.
+    pub fn canonical(&self) -> ProgramGraph {
+        let names = canonical_names(
+            self.nodes.iter().copied(),
+            self.edges
+                .iter()
+                .map(|e| (e.from(), e.action().to_string(), e.to())),
+        );
+        let mut pg = self.rename(&names);
+        pg.edges
+            .sort_by_cached_key(|e| (e.from(), e.to(), e.action().to_string()));
+        pg.outgoing = Self::from_edges(pg.edges.clone()).outgoing;
+        pg.nodes.sort();
+        pg
+    }
+
+    /// Merges every node with a single incoming and a single outgoing edge
+    /// into one edge, and removes nodes whose only outgoing edge is `skip`.
+    /// The nodes of the reduced graph are numbered as by
+    /// [`ProgramGraph::canonical`].
+    ///
+    /// Calls and returns are never merged, and the nodes they connect are
+    /// kept. An execution of the reduced graph only gets stuck on a condition
+    /// in the middle of an edge where the original graph would get stuck
+    /// after the preceding actions.
+    pub fn reduce(&self) -> ReducedProgramGraph {
+        let kept: BTreeSet<Node> = [Node::Start, Node::End]
+            .into_iter()
+            .chain(self.returns.iter().flat_map(|(c, r)| [*c, *r]))
+            .collect();
+
+        let mut edges = self.edges.iter().map(ReducedEdge::from).collect_vec();
+        while remove_skip_node(&mut edges, &kept) || merge_chain(&mut edges, &kept) {}
+
+        let names = canonical_names(
+            edges.iter().flat_map(|e| [e.from, e.to]).unique(),
+            edges.iter().map(|e| (e.from, e.label(), e.to)),
+        );
+        for e in &mut edges {
+            e.from = names[&e.from];
+            e.to = names[&e.to];
+        }
+        edges.sort_by_cached_key(|e| (e.from, e.to, e.label()));
+        ReducedProgramGraph { edges }
+    }
+}
+
+/// Removes a node whose only outgoing edge is a `skip`, by redirecting the
+/// edges into it to the target of the `skip`.
+fn remove_skip_node(edges: &mut Vec<ReducedEdge>, kept: &BTreeSet<Node>) -> bool {
+    for node in edges.iter().map(|e| e.from).unique().collect_vec() {
+        if kept.contains(&node) {
+            continue;
+        }
+        let [idx] = edges.iter().positions(|e| e.from == node).collect_vec()[..] else {
+            continue;
+        };
+        if !edges[idx].actions.is_empty() || edges[idx].to == node {
+            continue;
+        }
+        let target = edges.remove(idx).to;
+        for e in edges.iter_mut().filter(|e| e.to == node) {
+            e.to = target;
+        }
+        return true;
+    }
+    false
+}
+
+/// Merges the edges into and out of a node with a single edge of each.
+fn merge_chain(edges: &mut Vec<ReducedEdge>, kept: &BTreeSet<Node>) -> bool {
+    for node in edges.iter().map(|e| e.from).unique().collect_vec() {
+        if kept.contains(&node) {
+            continue;
+        }
+        let incoming = edges.iter().positions(|e| e.to == node).collect_vec();
+        let outgoing = edges.iter().positions(|e| e.from == node).collect_vec();
+        let ([i], [o]) = (&incoming[..], &outgoing[..]) else {
+            continue;
+        };
+        if i == o || edges[*i].is_call_or_return() || edges[*o].is_call_or_return() {
+            continue;
+        }
+        let out = edges.remove(*o);
+        let e = &mut edges[if o < i { i - 1 } else { *i }];
+        e.actions.extend(out.actions);
+        e.to = out.to;
+        e.span = e.span.join(out.span);
+        return true;
+    }
+    false
+}
+
+/// Names the nodes `q1`, `q2`, ... in breadth-first order from the initial
+/// node, visiting successors in the order of the labels of the edges leading
+/// to them. Unreachable nodes are visited from those without incoming edges
+/// first, and then in the order of `nodes`.
+fn canonical_names(
+    nodes: impl IntoIterator<Item = Node>,
+    edges: impl IntoIterator<Item = (Node, String, Node)>,
+) -> BTreeMap<Node, Node> {
+    let nodes = nodes.into_iter().collect_vec();
+    let mut successors: BTreeMap<Node, Vec<(String, Node)>> = BTreeMap::new();
+    let mut has_incoming = BTreeSet::new();
+    for (from, label, to) in edges {
+        successors.entry(from).or_default().push((label, to));
+        has_incoming.insert(to);
+    }
+    for succ in successors.values_mut() {
+        succ.sort_by(|(l, _), (r, _)| l.cmp(r));
+    }
+
+    let roots = std::iter::once(Node::Start)
+        .filter(|n| nodes.contains(n))
+        .chain(nodes.iter().copied().filter(|n| !has_incoming.contains(n)))
+        .chain(nodes.iter().copied());
+
+    let mut names = BTreeMap::new();
+    let mut next_id = 1;
+    for root in roots {
+        let mut queue = VecDeque::from([root]);
+        while let Some(n) = queue.pop_front() {
+            if names.contains_key(&n) {
+                continue;
+            }
+            let name = match n {
+                Node::Start | Node::End => n,
+                Node::Node(_) => {
+                    next_id += 1;
+                    Node::Node(NodeId(next_id - 1))
+                }
+            };
+            names.insert(n, name);
+            queue.extend(successors.get(&n).into_iter().flatten().map(|(_, t)| *t));
+        }
+    }
+    names
+}
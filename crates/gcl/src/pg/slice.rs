@@ -0,0 +1,350 @@
+//! Backward slicing of [`ProgramGraph`]s.
+//!
+//! An edge can affect the value of a variable at a node if the variable is
+//! defined by the edge and the definition reaches the node (a data
+//! dependency), or if the edge is a condition deciding whether the node is
+//! reached (a control dependency). The slice of a node and a set of variables
+//! is the least set of edges closed under both kinds of dependencies,
+//! starting from the variables at the node.
+//!
+//! The dependencies are computed for every path of the graph, including
+//! paths where a procedure returns to another caller than the one it was
+//! called from, such that a slice may contain more edges than necessary, but
+//! never fewer.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+
+use super::{Action, Edge, Node, ProgramGraph};
+use crate::ast::{AExpr, Target};
+
+/// The data and control dependencies of the edges of a [`ProgramGraph`], as
+/// computed by [`ProgramGraph::dependencies`]. Edges are identified by their
+/// index in [`ProgramGraph::edges`].
+#[derive(Debug, Clone)]
+pub struct Dependencies<'a> {
+    pg: &'a ProgramGraph,
+    /// The definitions reaching each node, as the target defined along with
+    /// the edge defining it.
+    reaching: IndexMap<Node, IndexSet<(Target, usize)>>,
+    /// The conditions deciding whether each node is reached.
+    control: IndexMap<Node, IndexSet<usize>>,
+}
+
+/// The edges of a [`ProgramGraph`] which can affect a set of variables at a
+/// node, as computed by [`ProgramGraph::slice`].
+#[derive(Debug, Clone)]
+pub struct Slice<'a> {
+    pg: &'a ProgramGraph,
+    node: Node,
+    edges: BTreeSet<usize>,
+}
+
+impl ProgramGraph {
+    pub fn dependencies(&self) -> Dependencies<'_> {
+        let reaching = reaching_definitions(self);
+        let control = control_dependencies(self);
+        Dependencies {
+            pg: self,
+            reaching,
+            control,
+        }
+    }
+
+    /// The edges which can affect the values of `vars` when `node` is
+    /// reached, including the conditions deciding whether it is reached.
+    pub fn slice(&self, node: Node, vars: &IndexSet<Target>) -> Slice<'_> {
+        self.dependencies().slice(node, vars)
+    }
+}
+
+impl<'a> Dependencies<'a> {
+    pub fn program_graph(&self) -> &'a ProgramGraph {
+        self.pg
+    }
+
+    /// The edges whose definitions of `target` reach `node`. Assignments to
+    /// elements of an array only add to the definitions of the array, while
+    /// assignments to variables replace them.
+    pub fn definitions(&self, node: Node, target: &Target) -> impl Iterator<Item = &'a Edge> {
+        let edges = self.pg.edges();
+        self.reaching
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .filter(move |(t, _)| t == target)
+            .map(move |(_, i)| &edges[*i])
+    }
+
+    /// The conditions `node` is control dependent on, that is, the conditions
+    /// after which `node` is always reached, but which are not always followed
+    /// by `node` when their source is reached.
+    pub fn controls(&self, node: Node) -> impl Iterator<Item = &'a Edge> {
+        let edges = self.pg.edges();
+        self.control
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .map(move |i| &edges[*i])
+    }
+
+    /// See [`ProgramGraph::slice`].
+    pub fn slice(&self, node: Node, vars: &IndexSet<Target>) -> Slice<'a> {
+        let mut edges = BTreeSet::new();
+        let mut queue = VecDeque::new();
+
+        let dependencies = |node: Node, vars: &IndexSet<Target>| {
+            let data = self
+                .reaching
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .filter(|(t, _)| vars.contains(t))
+                .map(|(_, i)| *i);
+            let control = self.control.get(&node).into_iter().flatten().copied();
+            data.chain(control).collect_vec()
+        };
+
+        queue.extend(dependencies(node, vars));
+        while let Some(i) = queue.pop_front() {
+            if !edges.insert(i) {
+                continue;
+            }
+            let e = &self.pg.edges()[i];
+            queue.extend(dependencies(e.from(), &uses(self.pg, e.action())));
+        }
+
+        Slice {
+            pg: self.pg,
+            node,
+            edges,
+        }
+    }
+}
+
+impl Slice<'_> {
+    /// The node the slice is computed for.
+    pub fn node(&self) -> Node {
+        self.node
+    }
+    /// The edges of the slice, in the order of [`ProgramGraph::edges`].
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().map(|i| &self.pg.edges()[*i])
+    }
+    pub fn contains(&self, edge: &Edge) -> bool {
+        self.edges().any(|e| e == edge)
+    }
+
+    /// The whole graph as in [`ProgramGraph::dot`], with the edges of the
+    /// slice and the node it is computed for highlighted.
+    pub fn dot(&self) -> String {
+        const HIGHLIGHT: &str = "#FF5555";
+        format!(
+            "digraph G {{\n{}\n}}",
+            self.pg
+                .edges()
+                .iter()
+                .enumerate()
+                .map(|(i, Edge(a, t, b, _))| {
+                    let style = if self.edges.contains(&i) {
+                        format!(", color=\"{HIGHLIGHT}\", penwidth=3")
+                    } else {
+                        String::new()
+                    };
+                    let node = |n: &Node| {
+                        if *n == self.node {
+                            format!("{n:?}[label=\"{n}\", color=\"{HIGHLIGHT}\"]")
+                        } else {
+                            format!("{n:?}[label=\"{n}\"]")
+                        }
+                    };
+                    format!(
+                        "  {}; {a:?} -> {b:?}[label={:?}{style}]; {};",
+                        node(a),
+                        t.to_string(),
+                        node(b),
+                    )
+                })
+                .format("  \n")
+        )
+    }
+}
+
+/// The local variables of the procedure called along `action`, if any.
+fn locals(pg: &ProgramGraph, action: &Action) -> Vec<Target> {
+    match action {
+        Action::Call(call) | Action::Return(call) => pg
+            .procedure(&call.name)
+            .into_iter()
+            .flat_map(|s| s.locals())
+            .map(|v| Target::Variable(v.clone()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The targets assigned by `action`.
+fn defines(pg: &ProgramGraph, action: &Action) -> Vec<Target> {
+    match action {
+        Action::Assignment(x, _) | Action::BoolAssignment(x, _) => vec![x.clone().unit()],
+        Action::Call(_) => locals(pg, action),
+        Action::Return(call) => call.results.iter().map(|t| t.clone().unit()).collect(),
+        Action::Skip | Action::Condition(_) => vec![],
+    }
+}
+
+/// The targets whose previous definitions no longer reach past `action`.
+/// Assignments to elements of arrays leave the other elements unchanged, and
+/// do not replace the definitions of the array.
+fn kills(pg: &ProgramGraph, action: &Action) -> Vec<Target> {
+    let kill = |x: &Target<Box<AExpr>>| match x {
+        Target::Variable(v) => Some(Target::Variable(v.clone())),
+        Target::Array(_, _) => None,
+    };
+    match action {
+        Action::Assignment(x, _) | Action::BoolAssignment(x, _) => kill(x).into_iter().collect(),
+        Action::Call(_) => locals(pg, action),
+        // NOTE: the locals are restored to their values before the call, which
+        // are propagated from the call node by `reaching_definitions`
+        Action::Return(call) => locals(pg, action)
+            .into_iter()
+            .chain(call.results.iter().filter_map(kill))
+            .collect(),
+        Action::Skip | Action::Condition(_) => vec![],
+    }
+}
+
+/// The targets whose values `action` depends on.
+fn uses(pg: &ProgramGraph, action: &Action) -> IndexSet<Target> {
+    let index = |x: &Target<Box<AExpr>>| match x {
+        Target::Variable(_) => IndexSet::default(),
+        Target::Array(_, idx) => idx.fv(),
+    };
+    match action {
+        Action::Assignment(x, a) => index(x).union(&a.fv()).cloned().collect(),
+        Action::BoolAssignment(x, b) => index(x).union(&b.fv()).cloned().collect(),
+        Action::Skip => IndexSet::default(),
+        Action::Condition(b) => b.fv(),
+        Action::Call(call) => call.args.iter().flat_map(|a| a.fv()).collect(),
+        Action::Return(call) => pg
+            .procedure(&call.name)
+            .into_iter()
+            .flat_map(|s| &s.results)
+            .map(|v| Target::Variable(v.clone()))
+            .chain(call.results.iter().flat_map(index))
+            .collect(),
+    }
+}
+
+fn reaching_definitions(pg: &ProgramGraph) -> IndexMap<Node, IndexSet<(Target, usize)>> {
+    let mut facts: IndexMap<Node, IndexSet<(Target, usize)>> = pg
+        .nodes()
+        .iter()
+        .map(|n| (*n, IndexSet::default()))
+        .collect();
+    let mut worklist: VecDeque<Node> = pg.nodes().iter().copied().collect();
+
+    fn propagate(
+        facts: &mut IndexMap<Node, IndexSet<(Target, usize)>>,
+        to: Node,
+        new: IndexSet<(Target, usize)>,
+    ) -> bool {
+        let target = facts.entry(to).or_default();
+        let len = target.len();
+        target.extend(new);
+        target.len() > len
+    }
+
+    while let Some(n) = worklist.pop_front() {
+        for (i, e) in pg.edges().iter().enumerate().filter(|(_, e)| e.from() == n) {
+            let killed = kills(pg, e.action());
+            let new = facts[&n]
+                .iter()
+                .filter(|(t, _)| !killed.contains(t))
+                .cloned()
+                .chain(defines(pg, e.action()).into_iter().map(|t| (t, i)))
+                .collect();
+            if propagate(&mut facts, e.to(), new) {
+                worklist.push_back(e.to());
+            }
+
+            // NOTE: the locals of the procedure are restored when returning,
+            // so their definitions before the call reach the return node
+            if let (Action::Call(_), Some(r)) = (e.action(), pg.return_node(n)) {
+                let restored = locals(pg, e.action());
+                let new = facts[&n]
+                    .iter()
+                    .filter(|(t, _)| restored.contains(t))
+                    .cloned()
+                    .collect();
+                if propagate(&mut facts, r, new) {
+                    worklist.push_back(r);
+                }
+            }
+        }
+    }
+
+    facts
+}
+
+/// The nodes every path from each node to a node without outgoing edges goes
+/// through, including the node itself. Nodes from which no such node can be
+/// reached are post dominated by every node.
+fn post_dominators(pg: &ProgramGraph) -> IndexMap<Node, BTreeSet<Node>> {
+    let all: BTreeSet<Node> = pg.nodes().iter().copied().collect();
+    let mut pdom: IndexMap<Node, BTreeSet<Node>> = pg
+        .nodes()
+        .iter()
+        .map(|&n| {
+            if pg.outgoing(n).is_empty() {
+                (n, BTreeSet::from([n]))
+            } else {
+                (n, all.clone())
+            }
+        })
+        .collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &n in pg.nodes() {
+            let Some(mut next) = pg
+                .outgoing(n)
+                .iter()
+                .map(|e| pdom[&e.to()].clone())
+                .reduce(|a, b| a.intersection(&b).copied().collect())
+            else {
+                continue;
+            };
+            next.insert(n);
+            if next != pdom[&n] {
+                pdom[&n] = next;
+                changed = true;
+            }
+        }
+    }
+
+    pdom
+}
+
+/// A node `y` is control dependent on a condition from `x` to `z`, if `y`
+/// post dominates `z` but does not strictly post dominate `x`.
+fn control_dependencies(pg: &ProgramGraph) -> IndexMap<Node, IndexSet<usize>> {
+    let pdom = post_dominators(pg);
+    let mut control: IndexMap<Node, IndexSet<usize>> = IndexMap::default();
+
+    for (i, e) in pg.edges().iter().enumerate() {
+        if !matches!(e.action(), Action::Condition(_)) {
+            continue;
+        }
+        for &y in &pdom[&e.to()] {
+            if y == e.from() || !pdom[&e.from()].contains(&y) {
+                control.entry(y).or_default().insert(i);
+            }
+        }
+    }
+
+    control
+}
@@ -1,5 +1,6 @@
 use crate::{
-    ast::{AExpr, AOp, Array, BExpr, Int, LogicOp, RelOp, Target, Variable},
+    ast::{AExpr, AExprKind, AOp, Array, BExpr, BExprKind, Int, LogicOp, RelOp, Target, Variable},
+    integer::{Integer, IntegerSemantics},
     pg::Action,
 };
 
@@ -14,7 +15,7 @@ pub enum SemanticsError {
     #[error("array '{name}' not found")]
     ArrayNotFound { name: String },
     #[error("index {index} in '{name}' is out-of-bounds")]
-    IndexOutOfBound { name: String, index: Int },
+    IndexOutOfBound { name: String, index: Integer },
     #[error("no progression")]
     NoProgression,
     #[error("an arithmetic operation overflowed")]
@@ -23,37 +24,54 @@ pub enum SemanticsError {
     EvaluateQuantifier,
     #[error("tried to evaluate function where argument was outside of domain")]
     OutsideFunctionDomain,
+    #[error("calls and returns can only be evaluated with a call stack")]
+    CallWithoutStack,
+    #[error("wrapping integers must have between 1 and 64 bits, not {bits}")]
+    UnsupportedBitWidth { bits: u32 },
 }
 
 pub trait SemanticsContext: Sized + Clone {
-    fn variable(&self, var: &Variable) -> Result<Int, SemanticsError>;
-    fn array_element(&self, array: &Array, index: Int) -> Result<Int, SemanticsError>;
-    fn set_variable(&self, var: &Variable, value: Int) -> Result<Self, SemanticsError>;
+    fn variable(&self, var: &Variable) -> Result<Integer, SemanticsError>;
+    fn array_element(&self, array: &Array, index: &Integer) -> Result<Integer, SemanticsError>;
+    fn set_variable(&self, var: &Variable, value: Integer) -> Result<Self, SemanticsError>;
     fn set_array_element(
         &self,
         array: &Array,
-        index: Int,
-        value: Int,
+        index: &Integer,
+        value: Integer,
     ) -> Result<Self, SemanticsError>;
-    fn array_length(&self, array: &Array) -> Result<Int, SemanticsError>;
-    fn array_count(&self, array: &Array, element: Int) -> Result<Int, SemanticsError>;
+    fn array_length(&self, array: &Array) -> Result<Integer, SemanticsError>;
+    fn array_count(&self, array: &Array, element: &Integer) -> Result<Integer, SemanticsError>;
+    fn bool_variable(&self, var: &Variable) -> Result<bool, SemanticsError>;
+    fn bool_array_element(&self, array: &Array, index: &Integer) -> Result<bool, SemanticsError>;
+    fn set_bool_variable(&self, var: &Variable, value: bool) -> Result<Self, SemanticsError>;
+    fn set_bool_array_element(
+        &self,
+        array: &Array,
+        index: &Integer,
+        value: bool,
+    ) -> Result<Self, SemanticsError>;
+    /// The model of integers arithmetic is evaluated in.
+    fn integers(&self) -> IntegerSemantics {
+        IntegerSemantics::Checked
+    }
 }
 
 #[derive(Clone)]
 pub struct EmptySemanticsContext;
 
 impl SemanticsContext for EmptySemanticsContext {
-    fn variable(&self, var: &Variable) -> Result<Int, SemanticsError> {
+    fn variable(&self, var: &Variable) -> Result<Integer, SemanticsError> {
         Err(SemanticsError::VariableNotFound {
             name: var.to_string(),
         })
     }
 
-    fn set_variable(&self, _var: &Variable, _value: Int) -> Result<Self, SemanticsError> {
+    fn set_variable(&self, _var: &Variable, _value: Integer) -> Result<Self, SemanticsError> {
         Ok(self.clone())
     }
 
-    fn array_element(&self, array: &Array, _index: Int) -> Result<Int, SemanticsError> {
+    fn array_element(&self, array: &Array, _index: &Integer) -> Result<Integer, SemanticsError> {
         Err(SemanticsError::ArrayNotFound {
             name: array.to_string(),
         })
@@ -62,44 +80,147 @@ impl SemanticsContext for EmptySemanticsContext {
     fn set_array_element(
         &self,
         _array: &Array,
-        _index: Int,
-        _value: Int,
+        _index: &Integer,
+        _value: Integer,
     ) -> Result<Self, SemanticsError> {
         Ok(self.clone())
     }
 
-    fn array_length(&self, array: &Array) -> Result<Int, SemanticsError> {
+    fn array_length(&self, array: &Array) -> Result<Integer, SemanticsError> {
         Err(SemanticsError::ArrayNotFound {
             name: array.to_string(),
         })
     }
 
-    fn array_count(&self, array: &Array, _element: Int) -> Result<Int, SemanticsError> {
+    fn array_count(&self, array: &Array, _element: &Integer) -> Result<Integer, SemanticsError> {
         Err(SemanticsError::ArrayNotFound {
             name: array.to_string(),
         })
     }
+
+    fn bool_variable(&self, var: &Variable) -> Result<bool, SemanticsError> {
+        Err(SemanticsError::VariableNotFound {
+            name: var.to_string(),
+        })
+    }
+
+    fn bool_array_element(&self, array: &Array, _index: &Integer) -> Result<bool, SemanticsError> {
+        Err(SemanticsError::ArrayNotFound {
+            name: array.to_string(),
+        })
+    }
+
+    fn set_bool_variable(&self, _var: &Variable, _value: bool) -> Result<Self, SemanticsError> {
+        Ok(self.clone())
+    }
+
+    fn set_bool_array_element(
+        &self,
+        _array: &Array,
+        _index: &Integer,
+        _value: bool,
+    ) -> Result<Self, SemanticsError> {
+        Ok(self.clone())
+    }
+}
+
+/// A context which evaluates arithmetic in `integers` rather than in the model
+/// of `context`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithIntegers<S> {
+    pub context: S,
+    pub integers: IntegerSemantics,
+}
+
+impl<S: SemanticsContext> SemanticsContext for WithIntegers<S> {
+    fn variable(&self, var: &Variable) -> Result<Integer, SemanticsError> {
+        self.context.variable(var)
+    }
+
+    fn array_element(&self, array: &Array, index: &Integer) -> Result<Integer, SemanticsError> {
+        self.context.array_element(array, index)
+    }
+
+    fn set_variable(&self, var: &Variable, value: Integer) -> Result<Self, SemanticsError> {
+        Ok(WithIntegers {
+            context: self.context.set_variable(var, value)?,
+            integers: self.integers,
+        })
+    }
+
+    fn set_array_element(
+        &self,
+        array: &Array,
+        index: &Integer,
+        value: Integer,
+    ) -> Result<Self, SemanticsError> {
+        Ok(WithIntegers {
+            context: self.context.set_array_element(array, index, value)?,
+            integers: self.integers,
+        })
+    }
+
+    fn array_length(&self, array: &Array) -> Result<Integer, SemanticsError> {
+        self.context.array_length(array)
+    }
+
+    fn array_count(&self, array: &Array, element: &Integer) -> Result<Integer, SemanticsError> {
+        self.context.array_count(array, element)
+    }
+
+    fn bool_variable(&self, var: &Variable) -> Result<bool, SemanticsError> {
+        self.context.bool_variable(var)
+    }
+
+    fn bool_array_element(&self, array: &Array, index: &Integer) -> Result<bool, SemanticsError> {
+        self.context.bool_array_element(array, index)
+    }
+
+    fn set_bool_variable(&self, var: &Variable, value: bool) -> Result<Self, SemanticsError> {
+        Ok(WithIntegers {
+            context: self.context.set_bool_variable(var, value)?,
+            integers: self.integers,
+        })
+    }
+
+    fn set_bool_array_element(
+        &self,
+        array: &Array,
+        index: &Integer,
+        value: bool,
+    ) -> Result<Self, SemanticsError> {
+        Ok(WithIntegers {
+            context: self.context.set_bool_array_element(array, index, value)?,
+            integers: self.integers,
+        })
+    }
+
+    fn integers(&self) -> IntegerSemantics {
+        self.integers
+    }
 }
 
 impl AExpr {
-    pub fn semantics<S: SemanticsContext>(&self, cx: &S) -> Result<Int, SemanticsError> {
-        Ok(match self {
-            AExpr::Number(n) => *n,
-            AExpr::Reference(Target::Variable(x)) => cx.variable(x)?,
-            AExpr::Reference(Target::Array(arr, idx)) => {
+    pub fn semantics<S: SemanticsContext>(&self, cx: &S) -> Result<Integer, SemanticsError> {
+        Ok(match &self.kind {
+            AExprKind::Number(n) => cx.integers().normalize((*n).into())?,
+            AExprKind::Reference(Target::Variable(x)) => cx.variable(x)?,
+            AExprKind::Reference(Target::Array(arr, idx)) => {
                 let idx = idx.semantics(cx)?;
-                cx.array_element(arr, idx)?
+                cx.array_element(arr, &idx)?
+            }
+            AExprKind::Binary(l, op, r) => {
+                cx.integers()
+                    .binary(*op, &l.semantics(cx)?, &r.semantics(cx)?)?
             }
-            AExpr::Binary(l, op, r) => op.semantic(l.semantics(cx)?, r.semantics(cx)?)?,
-            AExpr::Minus(n) => n
-                .semantics(cx)?
-                .checked_neg()
-                .ok_or(SemanticsError::ArithmeticOverflow)?,
+            AExprKind::Minus(n) => cx.integers().negate(&n.semantics(cx)?)?,
+            AExprKind::Declassify(e, _) => e.semantics(cx)?,
         })
     }
 }
 
 impl AOp {
+    /// The operation on checked 32-bit integers.
     pub fn semantic(&self, l: Int, r: Int) -> Result<Int, SemanticsError> {
         Ok(match self {
             AOp::Plus => l.checked_add(r).ok_or(SemanticsError::ArithmeticOverflow)?,
@@ -107,7 +228,7 @@ impl AOp {
             AOp::Times => l.checked_mul(r).ok_or(SemanticsError::ArithmeticOverflow)?,
             AOp::Divide => {
                 if r != 0 {
-                    l / r
+                    l.checked_div(r).ok_or(SemanticsError::ArithmeticOverflow)?
                 } else {
                     return Err(SemanticsError::DivisionByZero);
                 }
@@ -126,17 +247,22 @@ impl AOp {
 
 impl BExpr {
     pub fn semantics<S: SemanticsContext>(&self, cx: &S) -> Result<bool, SemanticsError> {
-        Ok(match self {
-            BExpr::Bool(b) => *b,
-            BExpr::Rel(l, op, r) => op.semantic(l.semantics(cx)?, r.semantics(cx)?),
-            BExpr::Logic(l, op, r) => op.semantic(l.semantics(cx)?, || r.semantics(cx))?,
-            BExpr::Not(b) => !b.semantics(cx)?,
+        Ok(match &self.kind {
+            BExprKind::Bool(b) => *b,
+            BExprKind::Reference(Target::Variable(x)) => cx.bool_variable(x)?,
+            BExprKind::Reference(Target::Array(arr, idx)) => {
+                let idx = idx.semantics(cx)?;
+                cx.bool_array_element(arr, &idx)?
+            }
+            BExprKind::Rel(l, op, r) => op.semantic(l.semantics(cx)?, r.semantics(cx)?),
+            BExprKind::Logic(l, op, r) => op.semantic(l.semantics(cx)?, || r.semantics(cx))?,
+            BExprKind::Not(b) => !b.semantics(cx)?,
         })
     }
 }
 
 impl RelOp {
-    pub fn semantic(&self, l: Int, r: Int) -> bool {
+    pub fn semantic<T: Ord>(&self, l: T, r: T) -> bool {
         match self {
             RelOp::Eq => l == r,
             RelOp::Ne => l != r,
@@ -179,7 +305,16 @@ impl Action {
             Action::Assignment(Target::Array(arr, idx), a) => {
                 let idx = idx.semantics(cx)?;
                 let value = a.semantics(cx)?;
-                cx.set_array_element(arr, idx, value)
+                cx.set_array_element(arr, &idx, value)
+            }
+            Action::BoolAssignment(Target::Variable(x), b) => {
+                let value = b.semantics(cx)?;
+                cx.set_bool_variable(x, value)
+            }
+            Action::BoolAssignment(Target::Array(arr, idx), b) => {
+                let idx = idx.semantics(cx)?;
+                let value = b.semantics(cx)?;
+                cx.set_bool_array_element(arr, &idx, value)
             }
             Action::Skip => Ok(cx.clone()),
             Action::Condition(b) => {
@@ -189,6 +324,8 @@ impl Action {
                     Err(SemanticsError::NoProgression)
                 }
             }
+            // NOTE: these are evaluated by `interpreter::Execution`
+            Action::Call(_) | Action::Return(_) => Err(SemanticsError::CallWithoutStack),
         }
     }
 }
@@ -0,0 +1,7 @@
+mod equivalence;
+mod formats;
+mod parse;
+mod procedures;
+mod reduce;
+mod spans;
+mod transform;
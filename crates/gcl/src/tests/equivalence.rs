@@ -0,0 +1,106 @@
+use crate::{
+    parse::{parse_action, parse_commands},
+    pg::{Determinism, Node, ProgramGraph, equivalence::Inequivalence},
+};
+
+fn graph(src: &str) -> ProgramGraph {
+    ProgramGraph::new(Determinism::NonDeterministic, &parse_commands(src).unwrap())
+}
+
+fn named(
+    edges: &[(&'static str, &str, &'static str)],
+) -> (ProgramGraph, Vec<(&'static str, Node)>) {
+    let (pg, nodes) = ProgramGraph::from_named_edges(
+        [],
+        edges
+            .iter()
+            .map(|(a, action, b)| (*a, parse_action(action).unwrap(), *b)),
+    );
+    (pg, nodes.into_iter().collect())
+}
+
+fn node(nodes: &[(&str, Node)], name: &str) -> Node {
+    nodes.iter().find(|(n, _)| *n == name).unwrap().1
+}
+
+const CHAIN: &[(&str, &str, &str)] = &[
+    ("qStart", "x := 1", "a"),
+    ("a", "y := 2", "b"),
+    ("b", "z := 3", "qFinal"),
+];
+
+#[test]
+fn renamed_graph_is_isomorphic() {
+    let pg = graph("x := 1 ; if x > 0 -> y := 1 [] x <= 0 -> y := 2 fi ; z := y");
+    let (renamed, nodes) = named(&[
+        ("qStart", "x := 1", "guard"),
+        ("guard", "x <= 0", "else"),
+        ("guard", "x > 0", "then"),
+        ("else", "y := 2", "join"),
+        ("then", "y := 1", "join"),
+        ("join", "z := y", "qFinal"),
+    ]);
+
+    let map = pg.find_isomorphism(&renamed).unwrap();
+    assert_eq!(map.len(), pg.nodes().len());
+    assert_eq!(map[&Node::Start], Node::Start);
+    assert_eq!(map[&Node::End], Node::End);
+    for e in pg.edges() {
+        let (from, to) = (map[&e.from()], map[&e.to()]);
+        assert!(
+            renamed
+                .outgoing(from)
+                .iter()
+                .any(|r| r.to() == to && r.action() == e.action()),
+            "{e} has no counterpart"
+        );
+    }
+    let guard = pg.outgoing(Node::Start)[0].to();
+    assert_eq!(map[&guard], node(&nodes, "guard"));
+}
+
+#[test]
+fn graph_without_named_start_tries_every_node() {
+    let pg = graph("x := 1 ; y := 2");
+    let (renamed, nodes) = named(&[("b", "y := 2", "c"), ("a", "x := 1", "b")]);
+    let map = pg.find_isomorphism(&renamed).unwrap();
+    assert_eq!(map[&Node::Start], node(&nodes, "a"));
+}
+
+#[test]
+fn graph_with_changed_edge_is_rejected_at_that_edge() {
+    let pg = graph("x := 1 ; y := 2 ; z := 3");
+    assert!(pg.find_isomorphism(&named(CHAIN).0).is_ok());
+
+    let (changed, nodes) = named(&[
+        ("qStart", "x := 1", "a"),
+        ("a", "y := 2", "b"),
+        ("b", "z := 4", "qFinal"),
+    ]);
+    let Err(Inequivalence::Missing { edge, counterpart }) = pg.find_isomorphism(&changed) else {
+        panic!("expected a missing edge");
+    };
+    assert_eq!(edge.action().to_string(), "z := 3");
+    assert_eq!(counterpart, Some(node(&nodes, "b")));
+}
+
+#[test]
+fn graph_with_extra_edge_is_rejected_at_that_edge() {
+    let pg = graph("x := 1 ; y := 2 ; z := 3");
+    let (extra, nodes) = named(&[CHAIN, &[("a", "skip", "b")]].concat());
+    let Err(Inequivalence::Extra { edge }) = pg.find_isomorphism(&extra) else {
+        panic!("expected an extra edge");
+    };
+    assert_eq!(edge.action().to_string(), "skip");
+    assert_eq!(edge.from(), node(&nodes, "a"));
+}
+
+#[test]
+fn graph_with_missing_edge_is_rejected_at_that_edge() {
+    let pg = graph("x := 1 ; y := 2 ; z := 3");
+    let (missing, _) = named(&CHAIN[..2]);
+    let Err(Inequivalence::Missing { edge, .. }) = pg.find_isomorphism(&missing) else {
+        panic!("expected a missing edge");
+    };
+    assert_eq!(edge.action().to_string(), "z := 3");
+}
@@ -0,0 +1,64 @@
+use crate::{
+    parse::parse_commands,
+    pg::{Determinism, ProgramGraph, formats::FormatError},
+};
+
+const PROGRAMS: &[&str] = &[
+    "x := 1 ; y := x * -2",
+    "A[x + 1] := A[x] ; b := true ; B[0] := b && x < 2",
+    "if x > 0 -> y := x [] x <= 0 -> skip fi ; do y > 0 -> y := y - 1 od",
+    "proc inc(n; m) m := n + 1 end ; call inc(x; y) ; call inc(y; x)",
+];
+
+fn graph(src: &str, determinism: Determinism) -> ProgramGraph {
+    ProgramGraph::new(determinism, &parse_commands(src).unwrap())
+}
+
+#[test]
+fn json_round_trips() {
+    for src in PROGRAMS {
+        for determinism in [Determinism::Deterministic, Determinism::NonDeterministic] {
+            let pg = graph(src, determinism);
+            let json = pg.json();
+            let parsed = ProgramGraph::from_json(&json).unwrap();
+            assert_eq!(parsed.json(), json, "{src}");
+            assert_eq!(parsed.nodes(), pg.nodes(), "{src}");
+            assert_eq!(parsed.edges(), pg.edges(), "{src}");
+        }
+    }
+}
+
+#[test]
+fn json_nodes_may_be_renamed() {
+    let json = r#"{
+        "edges": [
+            { "from": "qStart", "action": { "type": "Assignment", "target": "x", "expr": "1" }, "to": "mid" },
+            { "from": "mid", "action": { "type": "Condition", "condition": "x > 0" }, "to": "body" },
+            { "from": "body", "action": { "type": "Skip" }, "to": "qFinal" }
+        ]
+    }"#;
+    let parsed = ProgramGraph::from_json(json).unwrap();
+    let pg = graph(
+        "x := 1 ; if x > 0 -> skip fi",
+        Determinism::NonDeterministic,
+    );
+    assert!(pg.find_isomorphism(&parsed).is_ok());
+}
+
+#[test]
+fn json_without_start_is_rejected() {
+    let json = r#"{ "edges": [{ "from": "q1", "action": { "type": "Skip" }, "to": "qFinal" }] }"#;
+    assert!(matches!(
+        ProgramGraph::from_json(json),
+        Err(FormatError::MissingStart)
+    ));
+}
+
+#[test]
+fn json_with_malformed_action_is_rejected() {
+    let json = r#"{ "edges": [{ "from": "qStart", "action": { "type": "Condition", "condition": "x +" }, "to": "qFinal" }] }"#;
+    let Err(FormatError::Parse { src, .. }) = ProgramGraph::from_json(json) else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(src, "x +");
+}
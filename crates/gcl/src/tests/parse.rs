@@ -0,0 +1,53 @@
+use crate::parse::{line_column, parse_commands};
+
+fn located(src: &str) -> Vec<(String, String)> {
+    parse_commands(src)
+        .unwrap_err()
+        .errors()
+        .iter()
+        .map(|e| {
+            let span = e.span();
+            (e.src()[span.offset()..span.end()].to_string(), e.describe())
+        })
+        .collect()
+}
+
+#[test]
+fn malformed_commands_are_all_reported() {
+    let errors = located("x := ; y := 2 ; z := *");
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert_eq!(errors[0].0, ";");
+    assert!(errors[0].1.starts_with("1:6: Unrecognized Token."));
+    assert_eq!(errors[1].0, "*");
+    assert!(errors[1].1.starts_with("1:22: Unrecognized Token."));
+}
+
+#[test]
+fn malformed_guards_are_all_reported() {
+    let errors = located("if x > -> skip [] y < 1 -> z := fi");
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert_eq!(errors[0].0, "->");
+    assert!(errors[0].1.starts_with("1:8: "));
+    assert_eq!(errors[1].0, "fi");
+    assert!(errors[1].1.starts_with("1:33: "));
+}
+
+#[test]
+fn errors_are_described_on_separate_lines() {
+    let errors = parse_commands("x := ;\ny := )").unwrap_err();
+    let lines: Vec<_> = errors.to_string().lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    assert!(lines[0].starts_with("1:6: Unrecognized Token. Expected one of: "));
+    assert!(lines[1].starts_with("2:6: Unrecognized Token. Expected one of: "));
+}
+
+#[test]
+fn columns_count_utf16_code_units() {
+    let src = "x := ;\n/* é😀 */ y := )";
+    assert_eq!(line_column(src, src.find(';').unwrap()), (1, 6));
+    assert_eq!(line_column(src, src.find(')').unwrap()), (2, 16));
+    let errors = located(src);
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(errors[0].1.starts_with("1:6: "));
+    assert!(errors[1].1.starts_with("2:16: "));
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    ast::{ProcedureName, Variable},
+    parse::parse_commands,
+    pg::{
+        Action, Determinism, InlineError, Node, ProgramGraph,
+        analysis::{
+            call_string_analysis,
+            zone::{DifferenceConstraint, ZoneAnalysis},
+        },
+    },
+};
+
+const TWICE: &str =
+    "proc inc(n; m) m := n + 1 end ; x := 1 ; call inc(x; y) ; x := 5 ; call inc(x; z)";
+
+/// The nodes reachable from `node` without returning from a call.
+fn body(pg: &ProgramGraph, node: Node) -> BTreeSet<Node> {
+    let mut seen = BTreeSet::from([node]);
+    let mut pending = vec![node];
+    while let Some(n) = pending.pop() {
+        for e in pg.outgoing(n) {
+            if !matches!(e.action(), Action::Return(_)) && seen.insert(e.to()) {
+                pending.push(e.to());
+            }
+        }
+    }
+    seen
+}
+
+fn count(pg: &ProgramGraph, f: impl Fn(&Action) -> bool) -> usize {
+    pg.edges().iter().filter(|e| f(e.action())).count()
+}
+
+#[test]
+fn inlined_graph_copies_procedures() {
+    let cmds = parse_commands(TWICE).unwrap();
+    let shared = ProgramGraph::new(Determinism::Deterministic, &cmds);
+    let inlined = ProgramGraph::inlined(Determinism::Deterministic, &cmds).unwrap();
+
+    let is_body = |a: &Action| a.to_string() == "m := (n + 1)";
+    assert_eq!(count(&shared, is_body), 1);
+    assert_eq!(count(&inlined, is_body), 2);
+
+    // NOTE: every call still enters and leaves its copy by a call and a return
+    for pg in [&shared, &inlined] {
+        assert_eq!(count(pg, |a| matches!(a, Action::Call(_))), 2);
+        assert_eq!(count(pg, |a| matches!(a, Action::Return(_))), 2);
+    }
+
+    // NOTE: each copy is only left by the return to its own caller
+    for e in inlined.edges() {
+        if let Action::Call(_) = e.action() {
+            let returns_from_copy = inlined
+                .edges()
+                .iter()
+                .filter(|r| matches!(r.action(), Action::Return(_)))
+                .filter(|r| body(&inlined, e.to()).contains(&r.from()))
+                .map(|r| r.to())
+                .collect::<Vec<_>>();
+            assert_eq!(returns_from_copy, [inlined.return_node(e.from()).unwrap()]);
+        }
+    }
+}
+
+#[test]
+fn recursive_procedures_cannot_be_inlined() {
+    // NOTE: the error names the first procedure called again while inlining
+    for (src, procedure) in [
+        ("proc f(n; m) call f(n; m) end ; call f(x; y)", "f"),
+        (
+            "proc f(n; m) call g(n; m) end ; proc g(n; m) call f(n; m) end ; call g(x; y)",
+            "g",
+        ),
+    ] {
+        let cmds = parse_commands(src).unwrap();
+        assert_eq!(
+            ProgramGraph::inlined(Determinism::Deterministic, &cmds).unwrap_err(),
+            InlineError::Recursive {
+                procedure: ProcedureName(procedure.to_string())
+            },
+            "{src}"
+        );
+        // NOTE: calls and returns to a single subgraph support recursion
+        ProgramGraph::new(Determinism::Deterministic, &cmds);
+    }
+}
+
+#[test]
+fn call_strings_distinguish_calls() {
+    let cmds = parse_commands(TWICE).unwrap();
+    let pg = ProgramGraph::new(Determinism::Deterministic, &cmds);
+
+    let bounds = |k: usize, x: &str| {
+        let results = call_string_analysis(ZoneAnalysis::new(&cmds), &pg, k);
+        let constraints = results.facts[&Node::End].constraints().unwrap();
+        let bound = |left, right| {
+            constraints
+                .iter()
+                .find(|c| c.left == left && c.right == right)
+                .map(|c: &DifferenceConstraint| c.bound)
+        };
+        let x = Some(Variable(x.to_string()));
+        (bound(None, x.clone()).map(|b| -b), bound(x, None))
+    };
+
+    // NOTE: with the most recent call, each return only gets the results of
+    // its own call
+    assert_eq!(bounds(1, "x"), (Some(5), Some(5)));
+    assert_eq!(bounds(1, "y"), (Some(2), Some(2)));
+    assert_eq!(bounds(1, "z"), (Some(6), Some(6)));
+
+    // NOTE: without call strings, both returns get the joined results of
+    // both calls
+    assert_eq!(bounds(0, "x"), (Some(1), Some(5)));
+    assert_eq!(bounds(0, "z"), (Some(2), Some(6)));
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    ast::{Int, Variable},
+    integer::Integer,
+    interpreter::{Execution, InterpreterMemory},
+    parse::parse_commands,
+    pg::{Determinism, Node, ProgramGraph, reduce::ReducedProgramGraph},
+};
+
+fn graph(src: &str, determinism: Determinism) -> ProgramGraph {
+    ProgramGraph::new(determinism, &parse_commands(src).unwrap())
+}
+
+fn memory(values: &[(&str, Int)]) -> InterpreterMemory {
+    let mut mem = InterpreterMemory::default();
+    for (x, v) in values {
+        mem.variables
+            .insert(Variable(x.to_string()), Integer::from(*v));
+    }
+    mem
+}
+
+/// The memories in which executions of `pg` from `mem` terminate, performing
+/// the actions of each edge in order.
+fn final_memories(pg: &ReducedProgramGraph, mem: InterpreterMemory) -> BTreeSet<InterpreterMemory> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![(Node::Start, mem)];
+    let mut finals = BTreeSet::new();
+    while let Some((node, mem)) = pending.pop() {
+        if !seen.insert((node, mem.clone())) {
+            continue;
+        }
+        if node == Node::End {
+            finals.insert(mem.clone());
+        }
+        for e in pg.outgoing(node) {
+            let next = e
+                .actions
+                .iter()
+                .try_fold(mem.clone(), |mem, action| action.semantics(&mem));
+            if let Ok(next) = next {
+                pending.push((e.to, next));
+            }
+        }
+    }
+    finals
+}
+
+#[test]
+fn skip_chains_collapse() {
+    let reduced = graph("skip ; skip ; x := 1 ; skip", Determinism::Deterministic).reduce();
+    assert_eq!(reduced.edges().len(), 1, "{}", reduced.dot());
+    let e = &reduced.edges()[0];
+    assert_eq!((e.from, e.to), (Node::Start, Node::End));
+    assert_eq!(e.label(), "x := 1");
+
+    let reduced = graph("skip ; skip", Determinism::Deterministic).reduce();
+    assert_eq!(reduced.edges().len(), 1, "{}", reduced.dot());
+    assert!(reduced.edges()[0].actions.is_empty());
+    assert_eq!(reduced.edges()[0].label(), "skip");
+}
+
+#[test]
+fn sequences_merge_into_compound_actions() {
+    let reduced = graph(
+        "x := 1 ; if x > 0 -> y := x ; skip ; z := y [] x < 0 -> skip fi",
+        Determinism::NonDeterministic,
+    )
+    .reduce();
+    let mut labels: Vec<_> = reduced.edges().iter().map(|e| e.label()).collect();
+    labels.sort();
+    assert_eq!(labels, ["(x < 0)", "(x > 0); y := x; z := y", "x := 1"]);
+}
+
+#[test]
+fn compound_actions_keep_execution_behaviour() {
+    let programs = [
+        "x := 1 ; skip ; y := x + 1 ; z := y * 2",
+        "if x > 0 -> y := x + 1 ; z := y * 2 [] x < 0 -> y := 0 ; skip fi ; z := z + y",
+        "do y < 10 -> y := y + 3 ; skip ; z := z + 1 od ; x := z",
+        "if true -> x := 1 [] true -> x := 2 ; skip fi ; y := x",
+    ];
+    for src in programs {
+        for determinism in [Determinism::Deterministic, Determinism::NonDeterministic] {
+            let pg = graph(src, determinism);
+            let reduced = pg.reduce();
+            for x in [-2, 0, 3] {
+                let mem = memory(&[("x", x), ("y", 0), ("z", 0)]);
+                let exploration = Execution::new(mem.clone()).explore(&pg, 1000);
+                assert!(exploration.complete);
+                let expected: BTreeSet<_> = exploration.final_memories().cloned().collect();
+                assert_eq!(
+                    final_memories(&reduced, mem),
+                    expected,
+                    "{src} with x = {x}\n{}",
+                    reduced.dot()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn canonical_numbering_is_independent_of_construction() {
+    let src = "x := 1 ; if x > 0 -> y := x [] x <= 0 -> do y < 3 -> y := y + 1 od fi";
+    for determinism in [Determinism::Deterministic, Determinism::NonDeterministic] {
+        let first = graph(src, determinism);
+        let second = graph(src, determinism);
+        assert_eq!(first.canonical().nodes(), second.canonical().nodes());
+        assert_eq!(first.canonical().edges(), second.canonical().edges());
+        assert_eq!(first.canonical().dot(), second.canonical().dot());
+        assert_eq!(first.reduce(), second.reduce());
+        assert_eq!(first.reduce().dot(), second.reduce().dot());
+    }
+}
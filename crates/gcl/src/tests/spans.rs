@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::{AExprKind, AOp, BExprKind, Variable},
+    integer::Integer,
+    interpreter::{Execution, InterpreterMemory},
+    parse::{SourceSpan, parse_aexpr, parse_bexpr, parse_commands},
+    pg::{Determinism, ProgramGraph},
+};
+
+fn slice(src: &str, span: SourceSpan) -> &str {
+    &src[span.offset()..span.end()]
+}
+
+#[test]
+fn expressions_span_their_source() {
+    let src = "1 + (x - 2) * A[y]";
+    let a = parse_aexpr(src).unwrap();
+    assert_eq!(slice(src, a.span), src);
+    let AExprKind::Binary(l, AOp::Plus, r) = &a.kind else {
+        panic!("expected an addition, got {a}");
+    };
+    assert_eq!(slice(src, l.span), "1");
+    assert_eq!(slice(src, r.span), "(x - 2) * A[y]");
+    let AExprKind::Binary(l, AOp::Times, r) = &r.kind else {
+        panic!("expected a multiplication, got {r}");
+    };
+    assert_eq!(slice(src, l.span), "x - 2");
+    assert_eq!(slice(src, r.span), "A[y]");
+}
+
+#[test]
+fn boolean_references_keep_their_span() {
+    let src = "p && !(x < 1)";
+    let b = parse_bexpr(src).unwrap();
+    let BExprKind::Logic(l, _, r) = &b.kind else {
+        panic!("expected a conjunction, got {b}");
+    };
+    assert!(matches!(l.kind, BExprKind::Reference(_)));
+    assert_eq!(slice(src, l.span), "p");
+    assert_eq!(slice(src, r.span), "!(x < 1)");
+}
+
+#[test]
+fn equality_ignores_spans() {
+    let compact = parse_commands("x:=1+2;if x>0->y:=x fi").unwrap();
+    let spaced = parse_commands("x := 1 + 2 ;\nif x > 0 ->\n  y := x\nfi").unwrap();
+    assert_eq!(compact, spaced);
+
+    let compact = ProgramGraph::new(Determinism::Deterministic, &compact);
+    let spaced = ProgramGraph::new(Determinism::Deterministic, &spaced);
+    assert_ne!(
+        compact.edges().iter().map(|e| e.span()).collect::<Vec<_>>(),
+        spaced.edges().iter().map(|e| e.span()).collect::<Vec<_>>(),
+    );
+    assert_eq!(compact.edges(), spaced.edges());
+    let hashed: HashSet<_> = compact.edges().iter().collect();
+    assert!(spaced.edges().iter().all(|e| hashed.contains(e)));
+}
+
+#[test]
+fn steps_span_their_command() {
+    let src = "x := 1 ;\nif x > 0 -> y := 2 fi";
+    let pg = ProgramGraph::new(Determinism::Deterministic, &parse_commands(src).unwrap());
+    let mut memory = InterpreterMemory::default();
+    for x in ["x", "y"] {
+        memory
+            .variables
+            .insert(Variable(x.to_string()), Integer::from(0));
+    }
+    let mut exe = Execution::new(memory);
+    let mut spans = vec![];
+    while let Some(next) = exe.nexts(&pg).first().cloned() {
+        spans.push(next.trace().last().unwrap().0.span);
+        exe = next;
+    }
+    let spans: Vec<_> = spans.into_iter().map(|span| slice(src, span)).collect();
+    assert_eq!(spans, ["x := 1", "x > 0", "y := 2"]);
+}
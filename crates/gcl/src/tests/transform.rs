@@ -0,0 +1,18 @@
+use crate::{ast::CommandKind, parse::parse_commands};
+
+#[test]
+fn empty_conditional_is_kept() {
+    let cmds = parse_commands("x := 1 ; if fi").unwrap();
+    let eliminated = cmds.eliminate_dead_guards();
+    assert_eq!(eliminated.0.len(), 2);
+    assert!(matches!(&eliminated.0[1].kind, CommandKind::If(guards) if guards.is_empty()));
+}
+
+#[test]
+fn dead_conditional_is_stuck() {
+    let cmds = parse_commands("if false -> x := 1 [] false -> skip fi").unwrap();
+    assert_eq!(
+        cmds.eliminate_dead_guards().to_string(),
+        parse_commands("if false -> skip fi").unwrap().to_string()
+    );
+}
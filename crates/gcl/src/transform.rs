@@ -0,0 +1,577 @@
+//! Transformations of programs which preserve their semantics: constant
+//! folding, algebraic simplification, elimination of dead guards and copy
+//! propagation.
+//!
+//! The transformations assume [`IntegerSemantics::Checked`] integers, under
+//! which folding `200 / 3` to `66` is sound. They keep every assignment and
+//! every expression which may fail, such that an execution of the transformed
+//! program passes through the same memories, and gets stuck in the same way,
+//! in fewer steps.
+//!
+//! [`IntegerSemantics::Checked`]: crate::integer::IntegerSemantics::Checked
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ast::{
+    AExpr, AExprKind, AOp, BExpr, BExprKind, Call, Command, CommandKind, Commands, Guard, LogicOp,
+    Procedure, RelOp, Target, Variable,
+};
+
+impl Commands {
+    /// Applies [`Commands::propagate_copies`], [`Commands::fold_constants`] and
+    /// [`Commands::eliminate_dead_guards`] until the program no longer
+    /// changes.
+    pub fn optimize(&self) -> Commands {
+        let mut current = self.clone();
+        loop {
+            let next = current
+                .propagate_copies()
+                .fold_constants()
+                .eliminate_dead_guards();
+            if next == current {
+                return current;
+            }
+            current = next;
+        }
+    }
+
+    /// Folds every expression of the program with [`AExpr::fold`] and
+    /// [`BExpr::fold`].
+    pub fn fold_constants(&self) -> Commands {
+        Commands(self.0.iter().map(Command::fold_constants).collect())
+    }
+
+    /// Removes guards which are `false`, inlines conditionals with a single
+    /// `true` guard, replaces loops without guards by `skip`, and removes
+    /// `skip` from sequences of other commands.
+    ///
+    /// A conditional whose guards are all `false` is stuck, and is kept as
+    /// `if false -> skip fi`.
+    pub fn eliminate_dead_guards(&self) -> Commands {
+        let mut cmds = vec![];
+        for cmd in &self.0 {
+            cmd.eliminate_dead_guards(&mut cmds);
+        }
+
+        let first_skip = cmds.iter().find(|c| c.kind == CommandKind::Skip).cloned();
+        cmds.retain(|c| c.kind != CommandKind::Skip);
+        if cmds
+            .iter()
+            .all(|c| matches!(c.kind, CommandKind::Procedure(_)))
+        {
+            cmds.extend(first_skip);
+        }
+        Commands(cmds)
+    }
+
+    /// Replaces references to variables which are known to hold a constant or
+    /// the value of another variable, as established by assignments like
+    /// `x := 2` and `x := y`.
+    pub fn propagate_copies(&self) -> Commands {
+        self.propagate(&mut Facts::new())
+    }
+
+    /// The number of commands, guards and expression nodes of the program.
+    pub fn size(&self) -> usize {
+        self.0.iter().map(Command::size).sum()
+    }
+}
+
+impl Command {
+    fn fold_constants(&self) -> Command {
+        let kind = match &self.kind {
+            CommandKind::Assignment(t, a) => CommandKind::Assignment(t.fold(), a.fold()),
+            CommandKind::BoolAssignment(t, b) => CommandKind::BoolAssignment(t.fold(), b.fold()),
+            CommandKind::Skip => CommandKind::Skip,
+            CommandKind::If(guards) => CommandKind::If(fold_guards(guards)),
+            CommandKind::Loop(guards) => CommandKind::Loop(fold_guards(guards)),
+            CommandKind::Procedure(p) => CommandKind::Procedure(Procedure {
+                body: p.body.fold_constants(),
+                ..p.clone()
+            }),
+            CommandKind::Call(call) => CommandKind::Call(Call {
+                name: call.name.clone(),
+                args: call.args.iter().map(AExpr::fold).collect(),
+                results: call.results.iter().map(Target::fold).collect(),
+            }),
+            CommandKind::Par(components) => {
+                CommandKind::Par(components.iter().map(Commands::fold_constants).collect())
+            }
+        };
+        Command {
+            kind,
+            span: self.span,
+        }
+    }
+
+    fn eliminate_dead_guards(&self, out: &mut Vec<Command>) {
+        let kind = match &self.kind {
+            CommandKind::If(guards) => {
+                let live = live_guards(guards);
+                match (live.as_slice(), guards.first()) {
+                    // NOTE: `if fi` has no guards and is stuck as it is
+                    ([], None) => CommandKind::If(vec![]),
+                    ([], Some(first)) => {
+                        let mut stuck = first.clone();
+                        let span = stuck.cmds.0.first().map_or(stuck.guard_span, |c| c.span);
+                        stuck.cmds = Commands(vec![Command {
+                            kind: CommandKind::Skip,
+                            span,
+                        }]);
+                        CommandKind::If(vec![stuck])
+                    }
+                    ([g], _) if g.guard.kind == BExprKind::Bool(true) => {
+                        out.extend(g.cmds.0.iter().cloned());
+                        return;
+                    }
+                    (_, _) => CommandKind::If(live),
+                }
+            }
+            CommandKind::Loop(guards) => {
+                let live = live_guards(guards);
+                if live.is_empty() {
+                    CommandKind::Skip
+                } else {
+                    CommandKind::Loop(live)
+                }
+            }
+            CommandKind::Procedure(p) => CommandKind::Procedure(Procedure {
+                body: p.body.eliminate_dead_guards(),
+                ..p.clone()
+            }),
+            CommandKind::Par(components) => CommandKind::Par(
+                components
+                    .iter()
+                    .map(Commands::eliminate_dead_guards)
+                    .collect(),
+            ),
+            kind => kind.clone(),
+        };
+        out.push(Command {
+            kind,
+            span: self.span,
+        });
+    }
+
+    fn size(&self) -> usize {
+        1 + match &self.kind {
+            CommandKind::Assignment(t, a) => t.size() + a.size(),
+            CommandKind::BoolAssignment(t, b) => t.size() + b.size(),
+            CommandKind::Skip => 0,
+            CommandKind::If(guards) | CommandKind::Loop(guards) => guards
+                .iter()
+                .map(|g| 1 + g.guard.size() + g.cmds.size())
+                .sum(),
+            CommandKind::Procedure(p) => p.body.size(),
+            CommandKind::Call(call) => {
+                call.args.iter().map(AExpr::size).sum::<usize>()
+                    + call.results.iter().map(Target::size).sum::<usize>()
+            }
+            CommandKind::Par(components) => components.iter().map(Commands::size).sum(),
+        }
+    }
+}
+
+fn fold_guards(guards: &[Guard]) -> Vec<Guard> {
+    guards
+        .iter()
+        .map(|g| Guard {
+            guard_span: g.guard_span,
+            guard: g.guard.fold(),
+            cmds: g.cmds.fold_constants(),
+        })
+        .collect()
+}
+
+fn live_guards(guards: &[Guard]) -> Vec<Guard> {
+    guards
+        .iter()
+        .filter(|g| g.guard.kind != BExprKind::Bool(false))
+        .map(|g| Guard {
+            cmds: g.cmds.eliminate_dead_guards(),
+            ..g.clone()
+        })
+        .collect()
+}
+
+impl Target<Box<AExpr>> {
+    fn fold(&self) -> Self {
+        self.clone().map_idx(|idx| Box::new(idx.fold()))
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Target::Variable(_) => 1,
+            Target::Array(_, idx) => 1 + idx.size(),
+        }
+    }
+}
+
+impl AExpr {
+    /// Evaluates constant subexpressions and applies algebraic identities
+    /// like `x + 0 = x`, bottom-up.
+    ///
+    /// Subexpressions whose evaluation fails, like `1 / 0`, are kept, and
+    /// identities like `x * 0 = 0` are only applied if `x` cannot fail.
+    pub fn fold(&self) -> AExpr {
+        let number = |n| AExpr::number(n).with_span(self.span);
+        match &self.kind {
+            AExprKind::Number(_) => self.clone(),
+            AExprKind::Reference(t) => AExpr::reference(t.fold()).with_span(self.span),
+            AExprKind::Minus(e) => match e.fold() {
+                AExpr {
+                    kind: AExprKind::Number(n),
+                    ..
+                } if n.checked_neg().is_some() => number(-n),
+                e => AExpr::minus(e).with_span(self.span),
+            },
+            AExprKind::Declassify(e, c) => {
+                AExpr::declassify(e.fold(), c.clone()).with_span(self.span)
+            }
+            AExprKind::Binary(l, op, r) => {
+                let (l, r) = (l.fold(), r.fold());
+                let folded = match (&l.kind, &r.kind) {
+                    (AExprKind::Number(a), AExprKind::Number(b)) => op.semantic(*a, *b).ok(),
+                    _ => None,
+                };
+                if let Some(n) = folded {
+                    return number(n);
+                }
+                match (&l.kind, *op, &r.kind) {
+                    (_, AOp::Plus | AOp::Minus, AExprKind::Number(0))
+                    | (_, AOp::Times | AOp::Divide | AOp::Pow, AExprKind::Number(1)) => l,
+                    (AExprKind::Number(0), AOp::Plus, _)
+                    | (AExprKind::Number(1), AOp::Times, _) => r,
+                    (AExprKind::Number(0), AOp::Minus, _) => AExpr::minus(r).with_span(self.span),
+                    (_, AOp::Times, AExprKind::Number(0)) if l.is_total() => number(0),
+                    (AExprKind::Number(0), AOp::Times, _) if r.is_total() => number(0),
+                    (_, AOp::Pow, AExprKind::Number(0)) if l.is_total() => number(1),
+                    (_, AOp::Minus, _) if l == r && l.is_total() => number(0),
+                    (_, op, _) => AExpr::binary(l, op, r).with_span(self.span),
+                }
+            }
+        }
+    }
+
+    /// Whether evaluating the expression cannot fail, given that its
+    /// variables are defined.
+    fn is_total(&self) -> bool {
+        matches!(
+            self.kind,
+            AExprKind::Number(_) | AExprKind::Reference(Target::Variable(_))
+        )
+    }
+
+    fn size(&self) -> usize {
+        match &self.kind {
+            AExprKind::Number(_) => 1,
+            AExprKind::Reference(t) => t.size(),
+            AExprKind::Binary(l, _, r) => 1 + l.size() + r.size(),
+            AExprKind::Minus(e) | AExprKind::Declassify(e, _) => 1 + e.size(),
+        }
+    }
+}
+
+impl BExpr {
+    /// Evaluates constant subexpressions and applies logical identities like
+    /// `b && true = b`, bottom-up.
+    ///
+    /// As with [`AExpr::fold`], subexpressions which may fail are only
+    /// removed if they would not have been evaluated.
+    pub fn fold(&self) -> BExpr {
+        let bool = |b| BExpr::bool(b).with_span(self.span);
+        match &self.kind {
+            BExprKind::Bool(_) => self.clone(),
+            BExprKind::Reference(t) => BExpr::reference(t.fold()).with_span(self.span),
+            BExprKind::Rel(l, op, r) => {
+                let (l, r) = (l.fold(), r.fold());
+                match (&l.kind, &r.kind) {
+                    (AExprKind::Number(a), AExprKind::Number(b)) => bool(op.semantic(a, b)),
+                    _ if l == r && l.is_total() => {
+                        bool(matches!(op, RelOp::Eq | RelOp::Ge | RelOp::Le))
+                    }
+                    _ => BExpr::rel(l, *op, r).with_span(self.span),
+                }
+            }
+            BExprKind::Not(b) => {
+                let b = b.fold();
+                match b.kind {
+                    BExprKind::Bool(b) => bool(!b),
+                    BExprKind::Not(b) => *b,
+                    _ => (!b).with_span(self.span),
+                }
+            }
+            BExprKind::Logic(l, op, r) => {
+                let (l, r) = (l.fold(), r.fold());
+                match (&l.kind, *op, &r.kind) {
+                    (BExprKind::Bool(true), LogicOp::And | LogicOp::Land, _)
+                    | (BExprKind::Bool(false), LogicOp::Or | LogicOp::Lor, _) => r,
+                    (_, LogicOp::And | LogicOp::Land, BExprKind::Bool(true))
+                    | (_, LogicOp::Or | LogicOp::Lor, BExprKind::Bool(false)) => l,
+                    // NOTE: these short-circuit before evaluating the right operand
+                    (BExprKind::Bool(false), LogicOp::And, _) => bool(false),
+                    (BExprKind::Bool(true), LogicOp::Or, _) => bool(true),
+                    (_, LogicOp::And | LogicOp::Land, BExprKind::Bool(false)) if l.is_total() => {
+                        bool(false)
+                    }
+                    (BExprKind::Bool(false), LogicOp::Land, _) if r.is_total() => bool(false),
+                    (_, LogicOp::Or | LogicOp::Lor, BExprKind::Bool(true)) if l.is_total() => {
+                        bool(true)
+                    }
+                    (BExprKind::Bool(true), LogicOp::Lor, _) if r.is_total() => bool(true),
+                    (_, op, _) => BExpr::logic(l, op, r).with_span(self.span),
+                }
+            }
+        }
+    }
+
+    fn is_total(&self) -> bool {
+        match &self.kind {
+            BExprKind::Bool(_) | BExprKind::Reference(Target::Variable(_)) => true,
+            BExprKind::Reference(Target::Array(_, _)) => false,
+            BExprKind::Rel(l, _, r) => l.is_total() && r.is_total(),
+            BExprKind::Logic(l, _, r) => l.is_total() && r.is_total(),
+            BExprKind::Not(b) => b.is_total(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match &self.kind {
+            BExprKind::Bool(_) => 1,
+            BExprKind::Reference(t) => t.size(),
+            BExprKind::Rel(l, _, r) => 1 + l.size() + r.size(),
+            BExprKind::Logic(l, _, r) => 1 + l.size() + r.size(),
+            BExprKind::Not(b) => 1 + b.size(),
+        }
+    }
+}
+
+// Copy propagation
+
+/// The value a variable is known to hold: a constant or another variable.
+#[derive(Debug, Clone, PartialEq)]
+enum Known {
+    Int(AExpr),
+    Bool(BExpr),
+}
+
+type Facts = BTreeMap<Variable, Known>;
+
+/// Forgets what is known about `x`, and what is known in terms of `x`.
+fn kill(facts: &mut Facts, x: &Variable) {
+    facts.remove(x);
+    facts.retain(|_, known| match known {
+        Known::Int(AExpr {
+            kind: AExprKind::Reference(Target::Variable(y)),
+            ..
+        })
+        | Known::Bool(BExpr {
+            kind: BExprKind::Reference(Target::Variable(y)),
+            ..
+        }) => y != x,
+        _ => true,
+    });
+}
+
+impl Commands {
+    fn propagate(&self, facts: &mut Facts) -> Commands {
+        Commands(self.0.iter().map(|c| c.propagate(facts)).collect())
+    }
+
+    /// Collects the variables assigned by the commands into `assigned`.
+    /// Returns whether the commands contain a call, which may assign any
+    /// variable.
+    fn assigned(&self, assigned: &mut BTreeSet<Variable>) -> bool {
+        let mut calls = false;
+        for cmd in &self.0 {
+            match &cmd.kind {
+                CommandKind::Assignment(Target::Variable(x), _)
+                | CommandKind::BoolAssignment(Target::Variable(x), _) => {
+                    assigned.insert(x.clone());
+                }
+                CommandKind::If(guards) | CommandKind::Loop(guards) => {
+                    for g in guards {
+                        calls |= g.cmds.assigned(assigned);
+                    }
+                }
+                CommandKind::Call(_) => calls = true,
+                CommandKind::Par(components) => {
+                    for c in components {
+                        calls |= c.assigned(assigned);
+                    }
+                }
+                _ => {}
+            }
+        }
+        calls
+    }
+}
+
+impl Command {
+    fn propagate(&self, facts: &mut Facts) -> Command {
+        let kind = match &self.kind {
+            CommandKind::Assignment(t, a) => {
+                let (t, a) = (t.propagate(facts), a.propagate(facts));
+                if let Target::Variable(x) = &t {
+                    kill(facts, x);
+                    match &a.kind {
+                        AExprKind::Number(_) => {
+                            facts.insert(x.clone(), Known::Int(a.clone()));
+                        }
+                        AExprKind::Reference(Target::Variable(y)) if y != x => {
+                            facts.insert(x.clone(), Known::Int(a.clone()));
+                        }
+                        _ => {}
+                    }
+                }
+                CommandKind::Assignment(t, a)
+            }
+            CommandKind::BoolAssignment(t, b) => {
+                let (t, b) = (t.propagate(facts), b.propagate(facts));
+                if let Target::Variable(x) = &t {
+                    kill(facts, x);
+                    match &b.kind {
+                        BExprKind::Bool(_) => {
+                            facts.insert(x.clone(), Known::Bool(b.clone()));
+                        }
+                        BExprKind::Reference(Target::Variable(y)) if y != x => {
+                            facts.insert(x.clone(), Known::Bool(b.clone()));
+                        }
+                        _ => {}
+                    }
+                }
+                CommandKind::BoolAssignment(t, b)
+            }
+            CommandKind::Skip => CommandKind::Skip,
+            CommandKind::If(guards) => {
+                let mut after: Option<Facts> = None;
+                let guards = guards
+                    .iter()
+                    .map(|g| {
+                        let mut branch = facts.clone();
+                        let g = Guard {
+                            guard_span: g.guard_span,
+                            guard: g.guard.propagate(facts),
+                            cmds: g.cmds.propagate(&mut branch),
+                        };
+                        after = Some(match after.take() {
+                            None => branch,
+                            Some(mut after) => {
+                                after.retain(|x, known| branch.get(x) == Some(known));
+                                after
+                            }
+                        });
+                        g
+                    })
+                    .collect();
+                if let Some(after) = after {
+                    *facts = after;
+                }
+                CommandKind::If(guards)
+            }
+            CommandKind::Loop(guards) => {
+                // NOTE: only facts which hold on every iteration are used
+                let mut assigned = BTreeSet::new();
+                let mut calls = false;
+                for g in guards {
+                    calls |= g.cmds.assigned(&mut assigned);
+                }
+                if calls {
+                    facts.clear();
+                }
+                for x in &assigned {
+                    kill(facts, x);
+                }
+                CommandKind::Loop(
+                    guards
+                        .iter()
+                        .map(|g| Guard {
+                            guard_span: g.guard_span,
+                            guard: g.guard.propagate(facts),
+                            cmds: g.cmds.propagate(&mut facts.clone()),
+                        })
+                        .collect(),
+                )
+            }
+            CommandKind::Procedure(p) => CommandKind::Procedure(Procedure {
+                body: p.body.propagate(&mut Facts::new()),
+                ..p.clone()
+            }),
+            CommandKind::Call(call) => {
+                let call = Call {
+                    name: call.name.clone(),
+                    args: call.args.iter().map(|a| a.propagate(facts)).collect(),
+                    results: call.results.iter().map(|t| t.propagate(facts)).collect(),
+                };
+                facts.clear();
+                CommandKind::Call(call)
+            }
+            // NOTE: the components may interleave between any two of their
+            // steps, so nothing is known during or after them
+            CommandKind::Par(components) => {
+                facts.clear();
+                CommandKind::Par(components.clone())
+            }
+        };
+        Command {
+            kind,
+            span: self.span,
+        }
+    }
+}
+
+impl Target<Box<AExpr>> {
+    fn propagate(&self, facts: &Facts) -> Self {
+        self.clone().map_idx(|idx| Box::new(idx.propagate(facts)))
+    }
+}
+
+impl AExpr {
+    fn propagate(&self, facts: &Facts) -> AExpr {
+        let kind = match &self.kind {
+            AExprKind::Number(n) => AExprKind::Number(*n),
+            AExprKind::Reference(Target::Variable(x)) => match facts.get(x) {
+                Some(Known::Int(a)) => a.kind.clone(),
+                _ => return self.clone(),
+            },
+            AExprKind::Reference(t) => AExprKind::Reference(t.propagate(facts)),
+            AExprKind::Binary(l, op, r) => AExprKind::Binary(
+                Box::new(l.propagate(facts)),
+                *op,
+                Box::new(r.propagate(facts)),
+            ),
+            AExprKind::Minus(e) => AExprKind::Minus(Box::new(e.propagate(facts))),
+            AExprKind::Declassify(e, c) => {
+                AExprKind::Declassify(Box::new(e.propagate(facts)), c.clone())
+            }
+        };
+        AExpr {
+            kind,
+            span: self.span,
+        }
+    }
+}
+
+impl BExpr {
+    fn propagate(&self, facts: &Facts) -> BExpr {
+        let kind = match &self.kind {
+            BExprKind::Bool(b) => BExprKind::Bool(*b),
+            BExprKind::Reference(Target::Variable(x)) => match facts.get(x) {
+                Some(Known::Bool(b)) => b.kind.clone(),
+                _ => return self.clone(),
+            },
+            BExprKind::Reference(t) => BExprKind::Reference(t.propagate(facts)),
+            BExprKind::Rel(l, op, r) => BExprKind::Rel(l.propagate(facts), *op, r.propagate(facts)),
+            BExprKind::Logic(l, op, r) => BExprKind::Logic(
+                Box::new(l.propagate(facts)),
+                *op,
+                Box::new(r.propagate(facts)),
+            ),
+            BExprKind::Not(b) => BExprKind::Not(Box::new(b.propagate(facts))),
+        };
+        BExpr {
+            kind,
+            span: self.span,
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Inference of whether variables and arrays hold integers or booleans.
+//!
+//! Programs do not declare their variables, so the type of a name is inferred
+//! from its uses: references in arithmetic are integers, references used as
+//! conditions are booleans, and an assignment gives its target the type of the
+//! right-hand side. Names not constrained by any use, such as `x` and `y` in
+//! `x := y`, are integers. Parameters and results of procedures are integers.
+
+use std::collections::{BTreeMap, btree_map::Entry};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{
+        AExpr, AExprKind, BExpr, BExprKind, Call, Command, CommandKind, Commands, ProcedureName,
+        Target,
+    },
+    parse::SourceSpan,
+};
+
+#[derive(
+    tapi::Tapi, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[tapi(path = "GCL")]
+pub enum Type {
+    Int,
+    Bool,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Bool => write!(f, "bool"),
+        }
+    }
+}
+
+/// A use of `target` as `found`, where it was previously used as `expected`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub target: Target,
+    pub expected: Type,
+    pub found: Type,
+    pub span: SourceSpan,
+}
+
+/// A call or declaration which does not match the declarations of the
+/// program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcedureError {
+    Undeclared {
+        name: ProcedureName,
+        span: SourceSpan,
+    },
+    Redeclared {
+        name: ProcedureName,
+        span: SourceSpan,
+    },
+    /// The number of arguments and results of a call differs from the number
+    /// of parameters and results of the procedure.
+    Arity {
+        name: ProcedureName,
+        expected: (usize, usize),
+        found: (usize, usize),
+        span: SourceSpan,
+    },
+    /// A call inside a parallel composition, whose components do not have a
+    /// stack of their own to return with.
+    Parallel {
+        name: ProcedureName,
+        span: SourceSpan,
+    },
+}
+
+#[derive(Default)]
+struct Inference {
+    types: BTreeMap<Target, Type>,
+    /// Assignments of a bare reference, whose sides have the same type.
+    copies: Vec<(Target, Target, SourceSpan)>,
+    errors: Vec<TypeError>,
+}
+
+impl Inference {
+    fn constrain(&mut self, target: Target, ty: Type, span: SourceSpan) {
+        match self.types.entry(target) {
+            Entry::Vacant(e) => {
+                e.insert(ty);
+            }
+            Entry::Occupied(e) if *e.get() != ty => self.errors.push(TypeError {
+                target: e.key().clone(),
+                expected: *e.get(),
+                found: ty,
+                span,
+            }),
+            Entry::Occupied(_) => {}
+        }
+    }
+
+    fn commands(&mut self, cmds: &Commands) {
+        for cmd in &cmds.0 {
+            self.command(cmd);
+        }
+    }
+
+    fn command(&mut self, cmd: &Command) {
+        let span = cmd.span;
+        match &cmd.kind {
+            CommandKind::Assignment(
+                t,
+                AExpr {
+                    kind: AExprKind::Reference(s),
+                    ..
+                },
+            ) => {
+                self.index(t, span);
+                self.index(s, span);
+                self.copies.push((t.clone().unit(), s.clone().unit(), span));
+            }
+            CommandKind::Assignment(t, a) => {
+                self.index(t, span);
+                self.constrain(t.clone().unit(), Type::Int, span);
+                self.aexpr(a, span);
+            }
+            CommandKind::BoolAssignment(t, b) => {
+                self.index(t, span);
+                self.constrain(t.clone().unit(), Type::Bool, span);
+                self.bexpr(b, span);
+            }
+            CommandKind::Skip => {}
+            CommandKind::If(guards) | CommandKind::Loop(guards) => {
+                for g in guards {
+                    self.bexpr(&g.guard, g.guard_span);
+                    self.commands(&g.cmds);
+                }
+            }
+            CommandKind::Procedure(p) => {
+                for v in p.params.iter().chain(&p.results) {
+                    self.constrain(Target::Variable(v.clone()), Type::Int, span);
+                }
+                self.commands(&p.body);
+            }
+            CommandKind::Call(call) => {
+                for a in &call.args {
+                    self.aexpr(a, span);
+                }
+                for t in &call.results {
+                    self.index(t, span);
+                    self.constrain(t.clone().unit(), Type::Int, span);
+                }
+            }
+            CommandKind::Par(components) => {
+                for c in components {
+                    self.commands(c);
+                }
+            }
+        }
+    }
+
+    fn index(&mut self, t: &Target<Box<AExpr>>, span: SourceSpan) {
+        if let Target::Array(_, idx) = t {
+            self.aexpr(idx, span);
+        }
+    }
+
+    fn aexpr(&mut self, a: &AExpr, span: SourceSpan) {
+        match &a.kind {
+            AExprKind::Number(_) => {}
+            AExprKind::Reference(t) => {
+                self.index(t, span);
+                self.constrain(t.clone().unit(), Type::Int, span);
+            }
+            AExprKind::Binary(l, _, r) => {
+                self.aexpr(l, span);
+                self.aexpr(r, span);
+            }
+            AExprKind::Minus(a) | AExprKind::Declassify(a, _) => self.aexpr(a, span),
+        }
+    }
+
+    fn bexpr(&mut self, b: &BExpr, span: SourceSpan) {
+        match &b.kind {
+            BExprKind::Bool(_) => {}
+            BExprKind::Reference(t) => {
+                self.index(t, span);
+                self.constrain(t.clone().unit(), Type::Bool, span);
+            }
+            BExprKind::Rel(l, _, r) => {
+                self.aexpr(l, span);
+                self.aexpr(r, span);
+            }
+            BExprKind::Logic(l, _, r) => {
+                self.bexpr(l, span);
+                self.bexpr(r, span);
+            }
+            BExprKind::Not(b) => self.bexpr(b, span),
+        }
+    }
+
+    /// Propagates types along copies until nothing changes.
+    fn solve(&mut self) {
+        loop {
+            let mut changed = false;
+            for (t, s, _) in &self.copies {
+                match (self.types.get(t).copied(), self.types.get(s).copied()) {
+                    (Some(ty), None) => {
+                        self.types.insert(s.clone(), ty);
+                        changed = true;
+                    }
+                    (None, Some(ty)) => {
+                        self.types.insert(t.clone(), ty);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (t, s, span) in &self.copies {
+            match (self.types.get(t), self.types.get(s)) {
+                (Some(expected), Some(found)) if expected != found => {
+                    self.errors.push(TypeError {
+                        target: s.clone(),
+                        expected: *found,
+                        found: *expected,
+                        span: *span,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Commands {
+    fn infer(&self) -> Inference {
+        let mut inference = Inference::default();
+        inference.commands(self);
+        inference.solve();
+        inference
+    }
+
+    /// The inferred type of every variable and array in the program.
+    pub fn types(&self) -> BTreeMap<Target, Type> {
+        let types = self.infer().types;
+        self.fv()
+            .into_iter()
+            .map(|t| {
+                let ty = types.get(&t).copied().unwrap_or(Type::Int);
+                (t, ty)
+            })
+            .collect()
+    }
+
+    /// Checks that every call is of a procedure declared once, with the
+    /// declared number of arguments and results.
+    pub(crate) fn check_procedures(&self) -> Result<(), Vec<ProcedureError>> {
+        let mut errors = vec![];
+
+        let mut declared = BTreeMap::new();
+        for cmd in &self.0 {
+            let CommandKind::Procedure(p) = &cmd.kind else {
+                continue;
+            };
+            if declared.insert(&p.name, p).is_some() {
+                errors.push(ProcedureError::Redeclared {
+                    name: p.name.clone(),
+                    span: cmd.span,
+                });
+            }
+        }
+
+        let mut calls = vec![];
+        self.calls(&mut calls);
+        for (call, span, parallel) in calls {
+            if parallel {
+                errors.push(ProcedureError::Parallel {
+                    name: call.name.clone(),
+                    span,
+                });
+            }
+            match declared.get(&call.name) {
+                None => errors.push(ProcedureError::Undeclared {
+                    name: call.name.clone(),
+                    span,
+                }),
+                Some(p)
+                    if (p.params.len(), p.results.len())
+                        != (call.args.len(), call.results.len()) =>
+                {
+                    errors.push(ProcedureError::Arity {
+                        name: call.name.clone(),
+                        expected: (p.params.len(), p.results.len()),
+                        found: (call.args.len(), call.results.len()),
+                        span,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Collects every call, and whether it is inside a parallel composition.
+    fn calls<'a>(&'a self, calls: &mut Vec<(&'a Call, SourceSpan, bool)>) {
+        for cmd in &self.0 {
+            match &cmd.kind {
+                CommandKind::Call(call) => calls.push((call, cmd.span, false)),
+                CommandKind::If(guards) | CommandKind::Loop(guards) => {
+                    for g in guards {
+                        g.cmds.calls(calls);
+                    }
+                }
+                CommandKind::Procedure(p) => p.body.calls(calls),
+                CommandKind::Par(components) => {
+                    let start = calls.len();
+                    for c in components {
+                        c.calls(calls);
+                    }
+                    for (_, _, parallel) in &mut calls[start..] {
+                        *parallel = true;
+                    }
+                }
+                CommandKind::Assignment(_, _)
+                | CommandKind::BoolAssignment(_, _)
+                | CommandKind::Skip => {}
+            }
+        }
+    }
+
+    /// Checks that every name is used with a single type, and turns
+    /// assignments of bare boolean references into boolean assignments.
+    pub(crate) fn check_types(mut self) -> Result<Commands, Vec<TypeError>> {
+        let inference = self.infer();
+        if !inference.errors.is_empty() {
+            return Err(inference.errors);
+        }
+        self.resolve_copies(&inference.types);
+        Ok(self)
+    }
+
+    fn resolve_copies(&mut self, types: &BTreeMap<Target, Type>) {
+        for cmd in &mut self.0 {
+            match &mut cmd.kind {
+                CommandKind::Assignment(
+                    t,
+                    AExpr {
+                        kind: AExprKind::Reference(s),
+                        span,
+                    },
+                ) if types.get(&t.clone().unit()) == Some(&Type::Bool) => {
+                    let b = BExpr::reference(s.clone()).with_span(*span);
+                    cmd.kind = CommandKind::BoolAssignment(t.clone(), b);
+                }
+                CommandKind::If(guards) | CommandKind::Loop(guards) => {
+                    for g in guards {
+                        g.cmds.resolve_copies(types);
+                    }
+                }
+                CommandKind::Procedure(p) => p.body.resolve_copies(types),
+                CommandKind::Par(components) => {
+                    for c in components {
+                        c.resolve_copies(types);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 mod compression;
 pub mod config;
 mod db;
+mod notify;
 pub mod scoreboard;
 
 use std::{
@@ -12,7 +13,10 @@ use std::{
 
 use ce_core::ValidationResult;
 use ce_shell::{Analysis, Input};
-use color_eyre::{Result, eyre::Context};
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
 use driver::{Driver, Hub, Job, JobState};
 use futures_util::{StreamExt, TryStreamExt};
 use indexmap::IndexMap;
@@ -29,7 +33,10 @@ pub struct Checko {
     db: db::CheckoDb,
     groups_config: config::GroupsConfig,
     programs_config: config::ProgramsConfig,
+    notifier: notify::Notifier,
     last_finished: std::sync::Mutex<Option<chrono::DateTime<chrono::FixedOffset>>>,
+    paused: tokio::sync::watch::Sender<bool>,
+    wakeup: tokio::sync::Notify,
     group_states: tokio::sync::Mutex<IndexMap<(GroupName, Analysis), GroupState2>>,
 }
 
@@ -53,6 +60,7 @@ pub struct GroupState2Inner {
     latest_hash: Option<String>,
     status: GroupStatus,
     results: BTreeMap<ce_shell::Hash, JobState>,
+    rerun_requested: bool,
 }
 
 #[derive(
@@ -97,6 +105,15 @@ impl GroupState2 {
     pub async fn set_result(&self, hash: ce_shell::Hash, state: JobState) {
         self.inner.write().await.results.insert(hash, state);
     }
+
+    pub async fn request_rerun(&self) {
+        self.inner.write().await.rerun_requested = true;
+    }
+
+    /// Returns `true` if a rerun was requested since the last call.
+    pub async fn take_rerun_request(&self) -> bool {
+        std::mem::take(&mut self.inner.write().await.rerun_requested)
+    }
 }
 
 #[derive(Clone)]
@@ -139,7 +156,6 @@ impl GroupToTest {
             db.insert_cached_run(&key, &data)?;
         }
 
-        self.state.set_status(GroupStatus::Finished).await;
         self.state.set_result(input.hash(), state).await;
 
         Ok(())
@@ -154,6 +170,7 @@ impl Checko {
         tracing::debug!(?path, "opening checko");
 
         let runs_db_path = path.join("runs.db3");
+        let notifications_path = path.join("notifications.toml");
         let groups_path = dunce::canonicalize(path.join("groups.toml"))
             .wrap_err_with(|| format!("missing groups.toml at '{}'", path.display()))?;
         let programs_path = dunce::canonicalize(path.join("programs.toml"))
@@ -164,14 +181,18 @@ impl Checko {
         let db = db::CheckoDb::open(&runs_db_path).wrap_err("could not open db")?;
         let groups = config::read_groups(groups_path)?;
         let programs = config::read_programs(programs_path)?;
+        let notifications = config::read_notifications(notifications_path)?;
 
         Ok(Self {
             hub,
+            notifier: notify::Notifier::new(&path, notifications),
             path,
             db,
             groups_config: groups,
             programs_config: programs,
             last_finished: Default::default(),
+            paused: tokio::sync::watch::Sender::new(false),
+            wakeup: Default::default(),
             group_states: Default::default(),
         })
     }
@@ -184,6 +205,54 @@ impl Checko {
         self.programs_config.canonicalize().unwrap()
     }
 
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Pauses or resumes testing. A batch already in progress is finished
+    /// before the loop pauses.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    /// The configured groups which are currently excluded from testing.
+    pub fn excluded_groups(&self) -> Result<Vec<GroupName>> {
+        let excluded = self.db.excluded_groups()?;
+        Ok(self
+            .groups_config
+            .groups
+            .iter()
+            .filter(|g| excluded.iter().any(|e| e == g.name.as_str()))
+            .map(|g| g.name.clone())
+            .collect())
+    }
+
+    /// Excludes or includes the group in future runs. The exclusion is stored
+    /// in the database, and thus survives restarts.
+    pub fn set_excluded(&self, group: &GroupName, excluded: bool) -> Result<()> {
+        self.group_config(group)?;
+        self.db.set_excluded(group, excluded)
+    }
+
+    /// Schedules the group to be tested again for the analysis, ignoring any
+    /// cached results, and starts the next batch immediately unless paused.
+    pub async fn request_rerun(&self, group: &GroupName, analysis: Analysis) -> Result<()> {
+        let g = self.group_config(group)?;
+        if !self.programs_config.envs.contains_key(&analysis) {
+            bail!("no programs are configured for {analysis}");
+        }
+        self.group_state(&g, analysis).await.request_rerun().await;
+        self.wakeup.notify_one();
+        Ok(())
+    }
+
+    fn group_config(&self, group: &GroupName) -> Result<Arc<GroupConfig>> {
+        match self.groups_config.groups.iter().find(|g| g.name == *group) {
+            Some(g) => Ok(Arc::clone(g)),
+            None => bail!("unknown group '{group}'"),
+        }
+    }
+
     fn group_path(&self, config: &GroupConfig, analysis: Analysis) -> PathBuf {
         self.path
             .join("groups")
@@ -208,6 +277,50 @@ impl Checko {
             .collect_vec()
     }
 
+    /// Marks the group as failing compilation, notifying hooks if it was not
+    /// already failing.
+    async fn set_compilation_error(&self, g: &GroupConfig, a: Analysis, gs: &GroupState2) {
+        let prev = gs.set_status(GroupStatus::CompilationError).await;
+        if prev != GroupStatus::CompilationError {
+            self.notifier.notify(notify::Notification::new(
+                g.name.clone(),
+                a,
+                gs.latest_hash().await,
+                notify::NotificationEvent::CompilationError,
+            ));
+        }
+    }
+
+    /// Marks the group as finished with the results for `inputs`. Hooks are
+    /// notified of the results and of any drop in pass rate since the
+    /// previous hash, but only if the results differ from the previous run,
+    /// such that results loaded from the cache after a restart are not
+    /// reported again.
+    async fn set_finished(&self, g: &GroupConfig, a: Analysis, gs: &GroupState2, inputs: &[Input]) {
+        gs.set_status(GroupStatus::Finished).await;
+
+        let results = gs.results().await;
+        let summary = notify::RunSummary {
+            git_hash: gs.latest_hash().await,
+            passed: inputs
+                .iter()
+                .filter(|input| results.get(&input.hash()) == Some(&JobState::Succeeded))
+                .count(),
+            total: inputs.len(),
+        };
+        let previous = match self.db.replace_last_run(&g.name, a, &summary) {
+            Ok(previous) => previous,
+            Err(err) => {
+                tracing::error!(?g, ?err, "could not store last run");
+                return;
+            }
+        };
+        if previous.as_ref() != Some(&summary) {
+            self.notifier
+                .finished(g.name.clone(), a, &summary, previous.as_ref());
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     async fn update_group_repo(
         &self,
@@ -282,7 +395,9 @@ impl Checko {
         self: &Arc<Self>,
         analysis_inputs: &BTreeMap<Analysis, Arc<Vec<Input>>>,
     ) -> Result<Vec<GroupToTest>> {
+        let excluded = self.excluded_groups()?;
         let mut groups = self.groups_config.groups.clone();
+        groups.retain(|g| !excluded.contains(&g.name));
 
         groups.shuffle(&mut rand::rng());
 
@@ -304,8 +419,10 @@ impl Checko {
             let repo = match checko.update_group_repo(&g, a, deadline).await {
                 Ok(repo) => repo,
                 Err(err) => {
+                    // NOTE: this is most likely a network failure on our
+                    // side, and not something the group broke, so the status
+                    // is kept and hooks are not notified
                     tracing::error!(?g, ?err, "could not update group repo");
-                    gs.set_status(GroupStatus::CompilationError).await;
                     continue;
                 }
             };
@@ -313,7 +430,8 @@ impl Checko {
             compile_join_set.spawn(
                 async move {
                     let prev_status = gs.set_status(GroupStatus::CheckingForUpdate).await;
-                    if !gs.update_latest_hash(repo.git_hash.as_deref()).await {
+                    let rerun = gs.take_rerun_request().await;
+                    if !gs.update_latest_hash(repo.git_hash.as_deref()).await && !rerun {
                         gs.set_status(prev_status).await;
                         return Ok(None);
                     }
@@ -328,7 +446,10 @@ impl Checko {
                                 input,
                             }
                             .key();
-                            if let Some(job_data) = db.get_cached_run(&key)? {
+                            if rerun {
+                                db.delete_cached_run(&key)?;
+                                need_work = true;
+                            } else if let Some(job_data) = db.get_cached_run(&key)? {
                                 gs.set_result(input.hash(), job_data.state).await;
                             } else {
                                 need_work = true;
@@ -339,7 +460,7 @@ impl Checko {
                     }
 
                     if !need_work {
-                        gs.set_status(GroupStatus::Finished).await;
+                        checko.set_finished(&g, a, &gs, &inputs).await;
                         return Ok(None);
                     }
 
@@ -363,7 +484,7 @@ impl Checko {
                             })
                         }
                         Err(err) => {
-                            gs.set_status(GroupStatus::CompilationError).await;
+                            checko.set_compilation_error(&g, a, &gs).await;
                             tracing::error!(?err, "could not build group driver");
                             return Ok(None);
                         }
@@ -388,7 +509,7 @@ impl Checko {
                     if res.is_some() {
                         gs.set_status(GroupStatus::Testing).await;
                     } else {
-                        gs.set_status(GroupStatus::CompilationError).await;
+                        checko.set_compilation_error(&g, a, &gs).await;
                         for input in inputs.iter() {
                             gs.set_result(input.hash(), JobState::Failed).await;
                         }
@@ -418,7 +539,15 @@ impl Checko {
             .map(|(analysis, inputs)| (analysis, Arc::new(inputs.collect_vec())))
             .collect();
 
+        let mut paused = self.paused.subscribe();
+
         loop {
+            if *paused.borrow_and_update() {
+                tracing::info!("checko is paused");
+                paused.wait_for(|paused| !paused).await?;
+                tracing::info!("checko is resumed");
+            }
+
             let groups_to_test = self.groups_to_test(&analysis_inputs).await?;
             self.run_group_tests(groups_to_test, &analysis_inputs)
                 .await?;
@@ -427,27 +556,39 @@ impl Checko {
 
             *self.last_finished.lock().unwrap() = Some(chrono::Utc::now().fixed_offset());
             tracing::info!("waiting for next batch of runs");
-            tokio::time::sleep(Duration::from_secs(60)).await;
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+                _ = self.wakeup.notified() => {}
+            }
         }
     }
 
     async fn run_group_tests(
-        &self,
+        self: &Arc<Self>,
         groups_to_test: Vec<GroupToTest>,
         analysis_inputs: &BTreeMap<Analysis, Arc<Vec<Input>>>,
     ) -> Result<(), color_eyre::eyre::Error> {
-        let tests = groups_to_test.iter().flat_map(|gtt| {
-            analysis_inputs[&gtt.analysis]
-                .iter()
-                .cloned()
-                .map(move |input| {
+        let groups = groups_to_test.into_iter().map(|gtt| {
+            let inputs = Arc::clone(&analysis_inputs[&gtt.analysis]);
+            let checko = Arc::clone(self);
+            tokio::spawn(async move {
+                let tests = inputs.iter().cloned().map(|input| {
                     let gtt = gtt.clone();
-                    let db = self.db.clone();
+                    let db = checko.db.clone();
                     tokio::spawn(async move { gtt.test_input(&db, &input).await })
-                })
+                });
+                tokio_stream::iter(tests)
+                    .then(|test| async { test.await? })
+                    .try_collect::<()>()
+                    .await?;
+                checko
+                    .set_finished(&gtt.group, gtt.analysis, &gtt.state, &inputs)
+                    .await;
+                Ok::<_, color_eyre::eyre::Error>(())
+            })
         });
-        tokio_stream::iter(tests)
-            .then(|test| async { test.await? })
+        tokio_stream::iter(groups)
+            .then(|group| async { group.await? })
             .try_collect()
             .await
     }
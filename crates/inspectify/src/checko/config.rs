@@ -170,6 +170,33 @@ pub struct GroupConfig {
     pub commit: BTreeMap<Analysis, SmolStr>,
 }
 
+/// Hooks which are notified when the status of a group changes.
+///
+/// Read from the optional `notifications.toml` next to `groups.toml`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// URL which receives the notification as a JSON `POST` request.
+    pub url: Option<SmolStr>,
+    /// Command which is run with the notification as JSON on stdin. Relative
+    /// paths are resolved from the checko directory.
+    pub command: Option<SmolStr>,
+    /// Only notify about these groups. If empty, all groups are included.
+    #[serde(default)]
+    pub groups: Vec<GroupName>,
+}
+
+impl HookConfig {
+    pub fn includes(&self, group: &GroupName) -> bool {
+        self.groups.is_empty() || self.groups.contains(group)
+    }
+}
+
 pub fn read_programs(programs: impl AsRef<Path>) -> Result<ProgramsConfig> {
     let p = programs.as_ref();
     let src =
@@ -185,3 +212,14 @@ pub fn read_groups(groups: impl AsRef<Path>) -> Result<GroupsConfig> {
         toml::from_str(&src).wrap_err_with(|| format!("error parsing groups from file {p:?}"))?;
     Ok(parsed)
 }
+pub fn read_notifications(notifications: impl AsRef<Path>) -> Result<NotificationsConfig> {
+    let p = notifications.as_ref();
+    if !p.try_exists().unwrap_or(false) {
+        return Ok(NotificationsConfig::default());
+    }
+    let src =
+        fs::read_to_string(p).wrap_err_with(|| format!("could not read notifications at {p:?}"))?;
+    let parsed = toml::from_str(&src)
+        .wrap_err_with(|| format!("error parsing notifications from file {p:?}"))?;
+    Ok(parsed)
+}
@@ -4,11 +4,11 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use ce_shell::Input;
+use ce_shell::{Analysis, Input};
 use color_eyre::eyre::Context;
 use rusqlite::OptionalExtension;
 
-use super::{compression::Compressed, config::GroupConfig};
+use super::{compression::Compressed, config::GroupConfig, notify::RunSummary};
 use crate::endpoints::InspectifyJobMeta;
 
 #[derive(Clone)]
@@ -37,6 +37,17 @@ impl CheckoDb {
                 cache_key TEXT PRIMARY KEY,
                 data BLOB NOT NULL
             );
+            CREATE TABLE IF NOT EXISTS excluded_groups (
+                group_name TEXT PRIMARY KEY
+            );
+            CREATE TABLE IF NOT EXISTS last_runs (
+                group_name TEXT NOT NULL,
+                analysis TEXT NOT NULL,
+                git_hash TEXT,
+                passed INTEGER NOT NULL,
+                total INTEGER NOT NULL,
+                PRIMARY KEY (group_name, analysis)
+            );
             "#,
         )?;
 
@@ -86,6 +97,76 @@ impl CheckoDb {
             })?;
         Ok(())
     }
+
+    pub fn delete_cached_run(&self, key: &CacheKey) -> color_eyre::Result<()> {
+        self.conn()
+            .execute("DELETE FROM cached_runs WHERE cache_key = ?1", [&key.0])
+            .wrap_err_with(|| {
+                format!(
+                    "could not delete cached run for git_hash: {:?}, input: {:?}",
+                    key.1.git_hash, key.1.input
+                )
+            })?;
+        Ok(())
+    }
+
+    pub fn excluded_groups(&self) -> color_eyre::Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT group_name FROM excluded_groups")?;
+        let groups = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(groups)
+    }
+
+    pub fn set_excluded(&self, group_name: &str, excluded: bool) -> color_eyre::Result<()> {
+        let sql = if excluded {
+            "INSERT OR IGNORE INTO excluded_groups (group_name) VALUES (?1)"
+        } else {
+            "DELETE FROM excluded_groups WHERE group_name = ?1"
+        };
+        self.conn()
+            .execute(sql, [group_name])
+            .wrap_err_with(|| format!("could not update exclusion of group '{group_name}'"))?;
+        Ok(())
+    }
+
+    /// Records the summary of the latest run of the group for the analysis,
+    /// returning the previous one. The summary is stored in the database, so
+    /// that runs can be compared across restarts.
+    pub fn replace_last_run(
+        &self,
+        group_name: &str,
+        analysis: Analysis,
+        summary: &RunSummary,
+    ) -> color_eyre::Result<Option<RunSummary>> {
+        let conn = self.conn();
+        let previous = conn
+            .query_row(
+                "SELECT git_hash, passed, total FROM last_runs WHERE group_name = ?1 AND analysis = ?2",
+                (group_name, analysis.code()),
+                |row| {
+                    Ok(RunSummary {
+                        git_hash: row.get(0)?,
+                        passed: row.get(1)?,
+                        total: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO last_runs (group_name, analysis, git_hash, passed, total) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                group_name,
+                analysis.code(),
+                &summary.git_hash,
+                summary.passed,
+                summary.total,
+            ),
+        )
+        .wrap_err_with(|| format!("could not store last run of group '{group_name}'"))?;
+        Ok(previous)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -134,3 +215,35 @@ impl CacheKey<'_> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ce_shell::Analysis;
+
+    use super::{CheckoDb, RunSummary};
+
+    #[test]
+    fn last_run_survives_reopening() {
+        let dir = std::env::temp_dir().join(format!("checko-db-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("runs.db3");
+
+        let summary = |git_hash: &str, passed| RunSummary {
+            git_hash: Some(git_hash.to_string()),
+            passed,
+            total: 10,
+        };
+
+        let db = CheckoDb::open(&path).unwrap();
+        let previous = db.replace_last_run("g1", Analysis::Calculator, &summary("abc", 8));
+        assert_eq!(previous.unwrap(), None);
+        let previous = db.replace_last_run("g1", Analysis::Parity, &summary("abc", 3));
+        assert_eq!(previous.unwrap(), None);
+        drop(db);
+
+        let db = CheckoDb::open(&path).unwrap();
+        let previous = db.replace_last_run("g1", Analysis::Calculator, &summary("def", 5));
+        assert_eq!(previous.unwrap(), Some(summary("abc", 8)));
+    }
+}
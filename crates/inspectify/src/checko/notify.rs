@@ -0,0 +1,298 @@
+//! Notifications sent to configured hooks when the status of a group changes.
+
+use std::path::{Path, PathBuf};
+
+use ce_shell::Analysis;
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
+use tokio::io::AsyncWriteExt;
+
+use super::config::{GroupName, HookConfig, NotificationsConfig};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Notification {
+    pub group: GroupName,
+    pub analysis: Analysis,
+    pub git_hash: Option<String>,
+    pub event: NotificationEvent,
+    /// A human readable summary, which chat webhooks can display directly.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum NotificationEvent {
+    CompilationError,
+    Finished {
+        passed: usize,
+        total: usize,
+    },
+    PassRateDropped {
+        previous_git_hash: Option<String>,
+        previous_passed: usize,
+        passed: usize,
+        total: usize,
+    },
+}
+
+/// The outcome of testing a single git hash of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    pub git_hash: Option<String>,
+    pub passed: usize,
+    pub total: usize,
+}
+
+impl RunSummary {
+    /// Returns `true` if the fraction of passed programs is lower than in
+    /// `previous`.
+    pub fn dropped_from(&self, previous: &RunSummary) -> bool {
+        // NOTE: compare `a/b < c/d` as `a*d < c*b` to avoid floats
+        self.passed * previous.total < previous.passed * self.total
+    }
+}
+
+impl Notification {
+    pub fn new(
+        group: GroupName,
+        analysis: Analysis,
+        git_hash: Option<String>,
+        event: NotificationEvent,
+    ) -> Self {
+        let text = match &event {
+            NotificationEvent::CompilationError => {
+                format!("{group}: {analysis} failed to compile")
+            }
+            NotificationEvent::Finished { passed, total } => {
+                format!("{group}: {analysis} finished with {passed}/{total} passing")
+            }
+            NotificationEvent::PassRateDropped {
+                previous_passed,
+                passed,
+                total,
+                ..
+            } => format!(
+                "{group}: {analysis} dropped from {previous_passed} to {passed} passing (out of {total})"
+            ),
+        };
+        Self {
+            group,
+            analysis,
+            git_hash,
+            event,
+            text,
+        }
+    }
+}
+
+pub struct Notifier {
+    dir: PathBuf,
+    hooks: Vec<HookConfig>,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(dir: &Path, config: NotificationsConfig) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            hooks: config.hooks,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send the notification to all hooks interested in the group. Hooks are
+    /// run in the background, and failures are only logged.
+    pub fn notify(&self, notification: Notification) {
+        for hook in self
+            .hooks
+            .iter()
+            .filter(|h| h.includes(&notification.group))
+        {
+            let hook = hook.clone();
+            let notification = notification.clone();
+            let dir = self.dir.clone();
+            let client = self.client.clone();
+            tokio::spawn(async move {
+                if let Err(err) = send(&client, &dir, &hook, &notification).await {
+                    tracing::error!(?hook, ?err, "failed to send notification");
+                }
+            });
+        }
+    }
+
+    /// Notify about the results of a finished run, and about any drop in pass
+    /// rate since `previous`, if that was of another git hash.
+    pub fn finished(
+        &self,
+        group: GroupName,
+        analysis: Analysis,
+        summary: &RunSummary,
+        previous: Option<&RunSummary>,
+    ) {
+        self.notify(Notification::new(
+            group.clone(),
+            analysis,
+            summary.git_hash.clone(),
+            NotificationEvent::Finished {
+                passed: summary.passed,
+                total: summary.total,
+            },
+        ));
+        let dropped = previous
+            .filter(|previous| previous.git_hash != summary.git_hash)
+            .filter(|previous| summary.dropped_from(previous));
+        if let Some(previous) = dropped {
+            self.notify(Notification::new(
+                group,
+                analysis,
+                summary.git_hash.clone(),
+                NotificationEvent::PassRateDropped {
+                    previous_git_hash: previous.git_hash.clone(),
+                    previous_passed: previous.passed,
+                    passed: summary.passed,
+                    total: summary.total,
+                },
+            ));
+        }
+    }
+}
+
+#[tracing::instrument(skip(client, dir, notification), fields(text = notification.text))]
+async fn send(
+    client: &reqwest::Client,
+    dir: &Path,
+    hook: &HookConfig,
+    notification: &Notification,
+) -> Result<()> {
+    if let Some(url) = &hook.url {
+        client
+            .post(url.as_str())
+            .json(notification)
+            .send()
+            .await
+            .wrap_err_with(|| format!("could not post notification to '{url}'"))?
+            .error_for_status()?;
+    }
+
+    if let Some(command) = &hook.command {
+        let json = serde_json::to_vec(notification)?;
+        let mut child = tokio::process::Command::new(dir.join(command.as_str()))
+            .current_dir(dir)
+            .env("CHECKO_GROUP", notification.group.as_str())
+            .env("CHECKO_ANALYSIS", notification.analysis.code())
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .wrap_err_with(|| format!("could not run notification command '{command}'"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&json).await?;
+        }
+        let status = child.wait().await?;
+        if !status.success() {
+            bail!("notification command '{command}' failed: {status}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, time::Duration};
+
+    use axum::{Json, Router, extract::State, routing::post};
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// Starts a listener which forwards the bodies it receives, and returns a
+    /// notifier with a hook posting to it.
+    async fn listen() -> (Notifier, mpsc::UnboundedReceiver<serde_json::Value>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/",
+            post(
+                |State(tx): State<mpsc::UnboundedSender<serde_json::Value>>,
+                 Json(body): Json<serde_json::Value>| async move {
+                    tx.send(body).unwrap();
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app.with_state(tx)).await });
+
+        let config = serde_json::from_value(serde_json::json!({
+            "hooks": [{ "url": format!("http://{addr}/") }]
+        }))
+        .unwrap();
+        (Notifier::new(&std::env::temp_dir(), config), rx)
+    }
+
+    async fn received_types(
+        rx: &mut mpsc::UnboundedReceiver<serde_json::Value>,
+        count: usize,
+    ) -> BTreeSet<String> {
+        let mut types = BTreeSet::new();
+        for _ in 0..count {
+            let body = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+                .await
+                .expect("timed out waiting for notification")
+                .unwrap();
+            assert_eq!(body["group"], "group-1");
+            types.insert(body["event"]["type"].as_str().unwrap().to_string());
+        }
+        types
+    }
+
+    fn summary(git_hash: &str, passed: usize) -> RunSummary {
+        RunSummary {
+            git_hash: Some(git_hash.to_string()),
+            passed,
+            total: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn notifications_are_posted_to_hooks() {
+        let (notifier, mut rx) = listen().await;
+        let group: GroupName = serde_json::from_value(serde_json::json!("group-1")).unwrap();
+
+        notifier.notify(Notification::new(
+            group.clone(),
+            Analysis::Calculator,
+            Some("abc".to_string()),
+            NotificationEvent::CompilationError,
+        ));
+        assert_eq!(
+            received_types(&mut rx, 1).await,
+            BTreeSet::from(["CompilationError".to_string()])
+        );
+
+        notifier.finished(
+            group.clone(),
+            Analysis::Calculator,
+            &summary("def", 5),
+            Some(&summary("abc", 8)),
+        );
+        assert_eq!(
+            received_types(&mut rx, 2).await,
+            BTreeSet::from(["Finished".to_string(), "PassRateDropped".to_string()])
+        );
+
+        // NOTE: an improved pass rate only notifies about the finished run
+        notifier.finished(
+            group,
+            Analysis::Calculator,
+            &summary("ghi", 9),
+            Some(&summary("def", 5)),
+        );
+        assert_eq!(
+            received_types(&mut rx, 1).await,
+            BTreeSet::from(["Finished".to_string()])
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -3,14 +3,16 @@ use std::collections::HashMap;
 use ce_shell::{Analysis, Input};
 use driver::JobState;
 use indexmap::IndexMap;
-use itertools::{Either, Itertools};
+use itertools::Itertools;
 
 use super::{Checko, GroupStatus, config::GroupName};
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, serde::Serialize)]
 pub struct PublicAnalysis {
     analysis: Analysis,
-    programs: Vec<Option<Input>>,
+    /// The inputs of the shown programs. Hidden programs are only counted.
+    programs: Vec<Input>,
+    hidden_programs: usize,
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, serde::Serialize)]
@@ -24,7 +26,29 @@ pub struct PublicAnalysisResults {
     analysis: Analysis,
     status: GroupStatus,
     last_hash: Option<String>,
+    /// The results of the shown programs, in the order of
+    /// [`PublicAnalysis::programs`].
     results: Vec<PublicProgramResult>,
+    hidden_results: PublicHiddenResults,
+}
+
+/// Aggregated results of the hidden programs, which does not reveal which
+/// program had which result.
+#[derive(tapi::Tapi, Debug, Default, Clone, PartialEq, serde::Serialize)]
+pub struct PublicHiddenResults {
+    passed: usize,
+    failed: usize,
+    pending: usize,
+}
+
+impl PublicHiddenResults {
+    fn add(&mut self, state: JobState) {
+        match state {
+            JobState::Succeeded => self.passed += 1,
+            JobState::Queued | JobState::Running => self.pending += 1,
+            _ => self.failed += 1,
+        }
+    }
 }
 
 #[derive(tapi::Tapi, Debug, Clone, PartialEq, serde::Serialize)]
@@ -44,7 +68,12 @@ impl PublicState {
 
         let mut csv = String::new();
 
-        writeln!(csv, "Analysis,Group,{}", JobState::all().iter().format(",")).unwrap();
+        writeln!(
+            csv,
+            "Analysis,Group,{},Hidden passed,Hidden failed,Hidden pending",
+            JobState::all().iter().format(",")
+        )
+        .unwrap();
 
         for group in &self.groups {
             for analysis_result in &group.analysis_results {
@@ -57,15 +86,20 @@ impl PublicState {
                     *counts.entry(result.state).or_insert(0) += 1;
                 }
 
+                let hidden = &analysis_result.hidden_results;
+
                 writeln!(
                     csv,
-                    "{},{},{}",
+                    "{},{},{},{},{},{}",
                     analysis,
                     group,
                     JobState::all()
                         .iter()
                         .map(|state| counts.get(state).copied().unwrap_or(0))
                         .format(","),
+                    hidden.passed,
+                    hidden.failed,
+                    hidden.pending,
                 )
                 .unwrap();
             }
@@ -83,6 +117,21 @@ impl PublicState {
 //     GroupOrder(Vec<GroupName>),
 // }
 
+/// All configured programs of `analysis` with whether they are shown.
+fn analysis_programs(checko: &Checko, analysis: Analysis) -> Vec<(Input, bool)> {
+    checko
+        .programs_config()
+        .envs
+        .get(&analysis)
+        .map(|ps| {
+            ps.programs
+                .iter()
+                .map(|p| (analysis.input_from_str(&p.input).unwrap(), p.shown))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 async fn compute_public_groups(checko: &Checko) -> Vec<PublicGroup> {
     let mut groups = HashMap::<GroupName, PublicGroup>::new();
 
@@ -95,30 +144,26 @@ async fn compute_public_groups(checko: &Checko) -> Vec<PublicGroup> {
             });
 
         let gs_results = gs.results().await;
-        let results = checko
-            .programs_config
-            .inputs()
-            .flat_map(|(a, inputs)| {
-                if a != analysis {
-                    Either::Left(std::iter::empty())
-                } else {
-                    Either::Right(inputs.map(|input| {
-                        PublicProgramResult {
-                            state: gs_results
-                                .get(&input.hash())
-                                .cloned()
-                                .unwrap_or(JobState::Queued),
-                        }
-                    }))
-                }
-            })
-            .collect();
+        let mut results = Vec::new();
+        let mut hidden_results = PublicHiddenResults::default();
+        for (input, shown) in analysis_programs(checko, analysis) {
+            let state = gs_results
+                .get(&input.hash())
+                .cloned()
+                .unwrap_or(JobState::Queued);
+            if shown {
+                results.push(PublicProgramResult { state });
+            } else {
+                hidden_results.add(state);
+            }
+        }
 
         pg.analysis_results.push(PublicAnalysisResults {
             analysis,
             status: gs.status().await,
             last_hash: gs.latest_hash().await,
             results,
+            hidden_results,
         });
     }
 
@@ -135,11 +180,10 @@ pub async fn compute_public_state(checko: &Checko) -> PublicState {
             programs: ps
                 .programs
                 .iter()
-                .map(|p| {
-                    let input = analysis.input_from_str(&p.input).unwrap();
-                    Some(input)
-                })
+                .filter(|p| p.shown)
+                .map(|p| analysis.input_from_str(&p.input).unwrap())
                 .collect(),
+            hidden_programs: ps.programs.iter().filter(|p| !p.shown).count(),
         })
         .collect();
     let groups = compute_public_groups(checko)
@@ -149,8 +193,14 @@ pub async fn compute_public_state(checko: &Checko) -> PublicState {
             std::cmp::Reverse((
                 g.analysis_results
                     .iter()
-                    .flat_map(|a| a.results.iter().filter(|x| x.state == JobState::Succeeded))
-                    .count(),
+                    .map(|a| {
+                        a.results
+                            .iter()
+                            .filter(|x| x.state == JobState::Succeeded)
+                            .count()
+                            + a.hidden_results.passed
+                    })
+                    .sum::<usize>(),
                 g.analysis_results
                     .iter()
                     .flat_map(|a| a.results.iter().filter(|x| x.state == JobState::Warning))
@@ -165,3 +215,33 @@ pub async fn compute_public_state(checko: &Checko) -> PublicState {
         groups,
     }
 }
+
+/// The full results for teachers, with a row for every program of every group,
+/// including the hidden programs and their inputs.
+pub async fn compute_teacher_csv(checko: &Checko) -> String {
+    use std::fmt::Write;
+
+    let mut csv = String::new();
+
+    writeln!(csv, "Analysis,Group,Git hash,Program,Shown,State,Input").unwrap();
+
+    for (group_name, analysis, gs) in checko.group_states().await {
+        let gs_results = gs.results().await;
+        let last_hash = gs.latest_hash().await.unwrap_or_default();
+        for (input, shown) in analysis_programs(checko, analysis) {
+            let state = gs_results
+                .get(&input.hash())
+                .cloned()
+                .unwrap_or(JobState::Queued);
+            writeln!(
+                csv,
+                "{analysis},{group_name},{last_hash},{},{shown},{state},\"{}\"",
+                input.hash().hex(),
+                input.to_string().replace('"', "\"\""),
+            )
+            .unwrap();
+        }
+    }
+
+    csv
+}
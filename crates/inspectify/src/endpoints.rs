@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use axum::{Json, extract::State};
 use ce_core::ValidationResult;
@@ -19,6 +19,9 @@ pub struct AppState {
     pub driver: Option<driver::Driver<InspectifyJobMeta>>,
     pub checko: Option<Arc<checko::Checko>>,
     pub public_state: Arc<std::sync::RwLock<Option<PublicState>>>,
+    /// The token required by the checko admin endpoints. If absent, the admin
+    /// endpoints reject all requests.
+    pub admin_token: Option<Arc<str>>,
 }
 
 pub fn endpoints() -> tapi::endpoints::Endpoints<'static, AppState> {
@@ -28,6 +31,12 @@ pub fn endpoints() -> tapi::endpoints::Endpoints<'static, AppState> {
         &events::endpoint as E,
         &checko_csv::endpoint as E,
         &checko_public::endpoint as E,
+        &checko_admin_status::endpoint as E,
+        &checko_admin_rerun::endpoint as E,
+        &checko_admin_pause::endpoint as E,
+        &checko_admin_exclude::endpoint as E,
+        &checko_admin_export::endpoint as E,
+        &checko_admin_programs::endpoint as E,
         &jobs_cancel::endpoint as E,
         &exec_analysis::endpoint as E,
         &exec_reference::endpoint as E,
@@ -139,6 +148,69 @@ impl AppState {
             // .jobs(Some(25))
             .jobs(None)
     }
+    /// The hashes of the hidden checko programs, whose inputs must only be
+    /// sent to admins.
+    fn hidden_programs(&self) -> HashSet<Hash> {
+        match &self.checko {
+            Some(checko) => hidden_programs(&checko.programs_config()),
+            None => HashSet::new(),
+        }
+    }
+    /// The jobs which do not run a hidden program.
+    fn public_jobs(&self, hidden: &HashSet<Hash>) -> Vec<driver::Job<InspectifyJobMeta>> {
+        self.jobs()
+            .into_iter()
+            .filter(|job| is_public_job(job, hidden))
+            .collect()
+    }
+}
+
+fn is_public_job(job: &driver::Job<InspectifyJobMeta>, hidden: &HashSet<Hash>) -> bool {
+    match job.kind() {
+        driver::JobKind::Analysis(input) => !hidden.contains(&input.hash()),
+        _ => true,
+    }
+}
+
+/// The configured checko programs, leaving out the hidden ones unless
+/// `include_hidden`.
+fn checko_programs(
+    config: &checko::config::CanonicalProgramsConfig,
+    include_hidden: bool,
+) -> Vec<Program> {
+    config
+        .envs
+        .iter()
+        .flat_map(|(analysis, ps)| {
+            ps.programs
+                .iter()
+                .filter(move |p| include_hidden || p.shown)
+                .map(|p| {
+                    let input = analysis.input_from_str(&p.input).unwrap();
+                    let hash = input.hash();
+                    let hash_str = hash.hex();
+                    Program {
+                        hash,
+                        hash_str,
+                        input,
+                        shown: p.shown,
+                    }
+                })
+        })
+        .collect()
+}
+
+fn hidden_programs(config: &checko::config::CanonicalProgramsConfig) -> HashSet<Hash> {
+    config
+        .envs
+        .iter()
+        .flat_map(|(analysis, ps)| {
+            ps.programs
+                .iter()
+                .filter(|p| !p.shown)
+                .map(|p| analysis.input_from_str(&p.input).unwrap().hash())
+        })
+        .collect()
 }
 
 fn periodic_stream<T: Clone + Send + PartialEq + 'static, S: Send + 'static>(
@@ -189,15 +261,24 @@ pub struct Program {
     pub hash: Hash,
     pub hash_str: String,
     pub input: Input,
+    pub shown: bool,
 }
 
 async fn start_listening_on_job(
     state: AppState,
     tx: tokio::sync::mpsc::Sender<Result<Event, axum::BoxError>>,
+    hidden: &HashSet<Hash>,
     job: driver::Job<InspectifyJobMeta>,
 ) -> bool {
+    if !is_public_job(&job, hidden) {
+        return true;
+    }
     let event = Event::JobsChanged {
-        jobs: state.jobs().into_iter().map(|j| j.id()).collect(),
+        jobs: state
+            .public_jobs(hidden)
+            .into_iter()
+            .map(|j| j.id())
+            .collect(),
     };
     if tx.send(Ok(event)).await.is_err() {
         return false;
@@ -233,21 +314,30 @@ async fn events(State(state): State<AppState>) -> tapi::endpoints::Sse<Event> {
 
     let _ = tx.send(Ok(Event::Reset)).await;
 
+    // NOTE: the stream is public, so neither the programs nor the jobs of
+    // hidden checko programs are sent
+    let hidden = Arc::new(state.hidden_programs());
+
     tokio::spawn({
         let state = state.clone();
         let tx = tx.clone();
+        let hidden = hidden.clone();
         async move {
             tokio::time::sleep(Duration::from_millis(100)).await;
 
             let event = Event::JobsChanged {
-                jobs: state.jobs().into_iter().map(|j| j.id()).collect(),
+                jobs: state
+                    .public_jobs(&hidden)
+                    .into_iter()
+                    .map(|j| j.id())
+                    .collect(),
             };
             if tx.send(Ok(event)).await.is_err() {
                 return;
             }
 
-            for job in state.jobs() {
-                if !start_listening_on_job(state.clone(), tx.clone(), job).await {
+            for job in state.public_jobs(&hidden) {
+                if !start_listening_on_job(state.clone(), tx.clone(), &hidden, job).await {
                     break;
                 }
             }
@@ -263,7 +353,7 @@ async fn events(State(state): State<AppState>) -> tapi::endpoints::Sse<Event> {
                 match event {
                     HubEvent::JobAdded(id) => {
                         let job = state.hub.get_job(id).unwrap();
-                        if !start_listening_on_job(state.clone(), tx.clone(), job).await {
+                        if !start_listening_on_job(state.clone(), tx.clone(), &hidden, job).await {
                             break;
                         }
                     }
@@ -321,23 +411,7 @@ async fn events(State(state): State<AppState>) -> tapi::endpoints::Sse<Event> {
                 }))
                 .await
                 .unwrap();
-                let programs = checko
-                    .programs_config()
-                    .envs
-                    .iter()
-                    .flat_map(|(analysis, ps)| {
-                        ps.programs.iter().map(|p| {
-                            let input = analysis.input_from_str(&p.input).unwrap();
-                            let hash = input.hash();
-                            let hash_str = hash.hex();
-                            Program {
-                                hash,
-                                hash_str,
-                                input,
-                            }
-                        })
-                    })
-                    .collect();
+                let programs = checko_programs(&checko.programs_config(), false);
                 let event = Event::ProgramsConfig { programs };
                 let _ = tx.send(Ok(event)).await;
             }
@@ -371,16 +445,23 @@ struct ReferenceExecution {
     meta: ce_shell::Meta,
     output: Option<ce_shell::Output>,
     error: Option<String>,
+    diagnostics: Vec<ce_core::InputDiagnostic>,
 }
 
 #[tapi::tapi(path = "/reference", method = Post)]
 async fn exec_reference(Json(input): Json<ce_shell::Input>) -> Json<ReferenceExecution> {
     let output = input.reference_output();
     let error = output.as_ref().err().map(|e| e.to_string());
+    let diagnostics = output
+        .as_ref()
+        .err()
+        .map(|e| e.diagnostics())
+        .unwrap_or_default();
     Json(ReferenceExecution {
         meta: input.meta(),
         output: output.ok(),
         error,
+        diagnostics,
     })
 }
 
@@ -456,3 +537,404 @@ async fn checko_csv(State(state): State<AppState>) -> String {
         String::new()
     }
 }
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, serde::Serialize)]
+struct AdminStatus {
+    paused: bool,
+    excluded: Vec<GroupName>,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "type", content = "value")]
+enum AdminResponse {
+    Ok(AdminStatus),
+    Error { message: String },
+}
+
+/// Why an admin request was not carried out, sent as the status and text of
+/// the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdminError {
+    Unauthorized,
+    NotRunning,
+}
+
+impl axum::response::IntoResponse for AdminError {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::StatusCode;
+
+        match self {
+            AdminError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "invalid admin token").into_response()
+            }
+            AdminError::NotRunning => {
+                (StatusCode::SERVICE_UNAVAILABLE, "checko is not running").into_response()
+            }
+        }
+    }
+}
+
+#[derive(tapi::Tapi, Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AdminTokenParams {
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AdminRerunParams {
+    #[serde(default)]
+    token: String,
+    group: GroupName,
+    analysis: Analysis,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AdminPauseParams {
+    #[serde(default)]
+    token: String,
+    paused: bool,
+}
+
+#[derive(tapi::Tapi, Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AdminExcludeParams {
+    #[serde(default)]
+    token: String,
+    group: GroupName,
+    excluded: bool,
+}
+
+impl AppState {
+    /// Returns checko if `token` matches the configured admin token.
+    fn admin_checko(&self, token: &str) -> Result<&checko::Checko, AdminError> {
+        use subtle::ConstantTimeEq;
+
+        // NOTE: the comparison takes the same time regardless of where the
+        // tokens differ, so the token cannot be guessed one byte at a time
+        let authorized = match self.admin_token.as_deref() {
+            Some(admin_token) if !admin_token.is_empty() => {
+                bool::from(admin_token.as_bytes().ct_eq(token.as_bytes()))
+            }
+            _ => false,
+        };
+        if !authorized {
+            tracing::warn!("rejected checko admin request");
+            return Err(AdminError::Unauthorized);
+        }
+        self.checko.as_deref().ok_or(AdminError::NotRunning)
+    }
+
+    /// Runs `f` on checko if `token` matches the configured admin token,
+    /// responding with the resulting admin status.
+    async fn checko_admin<F>(&self, token: &str, f: F) -> Result<Json<AdminResponse>, AdminError>
+    where
+        F: AsyncFnOnce(&checko::Checko) -> color_eyre::Result<()>,
+    {
+        let checko = self.admin_checko(token)?;
+        let result = match f(checko).await {
+            Ok(()) => checko.excluded_groups(),
+            Err(err) => Err(err),
+        };
+        Ok(Json(match result {
+            Ok(excluded) => AdminResponse::Ok(AdminStatus {
+                paused: checko.is_paused(),
+                excluded,
+            }),
+            Err(err) => AdminResponse::Error {
+                message: err.to_string(),
+            },
+        }))
+    }
+}
+
+#[tapi::tapi(path = "/checko-admin/status", method = Post)]
+async fn checko_admin_status(
+    State(state): State<AppState>,
+    Json(params): Json<AdminTokenParams>,
+) -> Result<Json<AdminResponse>, AdminError> {
+    state.checko_admin(&params.token, async |_| Ok(())).await
+}
+
+#[tapi::tapi(path = "/checko-admin/rerun", method = Post)]
+async fn checko_admin_rerun(
+    State(state): State<AppState>,
+    Json(params): Json<AdminRerunParams>,
+) -> Result<Json<AdminResponse>, AdminError> {
+    state
+        .checko_admin(&params.token, async |checko| {
+            tracing::info!(group=?params.group, analysis=?params.analysis, "rerun requested");
+            checko.request_rerun(&params.group, params.analysis).await
+        })
+        .await
+}
+
+#[tapi::tapi(path = "/checko-admin/pause", method = Post)]
+async fn checko_admin_pause(
+    State(state): State<AppState>,
+    Json(params): Json<AdminPauseParams>,
+) -> Result<Json<AdminResponse>, AdminError> {
+    state
+        .checko_admin(&params.token, async |checko| {
+            tracing::info!(paused = params.paused, "pause requested");
+            checko.set_paused(params.paused);
+            Ok(())
+        })
+        .await
+}
+
+#[tapi::tapi(path = "/checko-admin/exclude", method = Post)]
+async fn checko_admin_exclude(
+    State(state): State<AppState>,
+    Json(params): Json<AdminExcludeParams>,
+) -> Result<Json<AdminResponse>, AdminError> {
+    state
+        .checko_admin(&params.token, async |checko| {
+            tracing::info!(group=?params.group, excluded = params.excluded, "exclusion requested");
+            checko.set_excluded(&params.group, params.excluded)
+        })
+        .await
+}
+
+/// The teacher export of all results, including hidden programs.
+#[tapi::tapi(path = "/checko-admin/export", method = Post)]
+async fn checko_admin_export(
+    State(state): State<AppState>,
+    Json(params): Json<AdminTokenParams>,
+) -> Result<Json<String>, AdminError> {
+    let checko = state.admin_checko(&params.token)?;
+    Ok(Json(checko::scoreboard::compute_teacher_csv(checko).await))
+}
+
+/// All configured programs, including the hidden ones.
+#[tapi::tapi(path = "/checko-admin/programs", method = Post)]
+async fn checko_admin_programs(
+    State(state): State<AppState>,
+    Json(params): Json<AdminTokenParams>,
+) -> Result<Json<Vec<Program>>, AdminError> {
+    let checko = state.admin_checko(&params.token)?;
+    Ok(Json(checko_programs(&checko.programs_config(), true)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+    use ce_shell::Analysis;
+
+    use super::{
+        AdminError, AdminExcludeParams, AdminPauseParams, AdminRerunParams, AdminResponse,
+        AdminStatus, AdminTokenParams, AppState, Event, checko_admin_exclude, checko_admin_export,
+        checko_admin_pause, checko_admin_programs, checko_admin_rerun, checko_admin_status,
+        checko_programs, hidden_programs,
+    };
+    use crate::checko::{
+        Checko,
+        config::{
+            CanonicalProgramConfig, CanonicalProgramsConfig, CanonicalProgramsEnvConfig, GroupName,
+        },
+    };
+
+    #[test]
+    fn public_programs_leave_out_hidden_inputs() {
+        let program = |src: &str, shown| CanonicalProgramConfig {
+            input: serde_json::json!({ "expression": src }).to_string(),
+            shown,
+        };
+        let config = CanonicalProgramsConfig {
+            envs: [(
+                Analysis::Calculator,
+                CanonicalProgramsEnvConfig {
+                    programs: vec![program("1 + 2", true), program("31337 * 4", false)],
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+
+        let event = Event::ProgramsConfig {
+            programs: checko_programs(&config, false),
+        };
+        let payload = serde_json::to_string(&event).unwrap();
+        assert!(payload.contains("1 + 2"), "{payload}");
+        assert!(!payload.contains("31337"), "{payload}");
+
+        let all = checko_programs(&config, true);
+        assert_eq!(all.len(), 2);
+        assert_eq!(
+            hidden_programs(&config),
+            [all[1].hash].into_iter().collect()
+        );
+    }
+
+    /// A checko directory with a single group, which is removed up front so
+    /// that tests start from an empty database.
+    fn checko_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("checko-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("groups.toml"), "[[groups]]\nname = \"g1\"\n").unwrap();
+        std::fs::write(
+            dir.join("programs.toml"),
+            "[[envs.Calculator]]\ninput = '{\"expression\":\"1 + 2\"}'\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn admin_state(checko: Option<Checko>) -> AppState {
+        AppState {
+            hub: driver::Hub::new().unwrap(),
+            driver: None,
+            checko: checko.map(Arc::new),
+            public_state: Default::default(),
+            admin_token: Some(Arc::from("secret")),
+        }
+    }
+
+    fn open_checko(dir: &std::path::Path) -> Checko {
+        Checko::open(driver::Hub::new().unwrap(), dir).unwrap()
+    }
+
+    fn group(name: &str) -> GroupName {
+        serde_json::from_value(serde_json::json!(name)).unwrap()
+    }
+
+    fn token(token: &str) -> Json<AdminTokenParams> {
+        Json(AdminTokenParams {
+            token: token.to_string(),
+        })
+    }
+
+    fn status<T: IntoResponse>(response: T) -> StatusCode {
+        response.into_response().status()
+    }
+
+    #[tokio::test]
+    async fn admin_requests_require_the_token() {
+        let dir = checko_dir("admin-token");
+        let state = admin_state(Some(open_checko(&dir)));
+
+        let wrong = checko_admin_status(State(state.clone()), token("guess")).await;
+        assert!(matches!(wrong, Err(AdminError::Unauthorized)));
+        assert_eq!(status(wrong), StatusCode::UNAUTHORIZED);
+
+        let missing: AdminTokenParams = serde_json::from_str("{}").unwrap();
+        let missing = checko_admin_status(State(state.clone()), Json(missing)).await;
+        assert_eq!(status(missing), StatusCode::UNAUTHORIZED);
+
+        let export = checko_admin_export(State(state.clone()), token("guess")).await;
+        assert_eq!(status(export), StatusCode::UNAUTHORIZED);
+        let programs = checko_admin_programs(State(state.clone()), token("")).await;
+        assert_eq!(status(programs), StatusCode::UNAUTHORIZED);
+
+        let programs = checko_admin_programs(State(state.clone()), token("secret")).await;
+        assert_eq!(programs.unwrap().0.len(), 1);
+
+        // NOTE: without a configured token, even an empty token is rejected
+        let state = AppState {
+            admin_token: None,
+            ..state
+        };
+        let open = checko_admin_status(State(state), token("")).await;
+        assert_eq!(status(open), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn admin_requests_without_checko_are_unavailable() {
+        let state = admin_state(None);
+
+        let export = checko_admin_export(State(state.clone()), token("secret")).await;
+        assert_eq!(status(export), StatusCode::SERVICE_UNAVAILABLE);
+        let programs = checko_admin_programs(State(state.clone()), token("secret")).await;
+        assert_eq!(status(programs), StatusCode::SERVICE_UNAVAILABLE);
+        let export = checko_admin_export(State(state), token("guess")).await;
+        assert_eq!(status(export), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rerun_of_unknown_group_is_an_error() {
+        let dir = checko_dir("admin-rerun");
+        let state = admin_state(Some(open_checko(&dir)));
+
+        let rerun = |group| {
+            checko_admin_rerun(
+                State(state.clone()),
+                Json(AdminRerunParams {
+                    token: "secret".to_string(),
+                    group,
+                    analysis: Analysis::Calculator,
+                }),
+            )
+        };
+        match rerun(group("g2")).await.unwrap().0 {
+            AdminResponse::Error { message } => {
+                assert!(message.contains("unknown group"), "{message}")
+            }
+            response => panic!("expected an error, got {response:?}"),
+        }
+        assert!(matches!(
+            rerun(group("g1")).await.unwrap().0,
+            AdminResponse::Ok(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume() {
+        let dir = checko_dir("admin-pause");
+        let state = admin_state(Some(open_checko(&dir)));
+
+        for paused in [true, false] {
+            let response = checko_admin_pause(
+                State(state.clone()),
+                Json(AdminPauseParams {
+                    token: "secret".to_string(),
+                    paused,
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                response.0,
+                AdminResponse::Ok(AdminStatus {
+                    paused,
+                    excluded: vec![],
+                })
+            );
+            assert_eq!(state.checko.as_ref().unwrap().is_paused(), paused);
+        }
+    }
+
+    #[tokio::test]
+    async fn exclusion_survives_restarts() {
+        let dir = checko_dir("admin-exclude");
+        let exclude = async |excluded| {
+            let state = admin_state(Some(open_checko(&dir)));
+            checko_admin_exclude(
+                State(state),
+                Json(AdminExcludeParams {
+                    token: "secret".to_string(),
+                    group: group("g1"),
+                    excluded,
+                }),
+            )
+            .await
+            .unwrap()
+            .0
+        };
+
+        assert_eq!(
+            exclude(true).await,
+            AdminResponse::Ok(AdminStatus {
+                paused: false,
+                excluded: vec![group("g1")],
+            })
+        );
+        assert_eq!(
+            open_checko(&dir).excluded_groups().unwrap(),
+            vec![group("g1")]
+        );
+
+        exclude(false).await;
+        assert_eq!(open_checko(&dir).excluded_groups().unwrap(), vec![]);
+    }
+}
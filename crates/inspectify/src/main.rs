@@ -65,6 +65,11 @@ struct Cli {
     /// The path to the checko SQLite database
     #[clap(long)]
     checko: Option<PathBuf>,
+    /// The token required to use the checko admin endpoints
+    ///
+    /// If not given, the admin endpoints are disabled.
+    #[clap(long, env = "CHECKO_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
 }
 
 async fn run() -> color_eyre::Result<()> {
@@ -122,6 +127,7 @@ async fn run() -> color_eyre::Result<()> {
             driver,
             checko,
             public_state,
+            admin_token: cli.admin_token.map(Arc::from),
         });
     let app = Router::new().nest("/api", api).fallback(static_dir);
 